@@ -0,0 +1,135 @@
+use async_std::{path::PathBuf, task::block_on};
+use bve::load::mesh::{load_mesh_from_file_cached, DiskMeshCache};
+use log::{error, info};
+use std::{convert::TryFrom, process::exit};
+use walkdir::WalkDir;
+
+#[derive(Clone)]
+pub struct Arguments {
+    pub help: bool,
+    pub cache_dir: PathBuf,
+    pub source_dir: Option<PathBuf>,
+    pub clear: bool,
+
+    pub log_output: Option<PathBuf>,
+    pub quiet: bool,
+    pub debug: bool,
+    pub trace: bool,
+}
+
+const HELP_MESSAGE: &str = r#"cargo run --bin bve-mesh-cache -- [options] <cache> [source]
+BVE-Reborn mesh cache tool -- prewarms or clears an on disk mesh cache
+
+General Options:
+  <cache>     Directory of the mesh cache
+  [source]    Directory to search for .b3d, .csv, and .x objects to add to
+                the cache. Objects already in the cache are skipped. Objects
+                with warnings or errors are cached along with them.
+  -c,--clear  Remove every entry from the cache before adding objects
+  -h,--help   Print this message
+
+Logging Options:
+  --log        Send all messages to a file. Errors and warnings
+                 will also be sent to stderr as normal.
+  -q,--quiet   Disable info level log messages
+  -v,--debug   Enable debug trace level log messages
+  -vv,--trace  Enable trace level log messages
+"#;
+
+impl Arguments {
+    #[allow(clippy::redundant_closure)] // PathBuf::try_from doesn't work
+    pub fn create(mut args: pico_args::Arguments) -> Result<Self, String> {
+        let o = Self {
+            help: args.contains(["-h", "--help"]),
+            clear: args.contains(["-c", "--clear"]),
+
+            log_output: args
+                .opt_value_from_os_str("--log", |os| PathBuf::try_from(os))
+                .map_err(|e| e.to_string())?,
+            quiet: args.contains(["-q", "--quiet"]),
+            debug: args.contains(["-v", "--debug"]),
+            trace: args.contains(["-vv", "--trace"]),
+
+            cache_dir: args
+                .free_from_os_str(|os| PathBuf::try_from(os))
+                .map_err(|e| e.to_string())?
+                .ok_or_else(|| String::from("No cache directory provided"))?,
+            source_dir: args
+                .free_from_os_str(|os| PathBuf::try_from(os))
+                .map_err(|e| e.to_string())?,
+        };
+
+        args.finish().map_err(|e| e.to_string())?;
+
+        Ok(o)
+    }
+
+    #[must_use]
+    pub fn from_args() -> Self {
+        let o = Self::create(pico_args::Arguments::from_env());
+
+        match o {
+            Ok(Arguments { help: true, .. }) => {
+                println!("{}", HELP_MESSAGE);
+                exit(0);
+            }
+            Err(e) => {
+                println!("Error parsing args: {}\n{}", e, HELP_MESSAGE);
+                exit(1);
+            }
+            Ok(o) => o,
+        }
+    }
+}
+
+/// Obj files are never cached, so aren't included.
+fn is_cacheable_object(path: &std::path::Path) -> bool {
+    let ext = path.extension().map(|e| e.to_string_lossy().to_lowercase());
+    matches!(ext.as_deref(), Some("b3d") | Some("csv") | Some("x"))
+}
+
+fn main() {
+    let options: Arguments = Arguments::from_args();
+
+    bve::log::enable_logger(&options.log_output, options.quiet, options.debug, options.trace);
+
+    let cache = DiskMeshCache::new(options.cache_dir.clone());
+
+    if options.clear {
+        match block_on(cache.clear()) {
+            Ok(removed) => info!("Removed {} entries from {}", removed, cache.directory().display()),
+            Err(err) => {
+                error!("Could not clear {}: {}", cache.directory().display(), err);
+                exit(1);
+            }
+        }
+    }
+
+    let source_dir: &std::path::Path = match &options.source_dir {
+        Some(source_dir) => source_dir.as_ref(),
+        None => return,
+    };
+
+    let mut loaded = 0_usize;
+    let mut failed = 0_usize;
+    for entry in WalkDir::new(source_dir).follow_links(true).into_iter().flatten() {
+        if entry.file_type().is_dir() || !is_cacheable_object(entry.path()) {
+            continue;
+        }
+
+        // Meshes with warnings or errors are cached along with their problems
+        let source = PathBuf::from(entry.path());
+        match block_on(load_mesh_from_file_cached(&source, &cache)) {
+            Ok(_) => loaded += 1,
+            Err(err) => {
+                error!("{}", err);
+                failed += 1;
+            }
+        }
+    }
+
+    info!("Loaded: {}, Failed: {}", loaded, failed);
+    if failed != 0 {
+        exit(1);
+    }
+}
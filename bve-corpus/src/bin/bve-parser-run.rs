@@ -18,6 +18,7 @@ use bve::{
     },
 };
 use log::{error, info, warn};
+use serde::Serialize;
 use std::{convert::TryFrom, io::stdout, process::exit, str::FromStr, time::Instant};
 
 #[derive(Debug, Copy, Clone, PartialEq)]
@@ -61,6 +62,7 @@ pub struct Arguments {
     pub source_file: PathBuf,
     pub errors: bool,
     pub print_result: bool,
+    pub json: bool,
     pub file_type: FileType,
 
     pub log_output: Option<PathBuf>,
@@ -90,6 +92,8 @@ General Options:
 Printing Options:
   -e,--errors  Print all warnings/errors
   -p,--print   Print parser output
+  -j,--json    Print parser output as JSON. Only supported
+                 for route-csv.
                  
 Logging Options:
   --log        Send all messages to a file. Errors and warnings
@@ -106,6 +110,7 @@ impl Arguments {
             help: args.contains(["-h", "--help"]),
             errors: args.contains(["-e", "--errors"]),
             print_result: args.contains(["-p", "--print"]),
+            json: args.contains(["-j", "--json"]),
 
             log_output: args
                 .opt_value_from_os_str("--log", |os| PathBuf::try_from(os))
@@ -148,7 +153,7 @@ impl Arguments {
     }
 }
 
-async fn parse_file<T: FileAwareFileParser>(source: &Path, options: &Arguments) {
+async fn run_parser<T: FileAwareFileParser>(source: &Path, options: &Arguments) -> T::Output {
     let contents = read_convert_utf8(source).await.expect("Must be able to read file");
 
     let start = Instant::now();
@@ -193,6 +198,23 @@ async fn parse_file<T: FileAwareFileParser>(source: &Path, options: &Arguments)
     } else {
         info!("Errors: {}", errors.len());
     }
+
+    output
+}
+
+async fn parse_file<T: FileAwareFileParser>(source: &Path, options: &Arguments) {
+    run_parser::<T>(source, options).await;
+}
+
+async fn parse_file_json<T>(source: &Path, options: &Arguments)
+where
+    T: FileAwareFileParser,
+    T::Output: Serialize,
+{
+    let output = run_parser::<T>(source, options).await;
+
+    serde_json::to_writer_pretty(&mut stdout().lock(), &output).expect("Must be able to write to stdout");
+    println!();
 }
 
 fn main() {
@@ -200,6 +222,17 @@ fn main() {
 
     bve::log::enable_logger(&options.log_output, options.quiet, options.debug, options.trace);
 
+    if options.json {
+        match options.file_type {
+            FileType::RouteCsv => block_on(parse_file_json::<ParsedRoute>(&options.source_file, &options)),
+            file_type => {
+                error!("JSON output is not supported for {:?}", file_type);
+                exit(1);
+            }
+        }
+        return;
+    }
+
     match options.file_type {
         FileType::AtsCfg => block_on(parse_file::<ParsedAtsConfig>(&options.source_file, &options)),
         FileType::B3D => block_on(parse_file::<ParsedStaticObjectB3D>(&options.source_file, &options)),
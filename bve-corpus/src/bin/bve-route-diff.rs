@@ -0,0 +1,144 @@
+use async_std::{
+    path::{Path, PathBuf},
+    task::block_on,
+};
+use bve::{
+    filesystem::read_convert_utf8,
+    parse::{
+        route::{diff::diff_routes, ParsedRoute},
+        FileAwareFileParser, ParserResult, UserError,
+    },
+};
+use log::{error, info, warn};
+use std::{convert::TryFrom, io::stdout, process::exit};
+
+#[derive(Clone)]
+pub struct Arguments {
+    pub help: bool,
+    pub old_file: PathBuf,
+    pub new_file: PathBuf,
+    pub base: Option<PathBuf>,
+    pub errors: bool,
+    pub json: bool,
+
+    pub log_output: Option<PathBuf>,
+    pub quiet: bool,
+    pub debug: bool,
+    pub trace: bool,
+}
+
+const HELP_MESSAGE: &str = r#"cargo run --bin bve-route-diff -- [options] <old> <new>
+BVE-Reborn route differ -- shows semantic changes between two versions of a route
+
+General Options:
+  <old>      Path to the original route file
+  <new>      Path to the updated route file
+  -b,--base  Extra folder to look for included files in. The folder
+               of each route file is always searched.
+  -h,--help  Print this message
+
+Printing Options:
+  -e,--errors  Print all parser errors for both files
+  -j,--json    Print the changes as JSON
+
+Logging Options:
+  --log        Send all messages to a file. Errors and warnings
+                 will also be sent to stderr as normal.
+  -q,--quiet   Disable info level log messages
+  -v,--debug   Enable debug trace level log messages
+  -vv,--trace  Enable trace level log messages
+"#;
+
+impl Arguments {
+    #[allow(clippy::redundant_closure)] // PathBuf::try_from doesn't work
+    pub fn create(mut args: pico_args::Arguments) -> Result<Self, String> {
+        let o = Self {
+            help: args.contains(["-h", "--help"]),
+            errors: args.contains(["-e", "--errors"]),
+            json: args.contains(["-j", "--json"]),
+            base: args
+                .opt_value_from_os_str(["-b", "--base"], |os| PathBuf::try_from(os))
+                .map_err(|e| e.to_string())?,
+
+            log_output: args
+                .opt_value_from_os_str("--log", |os| PathBuf::try_from(os))
+                .map_err(|e| e.to_string())?,
+            quiet: args.contains(["-q", "--quiet"]),
+            debug: args.contains(["-v", "--debug"]),
+            trace: args.contains(["-vv", "--trace"]),
+
+            old_file: args
+                .free_from_os_str(|os| PathBuf::try_from(os))
+                .map_err(|e| e.to_string())?
+                .ok_or_else(|| String::from("No original path provided"))?,
+            new_file: args
+                .free_from_os_str(|os| PathBuf::try_from(os))
+                .map_err(|e| e.to_string())?
+                .ok_or_else(|| String::from("No updated path provided"))?,
+        };
+
+        args.finish().map_err(|e| e.to_string())?;
+
+        Ok(o)
+    }
+
+    #[must_use]
+    pub fn from_args() -> Self {
+        let o = Self::create(pico_args::Arguments::from_env());
+
+        match o {
+            Ok(Arguments { help: true, .. }) => {
+                println!("{}", HELP_MESSAGE);
+                exit(0);
+            }
+            Err(e) => {
+                println!("Error parsing args: {}\n{}", e, HELP_MESSAGE);
+                exit(1);
+            }
+            Ok(o) => o,
+        }
+    }
+}
+
+async fn parse_route(source: &Path, options: &Arguments) -> ParsedRoute {
+    let contents = read_convert_utf8(source).await.expect("Must be able to read file");
+
+    let bases: Vec<&Path> = options.base.as_deref().into_iter().chain(source.parent()).collect();
+    let ParserResult { output, errors, .. } =
+        ParsedRoute::file_aware_parse_from(bases.iter().copied(), &source.to_string_lossy(), &contents).await;
+
+    if options.errors && !errors.is_empty() {
+        warn!("Errors in {}:", source.display());
+        for e in errors {
+            let e = e.to_data();
+            error!("\t{:?}", e.description_english);
+        }
+    } else {
+        info!("Errors in {}: {}", source.display(), errors.len());
+    }
+
+    output
+}
+
+fn main() {
+    let options: Arguments = Arguments::from_args();
+
+    bve::log::enable_logger(&options.log_output, options.quiet, options.debug, options.trace);
+
+    let (old, new) = block_on(async {
+        (
+            parse_route(&options.old_file, &options).await,
+            parse_route(&options.new_file, &options).await,
+        )
+    });
+
+    let diff = diff_routes(&old, &new);
+    info!("Changes: {}", diff.changes.len());
+
+    if options.json {
+        serde_json::to_writer_pretty(&mut stdout().lock(), &diff).expect("Must be able to write to stdout");
+        println!();
+    } else {
+        print!("{}", diff);
+    }
+}
@@ -163,7 +163,7 @@ fn program_main(options: Options) {
 
     let worker_thread_count = options.jobs.unwrap_or_else(num_cpus::get);
     let worker_threads: Vec<_> = (0..worker_thread_count)
-        .map(|_| create_worker_thread(&file_source, &result_sink, &shared))
+        .map(|_| create_worker_thread(&file_source, &result_sink, &shared, &options.route_options))
         .collect();
 
     let logger_thread = { std::thread::spawn(move || logger::receive_results(&options, result_source)) };
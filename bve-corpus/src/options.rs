@@ -1,3 +1,4 @@
+use bve::parse::route::RouteParseOptions;
 use pico_args::Arguments;
 use std::{convert::TryFrom, path::PathBuf, process::exit, str::FromStr};
 
@@ -44,6 +45,7 @@ pub struct Options {
     pub output: Option<PathBuf>,
     pub jobs: Option<usize>,
     pub file_types: Option<FileType>,
+    pub route_options: RouteParseOptions,
 
     pub log_output: Option<PathBuf>,
     pub quiet: bool,
@@ -71,6 +73,13 @@ General Options:
                  panel2[.cfg]
                  sound[.cfg]
                  
+Route Options:
+  --seed           Seed for $Rnd and random $Include choices
+  --no-includes    Remove $Include directives without loading them
+  --strict         Stop parsing a route after its first parse error.
+                     Preprocessing errors don't stop parsing.
+  --bve-ts-hacks   Enable BVE-TS compatibility hacks on every route
+                 
 Logging Options:
   --log        Send all messages to a file. Errors and warnings
                  will also be sent to stderr as normal.
@@ -82,6 +91,7 @@ Logging Options:
 impl Options {
     #[allow(clippy::redundant_closure)] // PathBuf::try_from doesn't work
     fn create(mut args: Arguments) -> Result<Self, String> {
+        let defaults = RouteParseOptions::default();
         let o = Self {
             help: args.contains(["-h", "--help"]),
             output: args
@@ -89,6 +99,16 @@ impl Options {
                 .map_err(|e| e.to_string())?,
             jobs: args.opt_value_from_str(["-j", "--jobs"]).map_err(|e| e.to_string())?,
             file_types: args.opt_value_from_str(["-f", "--file"]).map_err(|e| e.to_string())?,
+            route_options: RouteParseOptions {
+                rng_seed: args
+                    .opt_value_from_str("--seed")
+                    .map_err(|e| e.to_string())?
+                    .unwrap_or(defaults.rng_seed),
+                follow_includes: !args.contains("--no-includes"),
+                strict: args.contains("--strict"),
+                bve_ts_hacks: args.contains("--bve-ts-hacks"),
+                ..defaults
+            },
 
             log_output: args
                 .opt_value_from_os_str("--log", |os| PathBuf::try_from(os))
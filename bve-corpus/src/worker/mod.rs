@@ -13,7 +13,7 @@ use bve::{
         mesh::{ParsedStaticObjectB3D, ParsedStaticObjectCSV},
         panel1_cfg::ParsedPanel1Cfg,
         panel2_cfg::ParsedPanel2Cfg,
-        route::ParsedRoute,
+        route::{ParsedRoute, RouteParseOptions},
         sound_cfg::ParsedSoundCfg,
         train_dat::ParsedTrainDat,
         FileAwareFileParser, ParserResult, UserError,
@@ -42,6 +42,7 @@ pub fn create_worker_thread(
     job_source: &Receiver<File>,
     result_sink: &Sender<FileResult>,
     shared: &Arc<SharedData>,
+    route_options: &RouteParseOptions,
 ) -> WorkerThread {
     let last_respond: Arc<AtomicCell<Instant>> = Arc::new(AtomicCell::new(Instant::now()));
     let last_file = Arc::new(Mutex::new(PathBuf::new()));
@@ -51,7 +52,17 @@ pub fn create_worker_thread(
         let shared = Arc::clone(shared);
         let last_respond = Arc::clone(&last_respond);
         let last_file = Arc::clone(&last_file);
-        std::thread::spawn(move || processing_loop(&job_source, &result_sink, &shared, &last_respond, &last_file))
+        let route_options = route_options.clone();
+        std::thread::spawn(move || {
+            processing_loop(
+                &job_source,
+                &result_sink,
+                &shared,
+                &route_options,
+                &last_respond,
+                &last_file,
+            )
+        })
     };
     WorkerThread {
         handle,
@@ -75,6 +86,23 @@ fn run_parser<P: FileAwareFileParser>(path: &str, input: &str, counter: &AtomicU
 
     counter.fetch_add(1, Ordering::AcqRel);
 
+    parse_result(&warnings, &errors)
+}
+
+fn run_route_parser(path: &str, input: &str, options: &RouteParseOptions, counter: &AtomicU64) -> ParseResult {
+    let (ParserResult { warnings, errors, .. }, _) = block_on(ParsedRoute::parse_with_options(
+        std::iter::empty::<&Path>(),
+        path,
+        input,
+        options,
+    ));
+
+    counter.fetch_add(1, Ordering::AcqRel);
+
+    parse_result(&warnings, &errors)
+}
+
+fn parse_result<W: UserError, E: UserError>(warnings: &[W], errors: &[E]) -> ParseResult {
     if warnings.is_empty() && errors.is_empty() {
         ParseResult::Success
     } else {
@@ -89,6 +117,7 @@ fn processing_loop(
     job_source: &Receiver<File>,
     result_sink: &Sender<FileResult>,
     shared: &SharedData,
+    route_options: &RouteParseOptions,
     last_respond: &AtomicCell<Instant>,
     last_file: &Mutex<PathBuf>,
 ) {
@@ -105,6 +134,8 @@ fn processing_loop(
         // File reading isn't part of the operation.
         let file_contents = read_from_file(&file_ref.path);
         let folder = file_ref.path.parent().unwrap().to_string_lossy();
+        // Routes resolve includes relative to their own folder
+        let route_path = file_ref.path.to_string_lossy();
         // Say that we're still alive
         last_respond.store(Instant::now());
 
@@ -127,7 +158,9 @@ fn processing_loop(
             FileKind::Panel1Cfg => run_parser::<ParsedPanel1Cfg>(&folder, &file_contents, &shared.panel1_cfg.finished),
             FileKind::Panel2Cfg => run_parser::<ParsedPanel2Cfg>(&folder, &file_contents, &shared.panel2_cfg.finished),
             FileKind::SoundCfg => run_parser::<ParsedSoundCfg>(&folder, &file_contents, &shared.sound_cfg.finished),
-            FileKind::RouteCsv => run_parser::<ParsedRoute>(&folder, &file_contents, &shared.route_csv.finished),
+            FileKind::RouteCsv => {
+                run_route_parser(&route_path, &file_contents, route_options, &shared.route_csv.finished)
+            }
             _ => ParseResult::Success,
         });
         USE_DEFAULT_PANIC_HANLDER.with(|v| *v.borrow_mut() = true);
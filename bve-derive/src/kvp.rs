@@ -59,6 +59,14 @@ struct Field {
     rename: Option<String>,
     #[darling(map = "split_aliases", default)]
     alias: Vec<String>,
+    /// Aliases whose values are written differently, parsed as `alternate` then converted to the field's type.
+    #[darling(map = "split_aliases", default)]
+    alternate_alias: Vec<String>,
+    #[darling(default)]
+    alternate: Option<String>,
+    /// File fields that aren't a section, which the file fills in itself. Left at their default when parsing.
+    #[darling(default)]
+    skip: bool,
 }
 
 fn parse_fields(item: &ItemStruct) -> Vec<Field> {
@@ -133,7 +141,7 @@ pub fn kvp_file(item: TokenStream) -> TokenStream {
     let ident_str = ident.to_string();
     let ident_str_colon = ident_str + ":";
 
-    let fields = parse_fields(&item);
+    let fields: Vec<Field> = parse_fields(&item).into_iter().filter(|f| !f.skip).collect();
 
     // Error Checking
 
@@ -278,6 +286,20 @@ pub fn kvp_section(item: TokenStream) -> TokenStream {
     fields
         .iter()
         .for_each(|f| assert!(!(f.bare && !f.alias.is_empty()), "Bare fields can't have aliases"));
+    fields
+        .iter()
+        .for_each(|f| assert!(!f.skip, "Only fields of files can be skipped"));
+    fields.iter().for_each(|f| {
+        assert_eq!(
+            f.alternate_alias.is_empty(),
+            f.alternate.is_none(),
+            "Alternate aliases and the alternate type must be given together"
+        );
+        assert!(
+            f.alternate.is_none() || (!f.bare && f.kind == FieldKind::Normal),
+            "Only regular, non-bare fields can have alternate aliases"
+        );
+    });
 
     // Used to assign bare fields indexes as we go. The runtime also keeps track of the current bare field index,
     // matching its index with the index we assign each field.
@@ -337,6 +359,11 @@ pub fn kvp_section(item: TokenStream) -> TokenStream {
                 let optional = <#ty as crate::parse::kvp::FromKVPValue>::from_kvp_value(value);
                 // Push a warning if the conversion from a value fails
                 if let Some(inner) = optional {
+                    warnings.extend(
+                        crate::parse::kvp::FromKVPValue::kvp_value_warnings(&inner, value)
+                            .into_iter()
+                            .map(|kind| crate::parse::kvp::KVPGenericWarning{ span: field.span, kind })
+                    );
                     parsed.#ident.push(inner);
                 } else {
                     warnings.push(crate::parse::kvp::KVPGenericWarning{
@@ -353,7 +380,13 @@ pub fn kvp_section(item: TokenStream) -> TokenStream {
                 let value_optional = <#ty as crate::parse::kvp::FromKVPValue>::from_kvp_value(value);
 
                 if key_optional.is_some() && value_optional.is_some() {
-                    parsed.#ident.insert(key_optional.unwrap(), value_optional.unwrap());
+                    let inner = value_optional.unwrap();
+                    warnings.extend(
+                        crate::parse::kvp::FromKVPValue::kvp_value_warnings(&inner, value)
+                            .into_iter()
+                            .map(|kind| crate::parse::kvp::KVPGenericWarning{ span: field.span, kind })
+                    );
+                    parsed.#ident.insert(key_optional.unwrap(), inner);
                 } else {
                     if !key_optional.is_some() {
                         warnings.push(crate::parse::kvp::KVPGenericWarning{
@@ -378,6 +411,11 @@ pub fn kvp_section(item: TokenStream) -> TokenStream {
             FieldKind::Normal => quote! {{
                 let optional = <#ty as crate::parse::kvp::FromKVPValue>::from_kvp_value(value);
                 if let Some(inner) = optional {
+                    warnings.extend(
+                        crate::parse::kvp::FromKVPValue::kvp_value_warnings(&inner, value)
+                            .into_iter()
+                            .map(|kind| crate::parse::kvp::KVPGenericWarning{ span: field.span, kind })
+                    );
                     parsed.#ident = inner;
                 } else {
                     warnings.push(crate::parse::kvp::KVPGenericWarning{
@@ -390,9 +428,41 @@ pub fn kvp_section(item: TokenStream) -> TokenStream {
                 #bare_operation
             }},
         };
+        // Alternate aliases are parsed as a different type, then converted into the field's type.
+        let alternate = field.alternate.as_ref().map_or_else(TokenStream2::new, |alternate| {
+            let alternate: Type = syn::parse_str(alternate).expect("Alternate must be a type");
+            let alternate_aliases = combine_token_streams(
+                field
+                    .alternate_alias
+                    .iter()
+                    .map(|alias| quote! { crate::parse::kvp::ValueData::KeyValuePair{ key: #alias, value } })
+                    .intersperse(quote! {|}),
+            );
+            quote! {
+                #alternate_aliases => {
+                    let optional = <#alternate as crate::parse::kvp::FromKVPValue>::from_kvp_value(value);
+                    if let Some(inner) = optional {
+                        warnings.extend(
+                            crate::parse::kvp::FromKVPValue::kvp_value_warnings(&inner, value)
+                                .into_iter()
+                                .map(|kind| crate::parse::kvp::KVPGenericWarning{ span: field.span, kind })
+                        );
+                        parsed.#ident = <#ty as crate::parse::kvp::FromAlternateKVPValue<#alternate>>::from_alternate_kvp_value(inner);
+                    } else {
+                        warnings.push(crate::parse::kvp::KVPGenericWarning{
+                            span: field.span,
+                            kind: crate::parse::kvp::KVPGenericWarningKind::InvalidValue {
+                                value: String::from(value),
+                            }
+                        })
+                    };
+                },
+            }
+        });
         // Hey look ma, a match arm
         quote! {
             #primary #aliases => #operation,
+            #alternate
         }
     }));
 
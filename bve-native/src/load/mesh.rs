@@ -1,6 +1,7 @@
 use crate::{
-    parse::mesh::{BlendMode, Glow, Mesh_Error, Mesh_Warning},
-    str_to_owned_ptr, unowned_ptr_to_str, COption, CVector,
+    owned_ptr_to_string,
+    parse::mesh::{BlendMode, Glow, Mesh_Error, Mesh_Warning, WrapMode},
+    str_to_owned_ptr, string_to_owned_ptr, unowned_ptr_to_str, COption, CVector,
 };
 use async_std::task::block_on;
 use bve::{load::mesh, ColorU8RGB, ColorU8RGBA};
@@ -138,6 +139,9 @@ pub struct Mesh_Texture {
     pub texture_id: COption<usize>,
     pub decal_transparent_color: COption<ColorU8RGB>,
     pub emission_color: ColorU8RGB,
+    pub light_map_id: COption<usize>,
+    pub wrap_mode: COption<WrapMode>,
+    pub crossfading: bool,
 }
 
 impl From<mesh::Texture> for Mesh_Texture {
@@ -146,6 +150,9 @@ impl From<mesh::Texture> for Mesh_Texture {
             texture_id: other.texture_id.into(),
             decal_transparent_color: other.decal_transparent_color.into(),
             emission_color: other.emission_color,
+            light_map_id: other.light_map_id.into(),
+            wrap_mode: other.wrap_mode.into(),
+            crossfading: other.crossfading,
         }
     }
 }
@@ -156,6 +163,58 @@ impl Into<mesh::Texture> for Mesh_Texture {
             texture_id: self.texture_id.into(),
             decal_transparent_color: self.decal_transparent_color.into(),
             emission_color: self.emission_color,
+            light_map_id: self.light_map_id.into(),
+            wrap_mode: self.wrap_mode.into(),
+            crossfading: self.crossfading,
+        }
+    }
+}
+
+/// C safe wrapper for [`MeshText`](bve::load::mesh::MeshText).
+///
+/// # Safety
+///
+/// - Must be destroyed as part of its parent [`Loaded_Static_Mesh`].
+/// - `font` is null if the default font should be used.
+#[repr(C)]
+pub struct Mesh_Text {
+    pub text: *mut c_char,
+    pub color: ColorU8RGB,
+    pub background_color: ColorU8RGB,
+    pub padding_x: f32,
+    pub padding_y: f32,
+    pub font: *mut c_char,
+}
+
+impl From<mesh::MeshText> for Mesh_Text {
+    fn from(other: mesh::MeshText) -> Self {
+        Self {
+            text: string_to_owned_ptr(other.text),
+            color: other.color,
+            background_color: other.background_color,
+            padding_x: other.padding.x(),
+            padding_y: other.padding.y(),
+            font: other.font.map_or(null_mut(), string_to_owned_ptr),
+        }
+    }
+}
+
+impl Into<mesh::MeshText> for Mesh_Text {
+    fn into(self) -> mesh::MeshText {
+        mesh::MeshText {
+            text: if self.text.is_null() {
+                String::new()
+            } else {
+                unsafe { owned_ptr_to_string(self.text) }
+            },
+            color: self.color,
+            background_color: self.background_color,
+            padding: (self.padding_x, self.padding_y).into(),
+            font: if self.font.is_null() {
+                None
+            } else {
+                Some(unsafe { owned_ptr_to_string(self.font) })
+            },
         }
     }
 }
@@ -173,6 +232,7 @@ pub struct Mesh {
     pub color: ColorU8RGBA,
     pub blend_mode: BlendMode,
     pub glow: Glow,
+    pub text: COption<Mesh_Text>,
 }
 
 impl From<mesh::Mesh> for Mesh {
@@ -184,6 +244,7 @@ impl From<mesh::Mesh> for Mesh {
             color: other.color,
             blend_mode: other.blend_mode,
             glow: other.glow,
+            text: other.text.into(),
         }
     }
 }
@@ -197,12 +258,15 @@ impl Into<mesh::Mesh> for Mesh {
             color: self.color,
             blend_mode: self.blend_mode,
             glow: self.glow,
+            text: Option::<Mesh_Text>::from(self.text).map(Into::into),
         }
     }
 }
 
 /// C Interface for [`load_mesh_from_file`](bve::load::mesh::load_mesh_from_file).
 ///
+/// Returns null if the file could not be loaded.
+///
 /// # Safety
 ///
 /// - `file` must be non-null and null terminated.
@@ -212,7 +276,7 @@ impl Into<mesh::Mesh> for Mesh {
 pub unsafe extern "C" fn bve_load_mesh_from_file(file: *const c_char) -> *mut Loaded_Static_Mesh {
     let result = block_on(mesh::load_mesh_from_file(unowned_ptr_to_str(&file).as_ref()));
     match result {
-        Some(m) => Box::into_raw(Box::new(m.into())),
-        None => null_mut(),
+        Ok(m) => Box::into_raw(Box::new(m.into())),
+        Err(_) => null_mut(),
     }
 }
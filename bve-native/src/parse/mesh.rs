@@ -4,7 +4,7 @@ use crate::{interfaces::User_Error_Data, parse::Span, *};
 use bve::parse::{mesh, UserError};
 use bve_derive::c_interface;
 
-pub use mesh::{BlendMode, FileType, Glow, GlowAttenuationMode};
+pub use mesh::{BlendMode, FileType, Glow, GlowAttenuationMode, WrapMode};
 
 /// C safe wrapper for [`MeshError`](bve::parse::mesh::MeshError).
 ///
@@ -60,6 +60,20 @@ pub enum Mesh_Error_Kind {
         msg_english: *const c_char,
     },
     UnknownCSV,
+    UnreadableMaterialLibrary {
+        file: *const c_char,
+        error: *const c_char,
+    },
+    InvalidXHeader,
+    UnexpectedXToken {
+        token: *const c_char,
+    },
+    UnexpectedXEnd {
+        template: *const c_char,
+    },
+    UnknownXMaterial {
+        name: *const c_char,
+    },
 }
 
 impl Clone for Mesh_Error_Kind {
@@ -79,6 +93,28 @@ impl Clone for Mesh_Error_Kind {
                 }
             },
             Self::UnknownCSV => Self::UnknownCSV,
+            Self::UnreadableMaterialLibrary { file, error } => unsafe {
+                Self::UnreadableMaterialLibrary {
+                    file: copy_string(*file),
+                    error: copy_string(*error),
+                }
+            },
+            Self::InvalidXHeader => Self::InvalidXHeader,
+            Self::UnexpectedXToken { token } => unsafe {
+                Self::UnexpectedXToken {
+                    token: copy_string(*token),
+                }
+            },
+            Self::UnexpectedXEnd { template } => unsafe {
+                Self::UnexpectedXEnd {
+                    template: copy_string(*template),
+                }
+            },
+            Self::UnknownXMaterial { name } => unsafe {
+                Self::UnknownXMaterial {
+                    name: copy_string(*name),
+                }
+            },
         }
     }
 }
@@ -96,6 +132,20 @@ impl From<mesh::MeshErrorKind> for Mesh_Error_Kind {
                 msg_english: str_to_owned_ptr(&msg_english),
             },
             mesh::MeshErrorKind::UnknownCSV => Self::UnknownCSV,
+            mesh::MeshErrorKind::UnreadableMaterialLibrary { file, error } => Self::UnreadableMaterialLibrary {
+                file: str_to_owned_ptr(&file),
+                error: str_to_owned_ptr(&error),
+            },
+            mesh::MeshErrorKind::InvalidXHeader => Self::InvalidXHeader,
+            mesh::MeshErrorKind::UnexpectedXToken { token } => Self::UnexpectedXToken {
+                token: str_to_owned_ptr(&token),
+            },
+            mesh::MeshErrorKind::UnexpectedXEnd { template } => Self::UnexpectedXEnd {
+                template: str_to_owned_ptr(&template),
+            },
+            mesh::MeshErrorKind::UnknownXMaterial { name } => Self::UnknownXMaterial {
+                name: str_to_owned_ptr(&name),
+            },
         }
     }
 }
@@ -113,6 +163,20 @@ impl Into<mesh::MeshErrorKind> for Mesh_Error_Kind {
                 msg_english: unsafe { owned_ptr_to_string(msg_english as *mut c_char) },
             },
             Self::UnknownCSV => mesh::MeshErrorKind::UnknownCSV,
+            Self::UnreadableMaterialLibrary { file, error } => mesh::MeshErrorKind::UnreadableMaterialLibrary {
+                file: unsafe { owned_ptr_to_string(file as *mut c_char) },
+                error: unsafe { owned_ptr_to_string(error as *mut c_char) },
+            },
+            Self::InvalidXHeader => mesh::MeshErrorKind::InvalidXHeader,
+            Self::UnexpectedXToken { token } => mesh::MeshErrorKind::UnexpectedXToken {
+                token: unsafe { owned_ptr_to_string(token as *mut c_char) },
+            },
+            Self::UnexpectedXEnd { template } => mesh::MeshErrorKind::UnexpectedXEnd {
+                template: unsafe { owned_ptr_to_string(template as *mut c_char) },
+            },
+            Self::UnknownXMaterial { name } => mesh::MeshErrorKind::UnknownXMaterial {
+                name: unsafe { owned_ptr_to_string(name as *mut c_char) },
+            },
         }
     }
 }
@@ -170,6 +234,12 @@ impl Into<mesh::MeshWarning> for Mesh_Warning {
 #[derive(Debug)]
 pub enum Mesh_Warning_Kind {
     UselessInstruction { name: *const c_char },
+    UnsupportedXTemplate { name: *const c_char },
+    DuplicateFaceIndex { idx: usize },
+    DegenerateTriangle,
+    NonFinitePosition,
+    ZeroLengthNormal,
+    MalformedEncoding { encoding: *const c_char },
 }
 
 impl Clone for Mesh_Warning_Kind {
@@ -180,6 +250,20 @@ impl Clone for Mesh_Warning_Kind {
                     name: copy_string(*name),
                 }
             },
+            Self::UnsupportedXTemplate { name } => unsafe {
+                Self::UnsupportedXTemplate {
+                    name: copy_string(*name),
+                }
+            },
+            Self::DuplicateFaceIndex { idx } => Self::DuplicateFaceIndex { idx: *idx },
+            Self::DegenerateTriangle => Self::DegenerateTriangle,
+            Self::NonFinitePosition => Self::NonFinitePosition,
+            Self::ZeroLengthNormal => Self::ZeroLengthNormal,
+            Self::MalformedEncoding { encoding } => unsafe {
+                Self::MalformedEncoding {
+                    encoding: copy_string(*encoding),
+                }
+            },
         }
     }
 }
@@ -190,6 +274,16 @@ impl From<mesh::MeshWarningKind> for Mesh_Warning_Kind {
             mesh::MeshWarningKind::UselessInstruction { name } => Self::UselessInstruction {
                 name: str_to_owned_ptr(&name),
             },
+            mesh::MeshWarningKind::UnsupportedXTemplate { name } => Self::UnsupportedXTemplate {
+                name: str_to_owned_ptr(&name),
+            },
+            mesh::MeshWarningKind::DuplicateFaceIndex { idx } => Self::DuplicateFaceIndex { idx },
+            mesh::MeshWarningKind::DegenerateTriangle => Self::DegenerateTriangle,
+            mesh::MeshWarningKind::NonFinitePosition => Self::NonFinitePosition,
+            mesh::MeshWarningKind::ZeroLengthNormal => Self::ZeroLengthNormal,
+            mesh::MeshWarningKind::MalformedEncoding { encoding } => Self::MalformedEncoding {
+                encoding: str_to_owned_ptr(&encoding),
+            },
         }
     }
 }
@@ -200,6 +294,16 @@ impl Into<mesh::MeshWarningKind> for Mesh_Warning_Kind {
             Self::UselessInstruction { name } => mesh::MeshWarningKind::UselessInstruction {
                 name: unsafe { owned_ptr_to_string(name as *mut c_char) },
             },
+            Self::UnsupportedXTemplate { name } => mesh::MeshWarningKind::UnsupportedXTemplate {
+                name: unsafe { owned_ptr_to_string(name as *mut c_char) },
+            },
+            Self::DuplicateFaceIndex { idx } => mesh::MeshWarningKind::DuplicateFaceIndex { idx },
+            Self::DegenerateTriangle => mesh::MeshWarningKind::DegenerateTriangle,
+            Self::NonFinitePosition => mesh::MeshWarningKind::NonFinitePosition,
+            Self::ZeroLengthNormal => mesh::MeshWarningKind::ZeroLengthNormal,
+            Self::MalformedEncoding { encoding } => mesh::MeshWarningKind::MalformedEncoding {
+                encoding: unsafe { owned_ptr_to_string(encoding as *mut c_char) },
+            },
         }
     }
 }
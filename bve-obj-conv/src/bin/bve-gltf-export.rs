@@ -0,0 +1,296 @@
+use async_std::{
+    fs,
+    path::{Path, PathBuf},
+    task::block_on,
+};
+use bve::{
+    export::gltf::{export_gltf, EmbeddedImage},
+    filesystem::resolve_path,
+    load::mesh::{load_mesh_from_file, optimize_mesh, OptimizationSettings},
+    parse::UserError,
+};
+use log::{error, info, warn};
+use std::{convert::TryFrom, process::exit};
+use walkdir::WalkDir;
+
+#[derive(Clone)]
+pub struct Arguments {
+    pub help: bool,
+    pub source_dir: PathBuf,
+    pub output_dir: PathBuf,
+    pub glb: bool,
+    pub errors: bool,
+    pub optimize: bool,
+
+    pub log_output: Option<PathBuf>,
+    pub quiet: bool,
+    pub debug: bool,
+    pub trace: bool,
+}
+
+const HELP_MESSAGE: &str = r#"cargo run --bin bve-gltf-export -- [options] <source> <output>
+BVE-Reborn glTF exporter -- converts every static object in a directory to glTF
+
+General Options:
+  <source>   Directory to search for .b3d, .csv, .obj, and .x objects
+  <output>   Directory to write the converted objects to. The directory
+               structure of <source> is kept.
+  -h,--help  Print this message
+
+Output Options:
+  -b,--glb       Write binary .glb files instead of .gltf and .bin files.
+                   Textures are embedded, converted to png if needed.
+  -e,--errors    Print all warnings/errors in the objects
+  -O,--optimize  Merge meshes, weld vertices, and reorder triangles before
+                   exporting. Prints statistics for each object.
+
+Logging Options:
+  --log        Send all messages to a file. Errors and warnings
+                 will also be sent to stderr as normal.
+  -q,--quiet   Disable info level log messages
+  -v,--debug   Enable debug trace level log messages
+  -vv,--trace  Enable trace level log messages
+"#;
+
+impl Arguments {
+    #[allow(clippy::redundant_closure)] // PathBuf::try_from doesn't work
+    pub fn create(mut args: pico_args::Arguments) -> Result<Self, String> {
+        let o = Self {
+            help: args.contains(["-h", "--help"]),
+            glb: args.contains(["-b", "--glb"]),
+            errors: args.contains(["-e", "--errors"]),
+            optimize: args.contains(["-O", "--optimize"]),
+
+            log_output: args
+                .opt_value_from_os_str("--log", |os| PathBuf::try_from(os))
+                .map_err(|e| e.to_string())?,
+            quiet: args.contains(["-q", "--quiet"]),
+            debug: args.contains(["-v", "--debug"]),
+            trace: args.contains(["-vv", "--trace"]),
+
+            source_dir: args
+                .free_from_os_str(|os| PathBuf::try_from(os))
+                .map_err(|e| e.to_string())?
+                .ok_or_else(|| String::from("No source directory provided"))?,
+            output_dir: args
+                .free_from_os_str(|os| PathBuf::try_from(os))
+                .map_err(|e| e.to_string())?
+                .ok_or_else(|| String::from("No output directory provided"))?,
+        };
+
+        args.finish().map_err(|e| e.to_string())?;
+
+        Ok(o)
+    }
+
+    #[must_use]
+    pub fn from_args() -> Self {
+        let o = Self::create(pico_args::Arguments::from_env());
+
+        match o {
+            Ok(Arguments { help: true, .. }) => {
+                println!("{}", HELP_MESSAGE);
+                exit(0);
+            }
+            Err(e) => {
+                println!("Error parsing args: {}\n{}", e, HELP_MESSAGE);
+                exit(1);
+            }
+            Ok(o) => o,
+        }
+    }
+}
+
+fn is_object(path: &std::path::Path) -> bool {
+    let ext = path.extension().map(|e| e.to_string_lossy().to_lowercase());
+    matches!(ext.as_deref(), Some("b3d") | Some("csv") | Some("obj") | Some("x"))
+}
+
+/// Path to `to` from the directory `from`. Both must be absolute.
+fn relative_path(from: &std::path::Path, to: &std::path::Path) -> std::path::PathBuf {
+    let from: Vec<_> = from.components().collect();
+    let to: Vec<_> = to.components().collect();
+    let common = from.iter().zip(&to).take_while(|(a, b)| a == b).count();
+    let mut relative = std::path::PathBuf::new();
+    for _ in common..from.len() {
+        relative.push("..");
+    }
+    for component in &to[common..] {
+        relative.push(component);
+    }
+    relative
+}
+
+/// Finds each of the mesh's textures, relative to the mesh. Textures that can't be found are `None`.
+async fn find_textures(source: &Path, textures: &[String]) -> Vec<Option<PathBuf>> {
+    let source_dir = source.parent().unwrap_or_else(|| Path::new("."));
+    let mut found = Vec::with_capacity(textures.len());
+    for texture in textures {
+        let path = resolve_path(source_dir, PathBuf::from(texture)).await;
+        if path.is_none() {
+            warn!("Could not find texture {} used by {}", texture, source.display());
+        }
+        found.push(path);
+    }
+    found
+}
+
+/// Texture uris relative to the `.gltf` file in `output_dir`. Textures that weren't found keep their filename.
+async fn image_uris(output_dir: &Path, textures: &[String], found: &[Option<PathBuf>]) -> Vec<String> {
+    let output_dir = output_dir
+        .canonicalize()
+        .await
+        .unwrap_or_else(|_| output_dir.to_path_buf());
+    let mut uris = Vec::with_capacity(textures.len());
+    for (texture, path) in textures.iter().zip(found) {
+        let uri = match path {
+            Some(path) => match path.canonicalize().await {
+                Ok(canonical) => relative_path(output_dir.as_ref(), canonical.as_ref())
+                    .to_string_lossy()
+                    .into_owned(),
+                Err(_) => texture.clone(),
+            },
+            None => texture.clone(),
+        };
+        uris.push(uri);
+    }
+    uris
+}
+
+/// Texture data to embed in a `.glb`. Textures that weren't found or couldn't be converted aren't embedded.
+async fn embedded_images(found: &[Option<PathBuf>]) -> Vec<Option<EmbeddedImage>> {
+    let mut images = Vec::with_capacity(found.len());
+    for path in found {
+        let path = match path {
+            Some(path) => path,
+            None => {
+                images.push(None);
+                continue;
+            }
+        };
+        let image = match fs::read(path).await {
+            Ok(data) => EmbeddedImage::from_file_data(data).map_err(|e| e.to_string()),
+            Err(err) => Err(err.to_string()),
+        };
+        match image {
+            Ok(image) => images.push(Some(image)),
+            Err(err) => {
+                warn!("Could not embed texture {}: {}", path.display(), err);
+                images.push(None);
+            }
+        }
+    }
+    images
+}
+
+/// Converts a single object. Returns false if it couldn't be converted.
+async fn convert(source: &Path, output: &Path, options: &Arguments) -> bool {
+    let mut mesh = match load_mesh_from_file(source).await {
+        Ok(mesh) => mesh,
+        Err(err) => {
+            error!("{}", err);
+            return false;
+        }
+    };
+
+    if options.errors {
+        for w in &mesh.warnings {
+            let w = w.to_data();
+            warn!(
+                "\t{}:{} {:?}",
+                source.display(),
+                w.line.map(|v| v.to_string()).as_deref().unwrap_or("None"),
+                w.description_english
+            );
+        }
+        for e in &mesh.errors {
+            let e = e.to_data();
+            error!(
+                "\t{}:{} {:?}",
+                source.display(),
+                e.line.map(|v| v.to_string()).as_deref().unwrap_or("None"),
+                e.description_english
+            );
+        }
+    }
+
+    if options.optimize {
+        let statistics = optimize_mesh(&mut mesh, OptimizationSettings::default());
+        info!("Optimized {}: {}", source.display(), statistics);
+    }
+
+    let gltf = export_gltf(&mesh);
+    let textures: Vec<String> = mesh.textures.iter().cloned().collect();
+    let found = find_textures(source, &textures).await;
+
+    let output_dir = output.parent().unwrap_or_else(|| Path::new("."));
+    if let Err(err) = fs::create_dir_all(output_dir).await {
+        error!("Could not create directory {}: {}", output_dir.display(), err);
+        return false;
+    }
+
+    let result = if options.glb {
+        let images = embedded_images(&found).await;
+        fs::write(output.with_extension("glb"), gltf.to_glb(&images)).await
+    } else {
+        let bin_path = output.with_extension("bin");
+        let bin_name = bin_path
+            .file_name()
+            .expect("Output must have a file name")
+            .to_string_lossy()
+            .into_owned();
+        match fs::write(&bin_path, &gltf.buffer).await {
+            Ok(()) => {
+                let uris = image_uris(output_dir, &textures, &found).await;
+                fs::write(output.with_extension("gltf"), gltf.to_gltf(&bin_name, &uris)).await
+            }
+            Err(err) => Err(err),
+        }
+    };
+
+    match result {
+        Ok(()) => {
+            info!("Converted {}", source.display());
+            true
+        }
+        Err(err) => {
+            error!("Could not write {}: {}", output.display(), err);
+            false
+        }
+    }
+}
+
+fn main() {
+    let options: Arguments = Arguments::from_args();
+
+    bve::log::enable_logger(&options.log_output, options.quiet, options.debug, options.trace);
+
+    let source_dir: &std::path::Path = options.source_dir.as_ref();
+    let mut converted = 0_usize;
+    let mut failed = 0_usize;
+    for entry in WalkDir::new(source_dir).follow_links(true).into_iter().flatten() {
+        if entry.file_type().is_dir() || !is_object(entry.path()) {
+            continue;
+        }
+        let relative = entry
+            .path()
+            .strip_prefix(source_dir)
+            .expect("Walked path must be inside the source directory");
+        // Keep the original extension in the name, as objects with the same name but different formats are common
+        let mut output_name = relative.as_os_str().to_owned();
+        output_name.push(".gltf");
+        let output = options.output_dir.join(output_name);
+
+        let source = PathBuf::from(entry.path());
+        if block_on(convert(&source, &output, &options)) {
+            converted += 1;
+        } else {
+            failed += 1;
+        }
+    }
+
+    info!("Converted: {}, Failed: {}", converted, failed);
+    if failed != 0 {
+        exit(1);
+    }
+}
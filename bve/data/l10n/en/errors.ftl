@@ -7,12 +7,36 @@ kvp-unknown-section = Unknown Section: "{$section}"
 kvp-unknown-field = Unknown Field: "{$field}"
 kvp-too-many-fields = Too Many Values: Value {$number} of {$total}
 kvp-invalid-value = Invalid Value: "{$value}"
+kvp-function-script-trailing-input = Function script has unparsable input at the end: "{$input}"
+kvp-function-script-stack-underflow = Function script has an operator without enough operands
+kvp-function-script-invalid-result = Function script gives {$count} values instead of 1
+kvp-function-script-unknown-function = Unknown function "{$name}"
+kvp-function-script-argument-count = Function "{$name}" takes {$expected} arguments, but was given {$found}
+kvp-function-script-unknown-variable = Unknown variable "{$name}"
+kvp-function-script-missing-index = Variable "{$name}" must be given an index
+kvp-function-script-unexpected-index = Variable "{$name}" cannot be given an index
 
 mesh-warning-useless-instruction = Instruction "{$name}" has no effect
+mesh-warning-unsupported-x-template = {$name} data is not supported and was ignored
+mesh-warning-duplicate-face-index = Face uses vertex {$idx} more than once
+mesh-warning-degenerate-triangle = Face has a triangle with no area
+mesh-warning-non-finite-position = Transformation moved vertices to an infinite or NaN position
+mesh-warning-zero-length-normal = Vertex normal could not be calculated as the faces using it have no area
+mesh-warning-malformed-encoding = File is not valid {$encoding}, characters that could not be decoded were replaced
 mesh-error-utf8 = UTF-8 error on column {$column}
 mesh-error-out-of-bounds = Index {$idx} is out of bounds
 mesh-error-unknown-instruction = Unrecognized instruction {$name}
 mesh-error-unknown-csv = Unknown error in csv-like parsing
+mesh-error-unreadable-material-library = Unable to load material library {$file}: {$error}
+mesh-error-invalid-x-header = File is not a text format DirectX object
+mesh-error-unexpected-x-token = Unexpected "{$token}"
+mesh-error-unexpected-x-end = {$template} ended before all its data was read
+mesh-error-unknown-x-material = Unknown material {$name}
+
+load-error-unsupported-format = Cannot load "{$path}": "{$extension}" is not a supported mesh format
+load-error-unreadable = Cannot read "{$path}": {$error}
+load-error-malformed = Cannot load "{$path}": {$error}
+load-error-recursive-include = Cannot load "{$path}": it includes itself
 
 route-preprocessing-malformed-directive = The syntax for preprocessing directive "{$directive}"" is incorrect
 route-preprocessing-include-file-not-found = File "{$file}" included is not found
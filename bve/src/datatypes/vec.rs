@@ -1,9 +1,10 @@
 use glam::{Vec2, Vec3A, Vec4};
+use serde::{Deserialize, Serialize};
 
 macro_rules! gen_uivec2 {
     ($($name:ident => $ty:ty),*) => {$(
         #[repr(C)]
-        #[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+        #[derive(Debug, Copy, Clone, Eq, PartialEq, Hash, Serialize, Deserialize)]
         pub struct $name {
             pub x: $ty,
             pub y: $ty,
@@ -190,7 +191,7 @@ macro_rules! numeric_uivec2 {
 macro_rules! gen_uivec3 {
     ($($name:ident => $ty:ty),*) => {$(
         #[repr(C)]
-        #[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+        #[derive(Debug, Copy, Clone, Eq, PartialEq, Hash, Serialize, Deserialize)]
         pub struct $name {
             pub x: $ty,
             pub y: $ty,
@@ -391,7 +392,7 @@ macro_rules! numeric_uivec3 {
 macro_rules! gen_uivec4 {
     ($($name:ident => $ty:ty),*) => {$(
         #[repr(C)]
-        #[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+        #[derive(Debug, Copy, Clone, Eq, PartialEq, Hash, Serialize, Deserialize)]
         pub struct $name {
             pub x: $ty,
             pub y: $ty,
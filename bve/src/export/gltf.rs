@@ -0,0 +1,644 @@
+//! Export of static meshes to glTF 2.0.
+//!
+//! Every [`Mesh`] becomes a glTF mesh in its own node, with one primitive for single sided faces and one for double
+//! sided faces. BVE's left handed coordinates are converted to glTF's right handed ones by negating z. Data glTF has
+//! no place for is put into `extras`:
+//!
+//! - Materials have the `blend_mode`.
+//! - Meshes have the `glow` and `decal_transparent_color`.
+
+use crate::{
+    load::mesh::{LoadedStaticMesh, Mesh},
+    parse::mesh::{BlendMode, GlowAttenuationMode, WrapMode},
+    ColorU8RGB,
+};
+use glam::{Vec2, Vec3A};
+use image::{guess_format, ImageFormat, ImageOutputFormat, ImageResult};
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+
+const ARRAY_BUFFER: u32 = 34962;
+const ELEMENT_ARRAY_BUFFER: u32 = 34963;
+
+const UNSIGNED_BYTE: u32 = 5121;
+const UNSIGNED_INT: u32 = 5125;
+const FLOAT: u32 = 5126;
+
+const CLAMP_TO_EDGE: u32 = 33071;
+const REPEAT: u32 = 10497;
+
+const GLB_MAGIC: u32 = 0x4654_6C67;
+const GLB_JSON_CHUNK: u32 = 0x4E4F_534A;
+const GLB_BIN_CHUNK: u32 = 0x004E_4942;
+
+/// An exported glTF document, along with the binary data of its only buffer.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Gltf {
+    /// The glTF json, without a uri for the buffer. Image uris are the texture filenames from the mesh, which are
+    /// relative to the mesh, not to the exported file.
+    pub document: Value,
+    pub buffer: Vec<u8>,
+}
+
+impl Gltf {
+    /// Json for a `.gltf` file, which refers to the buffer as a separate file at `buffer_uri`.
+    ///
+    /// `image_uris` has a path relative to the `.gltf` file for each texture in the mesh's
+    /// [`TextureSet`](crate::load::mesh::TextureSet), in order.
+    #[must_use]
+    pub fn to_gltf(&self, buffer_uri: &str, image_uris: &[String]) -> String {
+        let mut document = self.document.clone();
+        document["buffers"][0]["uri"] = Value::String(encode_uri(buffer_uri));
+        for (idx, uri) in image_uris.iter().enumerate() {
+            document["images"][idx]["uri"] = Value::String(encode_uri(uri));
+        }
+        serde_json::to_string_pretty(&document).expect("Unable to serialize gltf")
+    }
+
+    /// A `.glb` file, with the buffer embedded.
+    ///
+    /// `images` has the data for each texture in the mesh's [`TextureSet`](crate::load::mesh::TextureSet), in order,
+    /// which is embedded in the buffer. Textures without data keep their uri.
+    #[must_use]
+    pub fn to_glb(&self, images: &[Option<EmbeddedImage>]) -> Vec<u8> {
+        let mut document = self.document.clone();
+        let mut buffer = self.buffer.clone();
+        for (idx, image) in images.iter().enumerate() {
+            if let Some(image) = image {
+                pad(&mut buffer, 0);
+                let view = push_array(
+                    &mut document,
+                    "bufferViews",
+                    json!({
+                        "buffer": 0,
+                        "byteOffset": buffer.len(),
+                        "byteLength": image.data.len(),
+                    }),
+                );
+                buffer.extend_from_slice(&image.data);
+                document["images"][idx] = json!({ "bufferView": view, "mimeType": image.mime_type });
+            }
+        }
+        if buffer.len() != self.buffer.len() {
+            document["buffers"] = json!([{ "byteLength": buffer.len() }]);
+        }
+
+        let mut json = serde_json::to_vec(&document).expect("Unable to serialize gltf");
+        pad(&mut json, b' ');
+        pad(&mut buffer, 0);
+
+        // The bin chunk is left out entirely when there's no buffer
+        let bin_length = if buffer.is_empty() { 0 } else { 8 + buffer.len() };
+        let length = 12 + 8 + json.len() + bin_length;
+        let mut glb = Vec::with_capacity(length);
+        for word in &[GLB_MAGIC, 2, length as u32, json.len() as u32, GLB_JSON_CHUNK] {
+            glb.extend_from_slice(&word.to_le_bytes());
+        }
+        glb.extend_from_slice(&json);
+        if !buffer.is_empty() {
+            for word in &[buffer.len() as u32, GLB_BIN_CHUNK] {
+                glb.extend_from_slice(&word.to_le_bytes());
+            }
+            glb.extend_from_slice(&buffer);
+        }
+        glb
+    }
+}
+
+/// An image to embed in a `.glb`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EmbeddedImage {
+    pub data: Vec<u8>,
+    pub mime_type: &'static str,
+}
+
+impl EmbeddedImage {
+    /// glTF only allows png and jpeg images. Images in other formats, such as the bmps common in BVE, are converted to
+    /// png.
+    ///
+    /// # Errors
+    ///
+    /// Returns Err if the image needs converting and could not be decoded.
+    pub fn from_file_data(data: Vec<u8>) -> ImageResult<Self> {
+        match guess_format(&data) {
+            Ok(ImageFormat::Png) => Ok(Self {
+                data,
+                mime_type: "image/png",
+            }),
+            Ok(ImageFormat::Jpeg) => Ok(Self {
+                data,
+                mime_type: "image/jpeg",
+            }),
+            _ => {
+                let mut png = Vec::new();
+                image::load_from_memory(&data)?.write_to(&mut png, ImageOutputFormat::Png)?;
+                Ok(Self {
+                    data: png,
+                    mime_type: "image/png",
+                })
+            }
+        }
+    }
+}
+
+/// Pushes `value` onto the array at `key` in `document`, creating the array if needed. Returns the index of `value`.
+fn push_array(document: &mut Value, key: &str, value: Value) -> usize {
+    let array = document
+        .as_object_mut()
+        .expect("Document must be an object")
+        .entry(key)
+        .or_insert_with(|| Value::Array(Vec::new()))
+        .as_array_mut()
+        .expect("Must be an array");
+    array.push(value);
+    array.len() - 1
+}
+
+/// Pads to a multiple of 4 bytes, as required by glTF for buffer views and glb chunks.
+fn pad(data: &mut Vec<u8>, value: u8) {
+    while data.len() % 4 != 0 {
+        data.push(value);
+    }
+}
+
+/// Texture paths in BVE are filesystem paths, glTF needs relative uris.
+fn encode_uri(path: &str) -> String {
+    path.replace('\\', "/").replace('%', "%25").replace(' ', "%20")
+}
+
+/// Converts a loaded mesh to glTF. Textures are referenced by their filenames in the mesh's
+/// [`TextureSet`](crate::load::mesh::TextureSet) until [`Gltf::to_gltf`] or [`Gltf::to_glb`] replace them.
+#[must_use]
+pub fn export_gltf(loaded: &LoadedStaticMesh) -> Gltf {
+    let mut builder = GltfBuilder::default();
+
+    let images: Vec<Value> = loaded
+        .textures
+        .iter()
+        .map(|filename| json!({ "uri": encode_uri(filename) }))
+        .collect();
+
+    let nodes: Vec<Value> = loaded
+        .meshes
+        .iter()
+        .enumerate()
+        .map(|(idx, mesh)| {
+            builder.mesh(idx, mesh);
+            json!({ "mesh": idx })
+        })
+        .collect();
+
+    let scene_nodes: Vec<usize> = (0..nodes.len()).collect();
+    let mut document = json!({
+        "asset": {
+            "version": "2.0",
+            "generator": concat!("bve-reborn ", env!("CARGO_PKG_VERSION")),
+        },
+        "scene": 0,
+        "scenes": [{ "nodes": scene_nodes }],
+        "nodes": nodes,
+        "meshes": builder.meshes,
+        "materials": builder.materials,
+        "accessors": builder.accessors,
+        "bufferViews": builder.views,
+        "buffers": [{ "byteLength": builder.buffer.len() }],
+    });
+    // Empty arrays are not allowed in glTF
+    if !images.is_empty() {
+        document["images"] = Value::Array(images);
+    }
+    if !builder.textures.is_empty() {
+        document["textures"] = Value::Array(builder.textures);
+        document["samplers"] = Value::Array(builder.samplers);
+    }
+    if loaded.meshes.is_empty() {
+        let object = document.as_object_mut().expect("Document must be an object");
+        for key in &["nodes", "meshes", "materials", "accessors", "bufferViews", "buffers"] {
+            object.remove(*key);
+        }
+        document["scenes"] = json!([{}]);
+    }
+
+    Gltf {
+        document,
+        buffer: builder.buffer,
+    }
+}
+
+#[derive(Default)]
+struct GltfBuilder {
+    buffer: Vec<u8>,
+    views: Vec<Value>,
+    accessors: Vec<Value>,
+    meshes: Vec<Value>,
+    materials: Vec<Value>,
+    textures: Vec<Value>,
+    samplers: Vec<Value>,
+    /// Texture index for each combination of image and wrap mode.
+    texture_lookup: BTreeMap<(usize, Option<WrapMode>), usize>,
+    /// Sampler index for each wrap mode.
+    sampler_lookup: BTreeMap<Option<WrapMode>, usize>,
+}
+
+impl GltfBuilder {
+    fn mesh(&mut self, idx: usize, mesh: &Mesh) {
+        let positions: Vec<Vec3A> = mesh
+            .vertices
+            .iter()
+            .map(|v| v.position * Vec3A::new(1.0, 1.0, -1.0))
+            .collect();
+        let min = positions.iter().fold(Vec3A::splat(f32::INFINITY), |a, &b| a.min(b));
+        let max = positions.iter().fold(Vec3A::splat(f32::NEG_INFINITY), |a, &b| a.max(b));
+        let position = self.vec3_accessor(&positions);
+        self.accessors[position]["min"] = json!([min.x(), min.y(), min.z()]);
+        self.accessors[position]["max"] = json!([max.x(), max.y(), max.z()]);
+
+        let normals: Vec<Vec3A> = mesh
+            .vertices
+            .iter()
+            .map(|v| v.normal * Vec3A::new(1.0, 1.0, -1.0))
+            .collect();
+        let normal = self.vec3_accessor(&normals);
+
+        let coords: Vec<Vec2> = mesh.vertices.iter().map(|v| v.coord).collect();
+        let coord = self.vec2_accessor(&coords);
+
+        let colors: Vec<u8> = mesh
+            .vertices
+            .iter()
+            .flat_map(|v| v.color.into_array().to_vec())
+            .collect();
+        let view = self.view(&colors, ARRAY_BUFFER);
+        let color = self.accessor(json!({
+            "bufferView": view,
+            "componentType": UNSIGNED_BYTE,
+            "normalized": true,
+            "count": mesh.vertices.len(),
+            "type": "VEC4",
+        }));
+
+        // Split faces by sidedness, reversing the winding to account for the flipped z axis.
+        let mut single_sided = Vec::new();
+        let mut double_sided = Vec::new();
+        for triangle in mesh.indices.chunks_exact(3) {
+            let list = if mesh.vertices[triangle[0]].double_sided {
+                &mut double_sided
+            } else {
+                &mut single_sided
+            };
+            list.extend_from_slice(&[triangle[0] as u32, triangle[2] as u32, triangle[1] as u32]);
+        }
+
+        let mut primitives = Vec::new();
+        for (indices, double_sided) in &[(single_sided, false), (double_sided, true)] {
+            if indices.is_empty() {
+                continue;
+            }
+            let bytes: Vec<u8> = indices.iter().flat_map(|i| i.to_le_bytes().to_vec()).collect();
+            let view = self.view(&bytes, ELEMENT_ARRAY_BUFFER);
+            let indices = self.accessor(json!({
+                "bufferView": view,
+                "componentType": UNSIGNED_INT,
+                "count": indices.len(),
+                "type": "SCALAR",
+            }));
+            let material = self.material(idx, mesh, *double_sided);
+            primitives.push(json!({
+                "attributes": {
+                    "POSITION": position,
+                    "NORMAL": normal,
+                    "TEXCOORD_0": coord,
+                    "COLOR_0": color,
+                },
+                "indices": indices,
+                "material": material,
+            }));
+        }
+
+        self.meshes.push(json!({
+            "name": format!("mesh{}", idx),
+            "primitives": primitives,
+            "extras": {
+                "glow": {
+                    "attenuation_mode": match mesh.glow.attenuation_mode {
+                        GlowAttenuationMode::DivideExponent2 => "divideexponent2",
+                        GlowAttenuationMode::DivideExponent4 => "divideexponent4",
+                    },
+                    "half_distance": mesh.glow.half_distance,
+                },
+                "decal_transparent_color": mesh.texture.decal_transparent_color.map(ColorU8RGB::into_array),
+            },
+        }));
+    }
+
+    fn material(&mut self, idx: usize, mesh: &Mesh, double_sided: bool) -> usize {
+        let alpha_mode = if mesh.texture.decal_transparent_color.is_some() {
+            "MASK"
+        } else if mesh.blend_mode == BlendMode::Additive || mesh.color.w != 255 {
+            "BLEND"
+        } else {
+            "OPAQUE"
+        };
+        let emissive = mesh.texture.emission_color;
+
+        // The mesh color is already in the vertex colors, so the base color factor is left at white.
+        let mut pbr = json!({
+            "metallicFactor": 0.0,
+            "roughnessFactor": 1.0,
+        });
+        if let Some(texture_id) = mesh.texture.texture_id {
+            let texture = self.texture(texture_id, mesh.texture.wrap_mode);
+            pbr["baseColorTexture"] = json!({ "index": texture });
+        }
+
+        self.materials.push(json!({
+            "name": format!("mesh{}{}", idx, if double_sided { "_double_sided" } else { "" }),
+            "pbrMetallicRoughness": pbr,
+            "emissiveFactor": [
+                f32::from(emissive.x) / 255.0,
+                f32::from(emissive.y) / 255.0,
+                f32::from(emissive.z) / 255.0,
+            ],
+            "alphaMode": alpha_mode,
+            "doubleSided": double_sided,
+            "extras": {
+                "blend_mode": match mesh.blend_mode {
+                    BlendMode::Normal => "normal",
+                    BlendMode::Additive => "additive",
+                },
+            },
+        }));
+        self.materials.len() - 1
+    }
+
+    fn texture(&mut self, image: usize, wrap_mode: Option<WrapMode>) -> usize {
+        if let Some(&texture) = self.texture_lookup.get(&(image, wrap_mode)) {
+            return texture;
+        }
+
+        let sampler = match self.sampler_lookup.get(&wrap_mode) {
+            Some(&sampler) => sampler,
+            None => {
+                let (s, t) = match wrap_mode.unwrap_or(WrapMode::RepeatRepeat) {
+                    WrapMode::ClampClamp => (CLAMP_TO_EDGE, CLAMP_TO_EDGE),
+                    WrapMode::ClampRepeat => (CLAMP_TO_EDGE, REPEAT),
+                    WrapMode::RepeatClamp => (REPEAT, CLAMP_TO_EDGE),
+                    WrapMode::RepeatRepeat => (REPEAT, REPEAT),
+                };
+                self.samplers.push(json!({ "wrapS": s, "wrapT": t }));
+                self.sampler_lookup.insert(wrap_mode, self.samplers.len() - 1);
+                self.samplers.len() - 1
+            }
+        };
+
+        self.textures.push(json!({ "source": image, "sampler": sampler }));
+        self.texture_lookup.insert((image, wrap_mode), self.textures.len() - 1);
+        self.textures.len() - 1
+    }
+
+    fn vec3_accessor(&mut self, values: &[Vec3A]) -> usize {
+        let bytes: Vec<u8> = values
+            .iter()
+            .flat_map(|v| {
+                let mut bytes = Vec::with_capacity(12);
+                for component in &[v.x(), v.y(), v.z()] {
+                    bytes.extend_from_slice(&component.to_le_bytes());
+                }
+                bytes
+            })
+            .collect();
+        let view = self.view(&bytes, ARRAY_BUFFER);
+        self.accessor(json!({
+            "bufferView": view,
+            "componentType": FLOAT,
+            "count": values.len(),
+            "type": "VEC3",
+        }))
+    }
+
+    fn vec2_accessor(&mut self, values: &[Vec2]) -> usize {
+        let bytes: Vec<u8> = values
+            .iter()
+            .flat_map(|v| {
+                let mut bytes = Vec::with_capacity(8);
+                bytes.extend_from_slice(&v.x().to_le_bytes());
+                bytes.extend_from_slice(&v.y().to_le_bytes());
+                bytes
+            })
+            .collect();
+        let view = self.view(&bytes, ARRAY_BUFFER);
+        self.accessor(json!({
+            "bufferView": view,
+            "componentType": FLOAT,
+            "count": values.len(),
+            "type": "VEC2",
+        }))
+    }
+
+    fn view(&mut self, bytes: &[u8], target: u32) -> usize {
+        pad(&mut self.buffer, 0);
+        self.views.push(json!({
+            "buffer": 0,
+            "byteOffset": self.buffer.len(),
+            "byteLength": bytes.len(),
+            "target": target,
+        }));
+        self.buffer.extend_from_slice(bytes);
+        self.views.len() - 1
+    }
+
+    fn accessor(&mut self, accessor: Value) -> usize {
+        self.accessors.push(accessor);
+        self.accessors.len() - 1
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        export::gltf::{export_gltf, EmbeddedImage, GLB_MAGIC},
+        load::mesh::{generate_meshes, LoadedStaticMesh},
+        parse::mesh::{
+            instructions::{create_instructions, post_process},
+            FileType,
+        },
+    };
+    use image::{ImageOutputFormat, Rgba, RgbaImage};
+    use serde_json::json;
+    use std::convert::TryInto;
+
+    const TEXTURED: &str = indoc::indoc!(
+        r#"
+        CreateMeshBuilder
+        AddVertex, 0, 0, 0
+        AddVertex, 1, 0, 0
+        AddVertex, 0, 1, 0
+        AddFace, 0, 1, 2
+        LoadTexture, wood.bmp
+    "#
+    );
+
+    fn encode(format: ImageOutputFormat) -> Vec<u8> {
+        let mut data = Vec::new();
+        image::DynamicImage::ImageRgba8(RgbaImage::from_pixel(2, 2, Rgba([255, 0, 0, 255])))
+            .write_to(&mut data, format)
+            .expect("Could not encode image");
+        data
+    }
+
+    fn load(source: &str) -> LoadedStaticMesh {
+        generate_meshes(post_process(create_instructions(source, FileType::CSV)))
+    }
+
+    fn read_u32(bytes: &[u8], offset: usize) -> u32 {
+        u32::from_le_bytes(bytes[offset..offset + 4].try_into().expect("Slice is 4 bytes"))
+    }
+
+    #[bve_derive::bve_test]
+    #[test]
+    fn triangle() {
+        let gltf = export_gltf(&load(indoc::indoc!(
+            r#"
+            CreateMeshBuilder
+            AddVertex, 0, 0, 0
+            AddVertex, 1, 0, 2
+            AddVertex, 0, 1, 0
+            AddFace, 0, 1, 2
+            SetColor, 255, 0, 0, 128
+            LoadTexture, textures\wood grain.png
+            SetWrapMode, ClampRepeat
+            SetBlendMode, Additive, 3, DivideExponent2
+            SetDecalTransparentColor, 0, 0, 255
+        "#
+        )));
+        let doc = &gltf.document;
+
+        assert_eq!(doc["meshes"].as_array().map(Vec::len), Some(1));
+        let primitive = &doc["meshes"][0]["primitives"][0];
+        let position =
+            &doc["accessors"][primitive["attributes"]["POSITION"].as_u64().expect("Missing position") as usize];
+        assert_eq!(position["count"], 3);
+        // z is flipped
+        assert_eq!(position["min"], json!([0.0, 0.0, -2.0]));
+        assert_eq!(position["max"], json!([1.0, 1.0, 0.0]));
+
+        // Winding is reversed
+        let indices = &doc["accessors"][primitive["indices"].as_u64().expect("Missing indices") as usize];
+        let view = &doc["bufferViews"][indices["bufferView"].as_u64().expect("Missing view") as usize];
+        let offset = view["byteOffset"].as_u64().expect("Missing offset") as usize;
+        let read: Vec<u32> = (0..3).map(|i| read_u32(&gltf.buffer, offset + i * 4)).collect();
+        assert_eq!(read, vec![0, 2, 1]);
+
+        let material = &doc["materials"][0];
+        assert_eq!(material["alphaMode"], "MASK");
+        assert_eq!(material["doubleSided"], false);
+        assert_eq!(material["extras"]["blend_mode"], "additive");
+        assert_eq!(doc["images"][0]["uri"], "textures/wood%20grain.png");
+        assert_eq!(doc["samplers"][0], json!({ "wrapS": 33071, "wrapT": 10497 }));
+
+        assert_eq!(
+            doc["meshes"][0]["extras"],
+            json!({
+                "glow": { "attenuation_mode": "divideexponent2", "half_distance": 3 },
+                "decal_transparent_color": [0, 0, 255],
+            })
+        );
+    }
+
+    #[bve_derive::bve_test]
+    #[test]
+    fn double_sided() {
+        let gltf = export_gltf(&load(indoc::indoc!(
+            r#"
+            CreateMeshBuilder
+            AddVertex, 0, 0, 0
+            AddVertex, 1, 0, 0
+            AddVertex, 0, 1, 0
+            AddVertex, 1, 1, 0
+            AddFace, 0, 1, 2
+            AddFace2, 1, 3, 2
+        "#
+        )));
+        let primitives = &gltf.document["meshes"][0]["primitives"];
+        assert_eq!(primitives.as_array().map(Vec::len), Some(2));
+        assert_eq!(gltf.document["materials"][0]["doubleSided"], false);
+        assert_eq!(gltf.document["materials"][1]["doubleSided"], true);
+        assert_eq!(gltf.document["materials"][0]["alphaMode"], "OPAQUE");
+        // glTF doesn't allow empty arrays
+        assert_eq!(gltf.document.get("images"), None);
+        assert_eq!(gltf.document.get("textures"), None);
+        assert_eq!(gltf.document.get("samplers"), None);
+    }
+
+    #[bve_derive::bve_test]
+    #[test]
+    fn unused_texture() {
+        let mut mesh = load(TEXTURED);
+        for m in &mut mesh.meshes {
+            m.texture.texture_id = None;
+        }
+        let gltf = export_gltf(&mesh);
+        assert_eq!(gltf.document["images"][0]["uri"], "wood.bmp");
+        assert_eq!(gltf.document.get("textures"), None);
+        assert_eq!(gltf.document.get("samplers"), None);
+    }
+
+    #[bve_derive::bve_test]
+    #[test]
+    fn image_uris() {
+        let gltf = export_gltf(&load(TEXTURED));
+        let document: serde_json::Value =
+            serde_json::from_str(&gltf.to_gltf("wood.bin", &[String::from(r"..\textures\wood grain.bmp")]))
+                .expect("Invalid json");
+        assert_eq!(document["buffers"][0]["uri"], "wood.bin");
+        assert_eq!(document["images"][0]["uri"], "../textures/wood%20grain.bmp");
+    }
+
+    #[bve_derive::bve_test]
+    #[test]
+    fn embedded_images() {
+        let png = EmbeddedImage::from_file_data(encode(ImageOutputFormat::Png)).expect("Could not load png");
+        assert_eq!(png.mime_type, "image/png");
+        assert_eq!(png.data, encode(ImageOutputFormat::Png));
+
+        // Bmps are converted to pngs
+        let bmp = EmbeddedImage::from_file_data(encode(ImageOutputFormat::Bmp)).expect("Could not load bmp");
+        assert_eq!(bmp.mime_type, "image/png");
+        assert_eq!(&bmp.data[1..4], b"PNG");
+
+        let gltf = export_gltf(&load(TEXTURED));
+        let glb = gltf.to_glb(&[Some(bmp.clone())]);
+        let json_length = read_u32(&glb, 12) as usize;
+        let json: serde_json::Value = serde_json::from_slice(&glb[20..20 + json_length]).expect("Invalid json chunk");
+        let image = &json["images"][0];
+        assert_eq!(image["mimeType"], "image/png");
+        assert_eq!(image.get("uri"), None);
+
+        let view = &json["bufferViews"][image["bufferView"].as_u64().expect("Missing view") as usize];
+        let offset = view["byteOffset"].as_u64().expect("Missing offset") as usize;
+        let length = view["byteLength"].as_u64().expect("Missing length") as usize;
+        let bin = &glb[20 + json_length + 8..];
+        assert_eq!(&bin[offset..offset + length], &bmp.data[..]);
+        assert!(json["buffers"][0]["byteLength"].as_u64().expect("Missing length") as usize >= offset + length);
+    }
+
+    #[bve_derive::bve_test]
+    #[test]
+    fn glb() {
+        let gltf = export_gltf(&load("CreateMeshBuilder\nCube, 1, 1, 1\n"));
+        let glb = gltf.to_glb(&[]);
+
+        assert_eq!(read_u32(&glb, 0), GLB_MAGIC);
+        assert_eq!(read_u32(&glb, 4), 2);
+        assert_eq!(read_u32(&glb, 8) as usize, glb.len());
+        assert_eq!(glb.len() % 4, 0);
+
+        let json_length = read_u32(&glb, 12) as usize;
+        let json: serde_json::Value = serde_json::from_slice(&glb[20..20 + json_length]).expect("Invalid json chunk");
+        assert_eq!(json, gltf.document);
+        // The bin chunk is padded, so may be a little longer than the buffer
+        let bin_length = read_u32(&glb, 20 + json_length) as usize;
+        let buffer_length = json["buffers"][0]["byteLength"].as_u64().expect("Missing length") as usize;
+        assert!(bin_length >= buffer_length && bin_length - buffer_length < 4);
+    }
+}
@@ -0,0 +1,3 @@
+//! Conversion of loaded data to formats used by other tools.
+
+pub mod gltf;
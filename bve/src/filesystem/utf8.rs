@@ -1,6 +1,7 @@
 use async_std::{fs::read, path::Path};
 use chardetng::EncodingDetector;
-use log::{debug, trace};
+use encoding_rs::Encoding;
+use log::{debug, trace, warn};
 use std::io::Result;
 
 /// Reads a file, detects the encoding, and converts to utf8.
@@ -9,14 +10,54 @@ use std::io::Result;
 ///
 /// Returns Err if opening/reading the file fails. All errors come from [`std::fs::read`].
 pub async fn read_convert_utf8(filename: impl AsRef<Path>) -> Result<String> {
+    read_convert_utf8_with(filename, None).await
+}
+
+/// Reads a file and converts it to utf8 from `encoding`. If `encoding` is `None`, it is detected.
+///
+/// # Errors
+///
+/// Returns Err if opening/reading the file fails. All errors come from [`std::fs::read`].
+pub async fn read_convert_utf8_with(filename: impl AsRef<Path>, encoding: Option<&'static Encoding>) -> Result<String> {
     debug!("Reading and converting {}", filename.as_ref().display());
 
     let bytes = read(filename).await?;
 
-    Ok(convert_to_utf8(bytes))
+    Ok(match encoding {
+        Some(encoding) => {
+            trace!("{} chosen by caller", encoding.name());
+            encoding.decode_with_bom_removal(&bytes).0.to_string()
+        }
+        None => convert_to_utf8(bytes),
+    })
+}
+
+/// Detects the encoding of `bytes` and converts them to utf8. Malformed sequences are replaced with U+FFFD and
+/// logged as a warning.
+#[must_use]
+pub fn convert_to_utf8(bytes: Vec<u8>) -> String {
+    let (result, encoding, malformed) = decode(bytes);
+    if malformed {
+        warn!(
+            "Replaced malformed {} sequences while converting to utf8",
+            encoding.name()
+        );
+    }
+    result
+}
+
+/// Detects the encoding of `bytes` and converts them to utf8. Malformed sequences are replaced with U+FFFD.
+///
+/// Also returns the encoding the bytes were decoded from if any sequences had to be replaced, so callers can report
+/// the broken file.
+#[must_use]
+pub fn convert_to_utf8_checked(bytes: Vec<u8>) -> (String, Option<&'static Encoding>) {
+    let (result, encoding, malformed) = decode(bytes);
+    (result, if malformed { Some(encoding) } else { None })
 }
 
-fn convert_to_utf8(bytes: Vec<u8>) -> String {
+/// Returns the converted string, the encoding it was converted from, and if there were malformed sequences.
+fn decode(bytes: Vec<u8>) -> (String, &'static Encoding, bool) {
     trace!("Converting file of {} bytes", bytes.len());
 
     // Byte order marks are not properly dealt with in chardetng, detect them here, encoding_rs will remove them
@@ -31,20 +72,21 @@ fn convert_to_utf8(bytes: Vec<u8>) -> String {
         let ascii_only = !detector.feed(&bytes, true);
         if ascii_only {
             trace!("UTF-8 chosen due to All ASCII");
-            return String::from_utf8(bytes).expect("Only ascii characters detected, but utf8 validation failed");
+            let result = String::from_utf8(bytes).expect("Only ascii characters detected, but utf8 validation failed");
+            return (result, encoding_rs::UTF_8, false);
         }
         (detector.guess(None, true), "chardetng")
     };
 
     trace!("{} chosen due to {}", encoding.name(), reason);
-    let (result, ..) = encoding.decode_with_bom_removal(&bytes);
+    let (result, malformed) = encoding.decode_with_bom_removal(&bytes);
 
-    result.to_string()
+    (result.to_string(), encoding, malformed)
 }
 
 #[cfg(test)]
 mod test {
-    use super::convert_to_utf8;
+    use super::{convert_to_utf8, convert_to_utf8_checked, decode};
 
     #[bve_derive::bve_test]
     #[test]
@@ -66,4 +108,20 @@ mod test {
             "こんにちは、元気ですか？"
         );
     }
+
+    #[bve_derive::bve_test]
+    #[test]
+    fn malformed() {
+        assert!(!decode(b"valid".to_vec()).2);
+        // Unpaired surrogate
+        assert!(decode(vec![0xFF, 0xFE, 0x00, 0xD8]).2);
+        assert_eq!(
+            convert_to_utf8_checked(b"valid".to_vec()),
+            (String::from("valid"), None)
+        );
+        assert_eq!(
+            convert_to_utf8_checked(vec![0xFF, 0xFE, 0x00, 0xD8]),
+            (String::from("\u{FFFD}"), Some(encoding_rs::UTF_16LE))
+        );
+    }
 }
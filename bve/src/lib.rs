@@ -61,6 +61,7 @@ pub use datatypes::*;
 
 pub mod data;
 mod datatypes;
+pub mod export;
 pub mod filesystem;
 pub mod l10n;
 pub mod load;
@@ -0,0 +1,480 @@
+use crate::{
+    load::{
+        animated::{AnimatedNode, AnimatedSubObject, LoadedAnimatedObject},
+        route::TrackPlacement,
+    },
+    parse::{
+        animated::{Damping, RefreshRate},
+        function_scripts::{CompiledFunctionScript, FunctionScriptContext},
+    },
+};
+use glam::{Mat4, Vec2, Vec3, Vec3A};
+
+/// Longest step used when moving damped rotations. Longer frames are split into equal steps no longer than this.
+const DAMPING_STEP: f32 = 1.0 / 240.0;
+/// Most steps a single frame is split into. Steps get longer instead, which is less accurate, but still stable.
+const MAX_DAMPING_STEPS: f32 = 1000.0;
+
+/// Output of a single `[Object]` section for the current frame.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct SubObjectFrame {
+    /// Index into [`AnimatedSubObject::states`] of the state to show. `None` if the state function chose a state that
+    /// doesn't exist, which shows nothing.
+    pub state: Option<usize>,
+    /// Transform from the state's mesh into the space of the top level animated file.
+    pub model: Mat4,
+    /// Offset added to the texture coordinates of the state's mesh.
+    pub texture_shift: Vec2,
+    /// How far along the track the object is moved from where it was placed, given by
+    /// [`AnimatedSubObject::track_follower_function`]. Already applied to `model` when the object is animated with a
+    /// [`TrackPlacement`]. unit: m
+    pub track_distance: f32,
+}
+
+impl Default for SubObjectFrame {
+    fn default() -> Self {
+        Self {
+            state: None,
+            model: Mat4::identity(),
+            texture_shift: Vec2::zero(),
+            track_distance: 0.0,
+        }
+    }
+}
+
+/// Value following a target like a damped spring.
+#[derive(Debug, Default, Copy, Clone, PartialEq)]
+struct DampedValue {
+    value: f32,
+    velocity: f32,
+    /// Set once the value has been given its first target, which it starts at.
+    started: bool,
+}
+
+impl DampedValue {
+    /// Moves the value towards `target` over `delta` seconds.
+    ///
+    /// Uses implicit Euler steps, which never blow up no matter the frequency or frame length.
+    fn update(&mut self, target: f32, delta: f32, damping: &Damping) -> f32 {
+        let frequency = damping.frequency;
+        if !self.started || frequency <= 0.0 {
+            self.value = target;
+            self.velocity = 0.0;
+            self.started = true;
+            return self.value;
+        }
+
+        let ratio = damping.damping_ratio.max(0.0);
+        let steps = (delta / DAMPING_STEP).ceil().max(1.0).min(MAX_DAMPING_STEPS);
+        let step = delta / steps;
+        let stiffness = frequency * frequency;
+        let divisor = 1.0 + 2.0 * ratio * frequency * step + stiffness * step * step;
+        for _ in 0..steps as u32 {
+            self.velocity = (self.velocity + step * stiffness * (target - self.value)) / divisor;
+            self.value += step * self.velocity;
+        }
+        self.value
+    }
+}
+
+#[derive(Debug, Default, Clone, PartialEq)]
+struct SubObjectState {
+    /// Time since the functions were last evaluated. unit: seconds
+    since_refresh: f32,
+    /// Set once the functions have been evaluated.
+    refreshed: bool,
+    rotations: [DampedValue; 3],
+}
+
+/// Animation of a [`LoadedAnimatedObject`], including every animated file nested inside it.
+///
+/// Only depends on the values given by the [`FunctionScriptContext`] and the length of each frame, so the same inputs
+/// always animate the same way.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct ObjectAnimation {
+    states: Vec<SubObjectState>,
+    frames: Vec<SubObjectFrame>,
+}
+
+impl ObjectAnimation {
+    /// Animation for `object`, which must be the object given to every call to [`update`](Self::update).
+    #[must_use]
+    pub fn new(object: &LoadedAnimatedObject) -> Self {
+        let count = count_sub_objects(object);
+        Self {
+            states: vec![SubObjectState::default(); count],
+            frames: vec![SubObjectFrame::default(); count],
+        }
+    }
+
+    /// Moves the animation forward by `delta` seconds.
+    ///
+    /// When the object was placed on the rail with `track`, sub-objects with a track follower function are moved
+    /// along the rail by it. Otherwise they stay where they are.
+    ///
+    /// Functions of sub-objects with a [`RefreshRate::Seconds`] are only evaluated once that much time has passed
+    /// since they were last evaluated, and keep their last output in between. Functions that fail to evaluate give 0.
+    ///
+    /// Returns a frame for every `[Object]` section, in the order they are found going depth first through the
+    /// nodes.
+    pub fn update(
+        &mut self,
+        object: &LoadedAnimatedObject,
+        track: Option<TrackPlacement<'_>>,
+        delta: f32,
+        context: &mut dyn FunctionScriptContext,
+    ) -> &[SubObjectFrame] {
+        let mut index = 0;
+        self.update_nodes(object, Mat4::identity(), track, delta, context, &mut index);
+        assert_eq!(
+            index,
+            self.frames.len(),
+            "Object isn't the one the animation was made for"
+        );
+        &self.frames
+    }
+
+    /// The frames given by the last call to [`update`](Self::update).
+    #[must_use]
+    pub fn frames(&self) -> &[SubObjectFrame] {
+        &self.frames
+    }
+
+    fn update_nodes(
+        &mut self,
+        object: &LoadedAnimatedObject,
+        parent: Mat4,
+        track: Option<TrackPlacement<'_>>,
+        delta: f32,
+        context: &mut dyn FunctionScriptContext,
+        index: &mut usize,
+    ) {
+        for node in &object.nodes {
+            match node {
+                AnimatedNode::Static(..) => {}
+                AnimatedNode::Nested { position, object } => {
+                    let transform = parent * Mat4::from_translation(Vec3::from(*position));
+                    self.update_nodes(object, transform, track, delta, context, index);
+                }
+                AnimatedNode::Object(sub_object) => {
+                    update_sub_object(
+                        sub_object,
+                        &mut self.states[*index],
+                        &mut self.frames[*index],
+                        parent,
+                        track,
+                        delta,
+                        context,
+                    );
+                    *index += 1;
+                }
+            }
+        }
+    }
+}
+
+fn count_sub_objects(object: &LoadedAnimatedObject) -> usize {
+    object
+        .nodes
+        .iter()
+        .map(|node| match node {
+            AnimatedNode::Static(..) => 0,
+            AnimatedNode::Nested { object, .. } => count_sub_objects(object),
+            AnimatedNode::Object(..) => 1,
+        })
+        .sum()
+}
+
+fn evaluate(script: Option<&CompiledFunctionScript>, context: &mut dyn FunctionScriptContext) -> f64 {
+    script.map_or(0.0, |script| script.evaluate(context).unwrap_or(0.0))
+}
+
+/// Rotation of `angle` radians around `direction`. Directions of zero length don't rotate.
+fn rotation(direction: Vec3A, angle: f32) -> Mat4 {
+    if angle == 0.0 || direction.length_squared() == 0.0 {
+        Mat4::identity()
+    } else {
+        Mat4::from_axis_angle(Vec3::from(direction.normalize()), angle)
+    }
+}
+
+fn update_sub_object(
+    sub_object: &AnimatedSubObject,
+    state: &mut SubObjectState,
+    frame: &mut SubObjectFrame,
+    parent: Mat4,
+    track: Option<TrackPlacement<'_>>,
+    delta: f32,
+    context: &mut dyn FunctionScriptContext,
+) {
+    state.since_refresh += delta;
+    let due = match sub_object.refresh_rate {
+        RefreshRate::EveryFrame => true,
+        RefreshRate::Seconds(rate) => !state.refreshed || state.since_refresh >= rate,
+    };
+    if !due {
+        return;
+    }
+    let elapsed = state.since_refresh;
+    state.since_refresh = 0.0;
+    state.refreshed = true;
+
+    frame.state = match &sub_object.state_function {
+        Some(function) => {
+            let value = evaluate(Some(function), context).round();
+            if value >= 0.0 && value < sub_object.states.len() as f64 {
+                Some(value as usize)
+            } else {
+                None
+            }
+        }
+        None if sub_object.states.is_empty() => None,
+        None => Some(0),
+    };
+
+    let translation = sub_object.translate.iter().fold(Vec3A::zero(), |sum, channel| {
+        sum + channel.direction * evaluate(channel.function.as_ref(), context) as f32
+    });
+
+    // Rotated around X, then Y, then Z
+    let mut rotations = Mat4::identity();
+    for (channel, damped) in sub_object.rotate.iter().zip(state.rotations.iter_mut()) {
+        let target = evaluate(channel.function.as_ref(), context) as f32;
+        let angle = match &channel.damping {
+            Some(damping) => damped.update(target, elapsed, damping),
+            None => target,
+        };
+        rotations = rotation(channel.direction, angle) * rotations;
+    }
+
+    frame.texture_shift = sub_object.texture_shift.iter().fold(Vec2::zero(), |sum, channel| {
+        sum + channel.direction * evaluate(channel.function.as_ref(), context) as f32
+    });
+
+    frame.track_distance = evaluate(sub_object.track_follower_function.as_ref(), context) as f32;
+    let follow = track.map_or(Mat4::identity(), |placement| placement.follow(frame.track_distance));
+
+    frame.model = follow * parent * Mat4::from_translation(Vec3::from(sub_object.position + translation)) * rotations;
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        load::{
+            animated::{
+                fixtures::{script, sub_object, TestContext},
+                AnimatedNode, AnimatedSubObject, LoadedAnimatedObject, ObjectAnimation,
+            },
+            route::{RailAlignment, TrackPlacement},
+        },
+        parse::{
+            animated::{Damping, RefreshRate},
+            route::ir::{ParsedCommand, ParsedDirective, TrackCurve},
+        },
+    };
+    use glam::{Vec2, Vec3, Vec3A};
+    use smallvec::smallvec;
+    use std::f32::consts::FRAC_PI_2;
+
+    fn object(sub_objects: Vec<AnimatedSubObject>) -> LoadedAnimatedObject {
+        LoadedAnimatedObject {
+            nodes: sub_objects.into_iter().map(AnimatedNode::Object).collect(),
+            ..LoadedAnimatedObject::default()
+        }
+    }
+
+    fn assert_close(actual: Vec3, expected: Vec3) {
+        assert!((actual - expected).length() < 1e-5, "{:?} != {:?}", actual, expected);
+    }
+
+    #[bve_derive::bve_test]
+    #[test]
+    fn states_and_transforms() {
+        let mut animated = sub_object(3);
+        animated.state_function = script("time");
+        animated.position = Vec3A::new(0.0, 0.0, 10.0);
+        animated.translate[0].function = script("time * 2");
+        animated.rotate[2].function = script(&format!("{}", FRAC_PI_2));
+        animated.texture_shift[1].function = script("time / 2");
+        animated.track_follower_function = script("time * 10");
+        let object = object(vec![animated, sub_object(0)]);
+
+        let mut animation = ObjectAnimation::new(&object);
+        let mut context = TestContext {
+            time: 1.4,
+            evaluations: 0,
+        };
+        let frames = animation.update(&object, None, 0.1, &mut context);
+        assert_eq!(frames.len(), 2);
+        assert_eq!(frames[0].state, Some(1));
+        // Rotated first, then moved
+        assert_close(
+            frames[0].model.transform_point3(Vec3::unit_x()),
+            Vec3::new(2.8, 1.0, 10.0),
+        );
+        assert_eq!(frames[0].texture_shift, Vec2::new(0.0, 0.7));
+        assert_eq!(frames[0].track_distance, 14.0);
+        assert_eq!(frames[1].track_distance, 0.0);
+        // No states and no function shows nothing
+        assert_eq!(frames[1].state, None);
+
+        for &(time, state) in &[(-0.6, None), (2.4, Some(2)), (2.6, None)] {
+            context.time = time;
+            assert_eq!(
+                animation.update(&object, None, 0.1, &mut context)[0].state,
+                state,
+                "{}",
+                time
+            );
+        }
+    }
+
+    #[bve_derive::bve_test]
+    #[test]
+    fn nested_position() {
+        let mut animated = sub_object(1);
+        animated.position = Vec3A::unit_y();
+        let object = LoadedAnimatedObject {
+            nodes: vec![AnimatedNode::Nested {
+                position: Vec3A::unit_x(),
+                object: object(vec![animated]),
+            }],
+            ..LoadedAnimatedObject::default()
+        };
+
+        let mut animation = ObjectAnimation::new(&object);
+        let mut context = TestContext {
+            time: 0.0,
+            evaluations: 0,
+        };
+        let frames = animation.update(&object, None, 0.1, &mut context);
+        assert_eq!(frames[0].state, Some(0));
+        assert_close(frames[0].model.transform_point3(Vec3::zero()), Vec3::new(1.0, 1.0, 0.0));
+    }
+
+    #[bve_derive::bve_test]
+    #[test]
+    fn refresh_rate() {
+        let mut animated = sub_object(1);
+        animated.translate[0].function = script("time");
+        animated.refresh_rate = RefreshRate::Seconds(0.5);
+        let object = object(vec![animated]);
+
+        let mut animation = ObjectAnimation::new(&object);
+        let mut context = TestContext {
+            time: 0.0,
+            evaluations: 0,
+        };
+        let mut positions = Vec::new();
+        for frame in 0..6 {
+            context.time = f64::from(frame);
+            let frames = animation.update(&object, None, 0.25, &mut context);
+            positions.push(frames[0].model.transform_point3(Vec3::zero()).x());
+        }
+        // Evaluated on the first frame, then every other frame
+        assert_eq!(positions, vec![0.0, 0.0, 2.0, 2.0, 4.0, 4.0]);
+        assert_eq!(context.evaluations, 3);
+    }
+
+    #[bve_derive::bve_test]
+    #[test]
+    fn track_follower() {
+        let radius = 100.0;
+        // Rolled 30 degrees right on the standard gauge
+        let alignment = RailAlignment::from_directives(&[ParsedDirective {
+            command: ParsedCommand::TrackCurve(TrackCurve {
+                curve: radius,
+                cant: 1435.0 / 2.0,
+            }),
+            position: smallvec![50.0],
+        }]);
+        let track = TrackPlacement {
+            alignment: &alignment,
+            position: 50.0,
+        };
+        let mut animated = sub_object(1);
+        animated.position = Vec3A::unit_y();
+        animated.track_follower_function = script("time");
+        let object = object(vec![animated]);
+
+        let mut animation = ObjectAnimation::new(&object);
+        let mut context = TestContext {
+            time: f64::from(radius * FRAC_PI_2),
+            evaluations: 0,
+        };
+        let placed = alignment.pose_at(50.0).transform();
+
+        // A quarter circle right, facing +x
+        let world = placed * animation.update(&object, Some(track), 0.1, &mut context)[0].model;
+        let (sin, cos) = (0.5, 0.75_f32.sqrt());
+        let expected = Vec3::new(radius, cos, 50.0 + radius - sin);
+        let position = world.transform_point3(Vec3::zero());
+        assert!(
+            (position - expected).length() < 1e-3,
+            "{:?} != {:?}",
+            position,
+            expected
+        );
+        let direction = world.transform_vector3(Vec3::unit_z());
+        assert!((direction - Vec3::unit_x()).length() < 1e-3, "{:?}", direction);
+
+        // Not placed on the rail, so it stays put
+        let frames = animation.update(&object, None, 0.1, &mut context);
+        assert_eq!(frames[0].track_distance, radius * FRAC_PI_2);
+        assert_close(frames[0].model.transform_point3(Vec3::zero()), Vec3::unit_y());
+    }
+
+    /// Angles of a damped rotation that starts at 0 and is then told to go to 1, sampled every `delta` seconds.
+    fn damped_angles(damping: Damping, delta: f32, seconds: f32) -> Vec<f32> {
+        let mut animated = sub_object(1);
+        animated.rotate[1].function = script("if[time > 0, 1, 0]");
+        animated.rotate[1].damping = Some(damping);
+        let object = object(vec![animated]);
+
+        let mut animation = ObjectAnimation::new(&object);
+        let mut context = TestContext {
+            time: 0.0,
+            evaluations: 0,
+        };
+        animation.update(&object, None, delta, &mut context);
+        context.time = 1.0;
+        let mut angles = Vec::new();
+        for _ in 0..(seconds / delta) as usize {
+            let frames = animation.update(&object, None, delta, &mut context);
+            // Rotation around Y moves the Z axis towards X by the angle
+            let rotated = frames[0].model.transform_vector3(Vec3::unit_z());
+            angles.push(rotated.x().atan2(rotated.z()));
+        }
+        angles
+    }
+
+    #[bve_derive::bve_test]
+    #[test]
+    fn damping() {
+        let critical = Damping {
+            frequency: 10.0,
+            damping_ratio: 1.0,
+        };
+        let angles = damped_angles(critical.clone(), 1.0 / 60.0, 3.0);
+        assert!(angles[0] > 0.0 && angles[0] < 0.5, "{}", angles[0]);
+        assert!(angles.windows(2).all(|pair| pair[0] <= pair[1]));
+        assert!(angles.iter().all(|&angle| angle <= 1.0));
+        assert!((angles[angles.len() - 1] - 1.0).abs() < 1e-3);
+
+        let bouncy = Damping {
+            frequency: 10.0,
+            damping_ratio: 0.2,
+        };
+        let angles = damped_angles(bouncy, 1.0 / 60.0, 3.0);
+        assert!(angles.iter().any(|&angle| angle > 1.2));
+        assert!((angles[angles.len() - 1] - 1.0).abs() < 1e-2);
+
+        // Deterministic, and long frames stay stable
+        assert_eq!(
+            damped_angles(critical.clone(), 1.0 / 60.0, 1.0),
+            damped_angles(critical.clone(), 1.0 / 60.0, 1.0)
+        );
+        let long_frames = damped_angles(critical, 10.0, 30.0);
+        assert!(long_frames.iter().all(|&angle| (angle - 1.0).abs() < 1e-2));
+    }
+}
@@ -0,0 +1,353 @@
+use crate::{
+    load::{
+        animated::{AnimatedNode, LoadedAnimatedObject, LoadedStateChangeSound, SubObjectFrame},
+        route::TrackPlacement,
+    },
+    parse::{
+        animated::PlayOn,
+        function_scripts::{CompiledFunctionScript, FunctionScriptContext},
+    },
+};
+use async_std::path::Path;
+use glam::{Mat4, Vec3};
+
+/// Looping sound of a `[Sound]` section, for the current frame.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct EmitterFrame<'a> {
+    /// Index of the `[Sound]` section, counting depth first through the object. The same every frame.
+    pub emitter: usize,
+    pub path: &'a Path,
+    /// In the space of the top level animated file.
+    pub position: Vec3,
+    pub volume: f32,
+    pub pitch: f32,
+    /// unit: m
+    pub radius: f32,
+    /// How far along the track the sound is moved from where the object was placed, given by
+    /// [`LoadedSound::track_follower_function`](super::LoadedSound::track_follower_function). Already applied to
+    /// `position` when the object is given a [`TrackPlacement`]. unit: m
+    pub track_distance: f32,
+}
+
+/// Sound of a `[StateChangeSound]` section, to be played once.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct OneShotSound<'a> {
+    /// Index of the frame of the object whose state changed.
+    pub sub_object: usize,
+    pub path: &'a Path,
+    /// In the space of the top level animated file.
+    pub position: Vec3,
+    pub volume: f32,
+    pub pitch: f32,
+    /// unit: m
+    pub radius: f32,
+}
+
+/// Receives the sounds of animated objects. Implemented by whatever plays the sounds.
+pub trait AnimatedAudioSink {
+    /// Called every update for every looping sound.
+    fn emitter(&mut self, frame: EmitterFrame<'_>);
+
+    /// Called when a sound should be played once.
+    fn one_shot(&mut self, sound: OneShotSound<'_>);
+}
+
+/// Sounds of a [`LoadedAnimatedObject`], including every animated file nested inside it.
+///
+/// Follows the frames given by an [`ObjectAnimation`](super::ObjectAnimation) of the same object.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct ObjectSounds {
+    /// State of every sub-object when the sounds were last updated. `None` before the first update.
+    last_states: Option<Vec<Option<usize>>>,
+}
+
+impl ObjectSounds {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sends every looping sound to `sink`, along with a one shot sound for every sub-object whose state changed
+    /// since the last update.
+    ///
+    /// The states the object starts in don't play anything. `frames` must come from the latest
+    /// [`ObjectAnimation::update`](super::ObjectAnimation::update) of `object`, given the same `track`.
+    pub fn update(
+        &mut self,
+        object: &LoadedAnimatedObject,
+        track: Option<TrackPlacement<'_>>,
+        frames: &[SubObjectFrame],
+        context: &mut dyn FunctionScriptContext,
+        sink: &mut dyn AnimatedAudioSink,
+    ) {
+        if let Some(last_states) = &self.last_states {
+            let mut sub_object = 0;
+            play_state_changes(object, frames, last_states, &mut sub_object, sink);
+        }
+        self.last_states = Some(frames.iter().map(|frame| frame.state).collect());
+
+        let mut emitter = 0;
+        update_emitters(object, Mat4::identity(), track, context, &mut emitter, sink);
+    }
+}
+
+fn play_state_changes(
+    object: &LoadedAnimatedObject,
+    frames: &[SubObjectFrame],
+    last_states: &[Option<usize>],
+    sub_object: &mut usize,
+    sink: &mut dyn AnimatedAudioSink,
+) {
+    for node in &object.nodes {
+        match node {
+            AnimatedNode::Static(..) => {}
+            AnimatedNode::Nested { object, .. } => play_state_changes(object, frames, last_states, sub_object, sink),
+            AnimatedNode::Object(animated) => {
+                let frame = &frames[*sub_object];
+                if frame.state != last_states[*sub_object] {
+                    for sound in &animated.state_change_sounds {
+                        if let Some(path) = state_change_path(sound, frame.state) {
+                            sink.one_shot(OneShotSound {
+                                sub_object: *sub_object,
+                                path,
+                                position: frame.model.transform_point3(Vec3::from(sound.position)),
+                                volume: sound.volume,
+                                pitch: sound.pitch,
+                                radius: sound.radius,
+                            });
+                        }
+                    }
+                }
+                *sub_object += 1;
+            }
+        }
+    }
+}
+
+/// The sound to play when the object changes into `state`.
+fn state_change_path(sound: &LoadedStateChangeSound, state: Option<usize>) -> Option<&Path> {
+    match state {
+        Some(state) if !sound.state_sounds.is_empty() => sound.state_sounds.get(state)?.as_deref(),
+        Some(_) if sound.play_on_show == PlayOn::Play => sound.sound.as_deref(),
+        None if sound.play_on_hide == PlayOn::Play => sound.sound.as_deref(),
+        _ => None,
+    }
+}
+
+fn evaluate_or(script: Option<&CompiledFunctionScript>, default: f32, context: &mut dyn FunctionScriptContext) -> f32 {
+    script.map_or(default, |script| script.evaluate(context).unwrap_or(0.0) as f32)
+}
+
+fn update_emitters(
+    object: &LoadedAnimatedObject,
+    parent: Mat4,
+    track: Option<TrackPlacement<'_>>,
+    context: &mut dyn FunctionScriptContext,
+    emitter: &mut usize,
+    sink: &mut dyn AnimatedAudioSink,
+) {
+    for node in &object.nodes {
+        if let AnimatedNode::Nested { position, object } = node {
+            let transform = parent * Mat4::from_translation(Vec3::from(*position));
+            update_emitters(object, transform, track, context, emitter, sink);
+        }
+    }
+
+    for sound in &object.sounds {
+        let track_distance = evaluate_or(sound.track_follower_function.as_ref(), 0.0, context);
+        let follow = track.map_or(Mat4::identity(), |placement| placement.follow(track_distance));
+        sink.emitter(EmitterFrame {
+            emitter: *emitter,
+            path: &sound.path,
+            position: (follow * parent).transform_point3(Vec3::from(sound.position)),
+            volume: evaluate_or(sound.volume_function.as_ref(), sound.volume, context).max(0.0),
+            pitch: evaluate_or(sound.pitch_function.as_ref(), sound.pitch, context).max(0.0),
+            radius: sound.radius,
+            track_distance,
+        });
+        *emitter += 1;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        load::animated::{
+            fixtures::{self, script, TestContext},
+            AnimatedAudioSink, AnimatedNode, AnimatedSubObject, EmitterFrame, LoadedAnimatedObject, LoadedSound,
+            LoadedStateChangeSound, ObjectAnimation, ObjectSounds, OneShotSound,
+        },
+        parse::animated::PlayOn,
+    };
+    use async_std::path::PathBuf;
+    use glam::{Vec3, Vec3A};
+
+    /// Owned copy of everything sent to the sink.
+    #[derive(Debug, Clone, PartialEq)]
+    enum Event {
+        Emitter {
+            emitter: usize,
+            path: PathBuf,
+            position: Vec3,
+            volume: f32,
+            pitch: f32,
+        },
+        OneShot {
+            sub_object: usize,
+            path: PathBuf,
+            position: Vec3,
+        },
+    }
+
+    #[derive(Default)]
+    struct RecordingSink {
+        events: Vec<Event>,
+    }
+
+    impl AnimatedAudioSink for RecordingSink {
+        fn emitter(&mut self, frame: EmitterFrame<'_>) {
+            self.events.push(Event::Emitter {
+                emitter: frame.emitter,
+                path: frame.path.to_path_buf(),
+                position: frame.position,
+                volume: frame.volume,
+                pitch: frame.pitch,
+            });
+        }
+
+        fn one_shot(&mut self, sound: OneShotSound<'_>) {
+            self.events.push(Event::OneShot {
+                sub_object: sound.sub_object,
+                path: sound.path.to_path_buf(),
+                position: sound.position,
+            });
+        }
+    }
+
+    fn sub_object(state_change_sounds: Vec<LoadedStateChangeSound>) -> AnimatedSubObject {
+        AnimatedSubObject {
+            position: Vec3A::new(0.0, 0.0, 5.0),
+            state_function: script("time"),
+            state_change_sounds,
+            ..fixtures::sub_object(3)
+        }
+    }
+
+    fn state_change_sound(state_sounds: &[Option<&str>], sound: &str, play_on_hide: PlayOn) -> LoadedStateChangeSound {
+        LoadedStateChangeSound {
+            state_sounds: state_sounds.iter().map(|sound| sound.map(PathBuf::from)).collect(),
+            sound: Some(PathBuf::from(sound)),
+            position: Vec3A::unit_y(),
+            volume: 1.0,
+            pitch: 1.0,
+            radius: 30.0,
+            play_on_show: PlayOn::Play,
+            play_on_hide,
+        }
+    }
+
+    fn one_shot(sub_object: usize, path: &str) -> Event {
+        Event::OneShot {
+            sub_object,
+            path: PathBuf::from(path),
+            position: Vec3::new(0.0, 1.0, 5.0),
+        }
+    }
+
+    /// Times to run at, and the one shot sounds expected at each.
+    fn run(object: &LoadedAnimatedObject, steps: &[(f64, Vec<Event>)]) {
+        let mut animation = ObjectAnimation::new(object);
+        let mut sounds = ObjectSounds::new();
+        for (time, expected) in steps {
+            let mut context = TestContext {
+                time: *time,
+                evaluations: 0,
+            };
+            let mut sink = RecordingSink::default();
+            let frames = animation.update(object, None, 1.0 / 60.0, &mut context);
+            sounds.update(object, None, frames, &mut context, &mut sink);
+            let one_shots: Vec<Event> = sink
+                .events
+                .into_iter()
+                .filter(|event| matches!(event, Event::OneShot { .. }))
+                .collect();
+            assert_eq!(&one_shots, expected, "{}", time);
+        }
+    }
+
+    #[bve_derive::bve_test]
+    #[test]
+    fn state_change_sounds() {
+        let object = LoadedAnimatedObject {
+            nodes: vec![
+                AnimatedNode::Object(sub_object(vec![state_change_sound(
+                    &[Some("a.wav"), None, Some("c.wav")],
+                    "hide.wav",
+                    PlayOn::Play,
+                )])),
+                AnimatedNode::Object(sub_object(vec![state_change_sound(&[], "show.wav", PlayOn::Silent)])),
+            ],
+            ..LoadedAnimatedObject::default()
+        };
+        run(&object, &[
+            // Starting states are silent
+            (0.0, vec![]),
+            (2.0, vec![one_shot(0, "c.wav"), one_shot(1, "show.wav")]),
+            (2.0, vec![]),
+            (1.0, vec![one_shot(1, "show.wav")]),
+            (5.0, vec![one_shot(0, "hide.wav")]),
+            (0.0, vec![one_shot(0, "a.wav"), one_shot(1, "show.wav")]),
+        ]);
+    }
+
+    #[bve_derive::bve_test]
+    #[test]
+    fn emitters() {
+        let sound = |path: &str, volume_function| LoadedSound {
+            path: PathBuf::from(path),
+            position: Vec3A::unit_z(),
+            volume: 0.5,
+            volume_function,
+            pitch: 1.5,
+            pitch_function: None,
+            radius: 30.0,
+            track_follower_function: None,
+        };
+        let object = LoadedAnimatedObject {
+            nodes: vec![AnimatedNode::Nested {
+                position: Vec3A::unit_x(),
+                object: LoadedAnimatedObject {
+                    sounds: vec![sound("nested.wav", None)],
+                    ..LoadedAnimatedObject::default()
+                },
+            }],
+            sounds: vec![sound("engine.wav", script("time / 10"))],
+            ..LoadedAnimatedObject::default()
+        };
+
+        let mut animation = ObjectAnimation::new(&object);
+        let mut sounds = ObjectSounds::new();
+        for &time in &[3.0, 7.0] {
+            let mut context = TestContext { time, evaluations: 0 };
+            let mut sink = RecordingSink::default();
+            let frames = animation.update(&object, None, 1.0 / 60.0, &mut context);
+            sounds.update(&object, None, frames, &mut context, &mut sink);
+            assert_eq!(sink.events, vec![
+                Event::Emitter {
+                    emitter: 0,
+                    path: PathBuf::from("nested.wav"),
+                    position: Vec3::new(1.0, 0.0, 1.0),
+                    volume: 0.5,
+                    pitch: 1.5,
+                },
+                Event::Emitter {
+                    emitter: 1,
+                    path: PathBuf::from("engine.wav"),
+                    position: Vec3::unit_z(),
+                    volume: (time / 10.0) as f32,
+                    pitch: 1.5,
+                },
+            ]);
+        }
+    }
+}
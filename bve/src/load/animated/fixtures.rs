@@ -0,0 +1,81 @@
+//! Objects and function script contexts shared by the animation and audio tests.
+
+use crate::{
+    load::{
+        animated::{AnimatedMesh, AnimatedSubObject, RotateChannel, TextureShiftChannel, TranslateChannel},
+        mesh::LoadedStaticMesh,
+    },
+    parse::{
+        animated::{RefreshRate, TextureOverride},
+        function_scripts::{parse_function_script, CompiledFunctionScript, FunctionScriptContext, Variable},
+    },
+};
+use glam::{Vec2, Vec3A};
+
+/// Gives `time` for [`Variable::Time`] and 0 for every other variable, counting how many variables were read.
+pub struct TestContext {
+    pub time: f64,
+    pub evaluations: usize,
+}
+
+impl FunctionScriptContext for TestContext {
+    fn variable(&mut self, variable: Variable, _index: Option<i64>) -> f64 {
+        self.evaluations += 1;
+        match variable {
+            Variable::Time => self.time,
+            _ => 0.0,
+        }
+    }
+}
+
+#[must_use]
+pub fn script(source: &str) -> Option<CompiledFunctionScript> {
+    let (remaining, script) = parse_function_script(source).expect("Could not parse");
+    assert_eq!(remaining, "");
+    Some(script.compiled())
+}
+
+/// Sub-object at the origin with `state_count` empty states, and no functions or sounds.
+#[must_use]
+pub fn sub_object(state_count: usize) -> AnimatedSubObject {
+    let translate = |direction| TranslateChannel {
+        direction,
+        function: None,
+    };
+    let rotate = |direction| RotateChannel {
+        direction,
+        function: None,
+        damping: None,
+    };
+    let texture_shift = |direction| TextureShiftChannel {
+        direction,
+        function: None,
+    };
+    AnimatedSubObject {
+        position: Vec3A::zero(),
+        states: vec![
+            AnimatedMesh {
+                path: None,
+                mesh: LoadedStaticMesh::default(),
+            };
+            state_count
+        ],
+        state_function: None,
+        translate: [
+            translate(Vec3A::unit_x()),
+            translate(Vec3A::unit_y()),
+            translate(Vec3A::unit_z()),
+        ],
+        rotate: [
+            rotate(Vec3A::unit_x()),
+            rotate(Vec3A::unit_y()),
+            rotate(Vec3A::unit_z()),
+        ],
+        texture_shift: [texture_shift(Vec2::unit_x()), texture_shift(Vec2::unit_y())],
+        track_follower_function: None,
+        texture_override: TextureOverride::None,
+        timetable_image: None,
+        refresh_rate: RefreshRate::EveryFrame,
+        state_change_sounds: Vec::new(),
+    }
+}
@@ -0,0 +1,699 @@
+//! Animated objects, loaded into a tree of meshes that function scripts switch between and move.
+
+use crate::{
+    filesystem::{read_convert_utf8, resolve_path},
+    load::{
+        mesh::{load_mesh_from_file, placeholder_mesh, LoadError, LoadErrorKind, LoadedStaticMesh, TextureSet},
+        route::TimetableTexture,
+    },
+    parse::{
+        animated::{
+            AnimatedObject, AnimatedSound, AnimatedStateChangeSound, Damping, Includes, ParsedAnimatedObject, PlayOn,
+            RefreshRate, TextureOverride,
+        },
+        function_scripts::{CompiledFunctionScript, ParsedFunctionScript},
+        kvp::KVPGenericWarning,
+        FileParser,
+    },
+};
+use async_std::path::{Path, PathBuf};
+use glam::{Vec2, Vec3A};
+use image::RgbaImage;
+use std::{ffi::OsStr, future::Future, io, pin::Pin, sync::Arc};
+
+pub use animation::*;
+pub use audio::*;
+
+mod animation;
+mod audio;
+#[cfg(test)]
+mod fixtures;
+
+/// A mesh along with the file it came from. Textures in the mesh are relative to the file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AnimatedMesh {
+    /// `None` for states that were left blank, which show nothing.
+    pub path: Option<PathBuf>,
+    pub mesh: LoadedStaticMesh,
+}
+
+/// Movement along `direction` by the value of `function`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TranslateChannel {
+    pub direction: Vec3A,
+    pub function: Option<CompiledFunctionScript>,
+}
+
+/// Rotation around `direction` by the value of `function`, in radians.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RotateChannel {
+    pub direction: Vec3A,
+    pub function: Option<CompiledFunctionScript>,
+    /// If set, the rotation follows the value of `function` as a damped spring instead of jumping to it.
+    pub damping: Option<Damping>,
+}
+
+/// Movement of texture coordinates along `direction` by the value of `function`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextureShiftChannel {
+    pub direction: Vec2,
+    pub function: Option<CompiledFunctionScript>,
+}
+
+/// A single `[Object]` section, with its states loaded.
+///
+/// All function scripts are [optimized](ParsedFunctionScript::optimized), then compiled.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AnimatedSubObject {
+    /// Where the object is placed after being rotated and translated.
+    pub position: Vec3A,
+    /// Meshes that `state_function` chooses between.
+    pub states: Vec<AnimatedMesh>,
+    pub state_function: Option<CompiledFunctionScript>,
+    /// X, Y, and Z channels.
+    pub translate: [TranslateChannel; 3],
+    /// X, Y, and Z channels.
+    pub rotate: [RotateChannel; 3],
+    /// X and Y channels.
+    pub texture_shift: [TextureShiftChannel; 2],
+    pub track_follower_function: Option<CompiledFunctionScript>,
+    pub texture_override: TextureOverride,
+    /// Image shown by meshes whose texture is [`TIMETABLE_TEXTURE`]. Only set by
+    /// [`LoadedAnimatedObject::set_timetable_texture`] when the timetable is generated.
+    pub timetable_image: Option<Arc<RgbaImage>>,
+    pub refresh_rate: RefreshRate,
+    /// `[StateChangeSound]` sections that follow this object.
+    pub state_change_sounds: Vec<LoadedStateChangeSound>,
+}
+
+/// A `[Sound]` section: a sound that loops for as long as the object exists.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LoadedSound {
+    pub path: PathBuf,
+    /// Relative to the animated file.
+    pub position: Vec3A,
+    pub volume: f32,
+    /// Replaces `volume` if set.
+    pub volume_function: Option<CompiledFunctionScript>,
+    pub pitch: f32,
+    /// Replaces `pitch` if set.
+    pub pitch_function: Option<CompiledFunctionScript>,
+    /// unit: m
+    pub radius: f32,
+    pub track_follower_function: Option<CompiledFunctionScript>,
+}
+
+/// A `[StateChangeSound]` section: sounds played once when the state of an object changes.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LoadedStateChangeSound {
+    /// Sound played when changing into the state with the same index. `None` for states without a sound.
+    ///
+    /// If there are any, they are played instead of `sound` when the object changes into a shown state.
+    pub state_sounds: Vec<Option<PathBuf>>,
+    /// Sound played when the object changes into a shown state if `play_on_show` is set, and when it is hidden if
+    /// `play_on_hide` is set.
+    pub sound: Option<PathBuf>,
+    /// Relative to the object, moving along with it.
+    pub position: Vec3A,
+    pub volume: f32,
+    pub pitch: f32,
+    /// unit: m
+    pub radius: f32,
+    pub play_on_show: PlayOn,
+    pub play_on_hide: PlayOn,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum AnimatedNode {
+    /// Mesh from an `[Include]` section, with the section's position already applied to its vertices.
+    Static(AnimatedMesh),
+    /// Animated file from an `[Include]` section, placed at `position`.
+    Nested {
+        position: Vec3A,
+        object: LoadedAnimatedObject,
+    },
+    /// An `[Object]` section.
+    Object(AnimatedSubObject),
+}
+
+/// A loaded animated file.
+///
+/// Problems are kept with the file they are in, so nested animated files report their own problems.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct LoadedAnimatedObject {
+    /// Included files first, then objects, each in file order.
+    pub nodes: Vec<AnimatedNode>,
+    pub sounds: Vec<LoadedSound>,
+    /// Problems parsing the animated file.
+    pub warnings: Vec<KVPGenericWarning>,
+    /// Files referred to by the animated file that couldn't be loaded. Meshes are replaced by
+    /// [`placeholder_mesh`], animated files are left out.
+    pub errors: Vec<LoadError>,
+}
+
+/// Texture name given to every mesh of an object with [`TextureOverride::Timetable`] when it is loaded. Stands for
+/// the object's [`timetable_image`](AnimatedSubObject::timetable_image), which is empty until a timetable is set.
+/// It isn't a file, so loading it as one fails and shows the placeholder texture.
+pub const TIMETABLE_TEXTURE: &str = "<timetable>";
+
+impl LoadedAnimatedObject {
+    /// Shows `texture` on every mesh of objects with [`TextureOverride::Timetable`], including those in nested files.
+    ///
+    /// Meant to be called whenever [`RouteTimetables::texture_at`](crate::load::route::RouteTimetables::texture_at)
+    /// changes, with the texture it gives. Images are used by their path. Generated timetables are stored in
+    /// [`AnimatedSubObject::timetable_image`], and the meshes use [`TIMETABLE_TEXTURE`].
+    pub fn set_timetable_texture(&mut self, texture: &TimetableTexture<'_>) {
+        let (name, image) = match texture {
+            TimetableTexture::Image(path) => (path.to_string_lossy().into_owned(), None),
+            TimetableTexture::Text(image) => (String::from(TIMETABLE_TEXTURE), Some(image)),
+        };
+        self.set_timetable(&name, image);
+    }
+
+    fn set_timetable(&mut self, name: &str, image: Option<&Arc<RgbaImage>>) {
+        for node in &mut self.nodes {
+            match node {
+                AnimatedNode::Nested { object, .. } => object.set_timetable(name, image),
+                AnimatedNode::Object(sub_object) if sub_object.texture_override == TextureOverride::Timetable => {
+                    for state in &mut sub_object.states {
+                        override_texture(&mut state.mesh, name);
+                    }
+                    sub_object.timetable_image = image.cloned();
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+/// Loads an animated file and every file it refers to.
+///
+/// # Errors
+///
+/// Returns Err if the animated file itself could not be loaded. Problems with the files it refers to are reported
+/// inside the object.
+pub async fn load_animated_from_file(file: impl AsRef<Path>) -> Result<LoadedAnimatedObject, LoadError> {
+    let path = file.as_ref();
+    let path = path.canonicalize().await.map_err(|err| LoadError::from_io(path, err))?;
+    load_animated(&path, &mut Vec::new()).await
+}
+
+/// `ancestors` are the animated files currently being loaded, which including again would never end.
+fn load_animated<'a>(
+    path: &'a Path,
+    ancestors: &'a mut Vec<PathBuf>,
+) -> Pin<Box<dyn Future<Output = Result<LoadedAnimatedObject, LoadError>> + 'a>> {
+    Box::pin(async move {
+        let source = read_convert_utf8(path)
+            .await
+            .map_err(|err| LoadError::from_io(path, err))?;
+        let parsed = ParsedAnimatedObject::parse_from(&source);
+        let directory = match path.parent() {
+            Some(parent) if !parent.as_os_str().is_empty() => parent,
+            _ => Path::new("."),
+        };
+
+        let mut loaded = LoadedAnimatedObject {
+            nodes: Vec::new(),
+            sounds: Vec::new(),
+            warnings: parsed.warnings,
+            errors: Vec::new(),
+        };
+
+        ancestors.push(path.to_path_buf());
+        for includes in &parsed.output.includes {
+            load_includes(directory, includes, ancestors, &mut loaded).await;
+        }
+        ancestors.pop();
+
+        let mut sub_objects = Vec::with_capacity(parsed.output.objects.len());
+        for object in parsed.output.objects {
+            sub_objects.push(load_object(directory, object, &mut loaded.errors).await);
+        }
+
+        // Sounds before the first object have nothing to play for, and are left out
+        let owners = &parsed.output.change_state_sound_objects;
+        for (sound, &owner) in parsed.output.change_state_sounds.iter().zip(owners) {
+            if let Some(sub_object) = owner.and_then(|owner| sub_objects.get_mut(owner)) {
+                let sound = load_state_change_sound(directory, sound, &mut loaded.errors).await;
+                sub_object.state_change_sounds.push(sound);
+            }
+        }
+        loaded.nodes.extend(sub_objects.into_iter().map(AnimatedNode::Object));
+
+        for sound in &parsed.output.sounds {
+            if let Some(path) = resolve_sound(directory, &sound.filename, &mut loaded.errors).await {
+                loaded.sounds.push(load_sound(path, sound));
+            }
+        }
+
+        Ok(loaded)
+    })
+}
+
+async fn load_includes(
+    directory: &Path,
+    includes: &Includes,
+    ancestors: &mut Vec<PathBuf>,
+    loaded: &mut LoadedAnimatedObject,
+) {
+    for file in includes.files.iter().filter(|file| !file.is_empty()) {
+        let path = match resolve_file(directory, file).await {
+            Ok(path) => path,
+            Err(error) => {
+                // Only meshes have a placeholder, missing animated files are left out
+                if !is_animated_file(&error.path) {
+                    loaded.nodes.push(AnimatedNode::Static(offset_mesh(
+                        placeholder_mesh_from(directory, file),
+                        includes.position,
+                    )));
+                }
+                loaded.errors.push(error);
+                continue;
+            }
+        };
+
+        if !is_animated_file(&path) {
+            let mesh = load_state(path, &mut loaded.errors).await;
+            loaded
+                .nodes
+                .push(AnimatedNode::Static(offset_mesh(mesh, includes.position)));
+            continue;
+        }
+
+        if ancestors.contains(&path) {
+            loaded.errors.push(LoadError {
+                path,
+                kind: LoadErrorKind::RecursiveInclude,
+            });
+            continue;
+        }
+        match load_animated(&path, ancestors).await {
+            Ok(object) => loaded.nodes.push(AnimatedNode::Nested {
+                position: includes.position,
+                object,
+            }),
+            Err(error) => loaded.errors.push(error),
+        }
+    }
+}
+
+async fn load_object(directory: &Path, object: AnimatedObject, errors: &mut Vec<LoadError>) -> AnimatedSubObject {
+    let mut states = Vec::with_capacity(object.states.len());
+    for state in &object.states {
+        let state = state.trim();
+        let mesh = if state.is_empty() {
+            AnimatedMesh {
+                path: None,
+                mesh: LoadedStaticMesh::default(),
+            }
+        } else {
+            match resolve_file(directory, state).await {
+                Ok(path) => load_state(path, errors).await,
+                Err(error) => {
+                    errors.push(error);
+                    placeholder_mesh_from(directory, state)
+                }
+            }
+        };
+        states.push(mesh);
+    }
+
+    if object.texture_override == TextureOverride::Timetable {
+        for state in &mut states {
+            override_texture(&mut state.mesh, TIMETABLE_TEXTURE);
+        }
+    }
+
+    let optimize = |script: Option<ParsedFunctionScript>| script.map(|script| script.optimized().compiled());
+    let translate = |direction, function| TranslateChannel {
+        direction,
+        function: optimize(function),
+    };
+    let rotate = |direction, function, damping| RotateChannel {
+        direction,
+        function: optimize(function),
+        damping,
+    };
+    let texture_shift = |direction, function| TextureShiftChannel {
+        direction,
+        function: optimize(function),
+    };
+
+    AnimatedSubObject {
+        position: object.position,
+        states,
+        state_function: optimize(object.state_function),
+        translate: [
+            translate(object.translate_x_direction, object.translate_x_function),
+            translate(object.translate_y_direction, object.translate_y_function),
+            translate(object.translate_z_direction, object.translate_z_function),
+        ],
+        rotate: [
+            rotate(
+                object.rotate_x_direction,
+                object.rotate_x_function,
+                object.rotate_x_damping,
+            ),
+            rotate(
+                object.rotate_y_direction,
+                object.rotate_y_function,
+                object.rotate_y_damping,
+            ),
+            rotate(
+                object.rotate_z_direction,
+                object.rotate_z_function,
+                object.rotate_z_damping,
+            ),
+        ],
+        texture_shift: [
+            texture_shift(object.texture_shift_x_direction, object.texture_shift_x_function),
+            texture_shift(object.texture_shift_y_direction, object.texture_shift_y_function),
+        ],
+        track_follower_function: optimize(object.track_follower_function),
+        texture_override: object.texture_override,
+        timetable_image: None,
+        refresh_rate: object.refresh_rate,
+        state_change_sounds: Vec::new(),
+    }
+}
+
+/// Replaces every texture of `mesh` with `texture`, dropping light maps.
+fn override_texture(mesh: &mut LoadedStaticMesh, texture: &str) {
+    mesh.textures = TextureSet::new();
+    let texture_id = mesh.textures.add(texture);
+    for part in &mut mesh.meshes {
+        part.texture.texture_id = Some(texture_id);
+        part.texture.light_map_id = None;
+    }
+}
+
+fn load_sound(path: PathBuf, sound: &AnimatedSound) -> LoadedSound {
+    let optimize = |script: &Option<ParsedFunctionScript>| script.as_ref().map(|script| script.optimized().compiled());
+    LoadedSound {
+        path,
+        position: sound.position,
+        volume: sound.volume,
+        volume_function: optimize(&sound.volume_function),
+        pitch: sound.pitch,
+        pitch_function: optimize(&sound.pitch_function),
+        radius: sound.radius,
+        track_follower_function: optimize(&sound.track_follower_function),
+    }
+}
+
+/// Sound files are only found, not loaded. Files that can't be found are reported, and left silent.
+async fn resolve_sound(directory: &Path, file: &str, errors: &mut Vec<LoadError>) -> Option<PathBuf> {
+    let file = file.trim();
+    if file.is_empty() {
+        return None;
+    }
+    match resolve_file(directory, file).await {
+        Ok(path) => Some(path),
+        Err(error) => {
+            errors.push(error);
+            None
+        }
+    }
+}
+
+async fn load_state_change_sound(
+    directory: &Path,
+    sound: &AnimatedStateChangeSound,
+    errors: &mut Vec<LoadError>,
+) -> LoadedStateChangeSound {
+    let mut state_sounds = Vec::with_capacity(sound.filenames.len());
+    for file in &sound.filenames {
+        state_sounds.push(resolve_sound(directory, file, errors).await);
+    }
+    LoadedStateChangeSound {
+        state_sounds,
+        sound: resolve_sound(directory, &sound.filename, errors).await,
+        position: sound.position,
+        volume: sound.volume,
+        pitch: sound.pitch,
+        radius: sound.radius,
+        play_on_show: sound.play_on_show.clone(),
+        play_on_hide: sound.play_on_hide.clone(),
+    }
+}
+
+/// Loads a mesh, replacing it with a placeholder if it fails to load.
+async fn load_state(path: PathBuf, errors: &mut Vec<LoadError>) -> AnimatedMesh {
+    let mesh = match load_mesh_from_file(&path).await {
+        Ok(mesh) => mesh,
+        Err(error) => {
+            errors.push(error);
+            placeholder_mesh()
+        }
+    };
+    AnimatedMesh { path: Some(path), mesh }
+}
+
+/// Finds `file` in `directory`, ignoring case. Animated files are written for Windows, so `\` separates directories.
+async fn resolve_file(directory: &Path, file: &str) -> Result<PathBuf, LoadError> {
+    let relative = PathBuf::from(file.replace('\\', "/"));
+    match resolve_path(directory, relative.clone()).await {
+        Some(path) => Ok(path),
+        None => Err(LoadError::from_io(
+            directory.join(relative),
+            io::Error::new(io::ErrorKind::NotFound, "file not found"),
+        )),
+    }
+}
+
+fn placeholder_mesh_from(directory: &Path, file: &str) -> AnimatedMesh {
+    AnimatedMesh {
+        path: Some(directory.join(file.replace('\\', "/"))),
+        mesh: placeholder_mesh(),
+    }
+}
+
+fn is_animated_file(path: &Path) -> bool {
+    path.extension()
+        .map(OsStr::to_string_lossy)
+        .map_or(false, |ext| ext.eq_ignore_ascii_case("animated"))
+}
+
+fn offset_mesh(mut mesh: AnimatedMesh, offset: Vec3A) -> AnimatedMesh {
+    for vertex in mesh.mesh.meshes.iter_mut().flat_map(|mesh| mesh.vertices.iter_mut()) {
+        vertex.position += offset;
+    }
+    mesh
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        load::{
+            animated::{load_animated_from_file, AnimatedNode, LoadedAnimatedObject, TIMETABLE_TEXTURE},
+            mesh::{load_mesh_from_file, placeholder_mesh, LoadErrorKind},
+            route::TimetableTexture,
+        },
+        parse::animated::RefreshRate,
+    };
+    use async_std::{fs, path::Path};
+    use glam::Vec3A;
+    use image::RgbaImage;
+    use std::sync::Arc;
+
+    const MESH: &str = "CreateMeshBuilder\nCube, 0.5, 0.5, 0.5\n";
+
+    const ROOT: &str = indoc::indoc!(
+        r#"
+        [Include]
+        Static.csv
+        Position = 0, 1, 0
+
+        [Include]
+        nested.animated
+        Position = 2, 0, 0
+
+        [Include]
+        missing.csv
+        missing.animated
+
+        [Object]
+        States = static.csv, , MISSING.b3d
+        Position = 1, 0, 0
+        RotateXFunction = 1 + 1
+        TranslateYFunctionRPN = time 2 *
+        RefreshRate = 0.5
+    "#
+    );
+
+    const NESTED: &str = indoc::indoc!(
+        r#"
+        [Include]
+        Static.csv
+        root.animated
+    "#
+    );
+
+    #[bve_derive::bve_test]
+    #[async_std::test]
+    async fn load_tree() {
+        let directory = std::env::temp_dir().join(format!("bve-animated-test-{}", std::process::id()));
+        fs::create_dir_all(&directory)
+            .await
+            .expect("Could not create directory");
+        fs::write(directory.join("static.csv"), MESH)
+            .await
+            .expect("Could not write");
+        fs::write(directory.join("root.animated"), ROOT)
+            .await
+            .expect("Could not write");
+        fs::write(directory.join("nested.animated"), NESTED)
+            .await
+            .expect("Could not write");
+
+        let object = load_animated_from_file(directory.join("root.animated"))
+            .await
+            .expect("Could not load");
+        let mesh = load_mesh_from_file(directory.join("static.csv"))
+            .await
+            .expect("Could not load mesh");
+        assert_eq!(object.warnings, vec![]);
+        assert_eq!(object.nodes.len(), 4);
+
+        // Static include is moved by its position
+        match &object.nodes[0] {
+            AnimatedNode::Static(included) => {
+                let offset: Vec<Vec3A> = included.mesh.meshes[0].vertices.iter().map(|v| v.position).collect();
+                let expected: Vec<Vec3A> = mesh.meshes[0]
+                    .vertices
+                    .iter()
+                    .map(|v| v.position + Vec3A::unit_y())
+                    .collect();
+                assert_eq!(offset, expected);
+            }
+            node => panic!("Expected static mesh, found {:?}", node),
+        }
+
+        // Nested file can't include the file including it
+        match &object.nodes[1] {
+            AnimatedNode::Nested { position, object } => {
+                assert_eq!(*position, Vec3A::new(2.0, 0.0, 0.0));
+                assert_eq!(object.nodes.len(), 1);
+                assert_eq!(object.errors.len(), 1);
+                assert!(matches!(object.errors[0].kind, LoadErrorKind::RecursiveInclude));
+            }
+            node => panic!("Expected nested object, found {:?}", node),
+        }
+
+        // Missing meshes are replaced with placeholders, missing animated files are left out, and each is reported
+        // once
+        match &object.nodes[2] {
+            AnimatedNode::Static(included) => assert_eq!(included.mesh, placeholder_mesh()),
+            node => panic!("Expected static mesh, found {:?}", node),
+        }
+        assert_eq!(object.errors.len(), 3);
+        assert!(
+            object
+                .errors
+                .iter()
+                .all(|error| matches!(error.kind, LoadErrorKind::Unreadable { .. }))
+        );
+
+        match &object.nodes[3] {
+            AnimatedNode::Object(sub_object) => {
+                assert_eq!(sub_object.position, Vec3A::unit_x());
+                assert_eq!(sub_object.states.len(), 3);
+                assert_eq!(sub_object.states[0].mesh, mesh);
+                assert_eq!(sub_object.states[1].path, None);
+                assert!(sub_object.states[1].mesh.meshes.is_empty());
+                assert_eq!(sub_object.states[2].mesh, placeholder_mesh());
+                let rotate_x = sub_object.rotate[0].function.as_ref().expect("No rotate function");
+                assert_eq!(rotate_x.script().constant_value(), Some(2.0));
+                assert_eq!(
+                    sub_object.translate[1].function.as_ref().map(ToString::to_string),
+                    Some(String::from("time * 2"))
+                );
+                assert_eq!(sub_object.refresh_rate, RefreshRate::Seconds(0.5));
+            }
+            node => panic!("Expected object, found {:?}", node),
+        }
+
+        fs::remove_dir_all(&directory)
+            .await
+            .expect("Could not remove directory");
+    }
+
+    #[bve_derive::bve_test]
+    #[async_std::test]
+    async fn timetable_override() {
+        let directory = std::env::temp_dir().join(format!("bve-animated-timetable-test-{}", std::process::id()));
+        fs::create_dir_all(&directory)
+            .await
+            .expect("Could not create directory");
+        fs::write(directory.join("static.csv"), MESH)
+            .await
+            .expect("Could not write");
+        let source = "[Object]\nStates = static.csv\nTextureOverride = Timetable\n[Object]\nStates = static.csv\n";
+        fs::write(directory.join("timetable.animated"), source)
+            .await
+            .expect("Could not write");
+
+        let mut object = load_animated_from_file(directory.join("timetable.animated"))
+            .await
+            .expect("Could not load");
+        let textures = |object: &LoadedAnimatedObject, index: usize| match &object.nodes[index] {
+            AnimatedNode::Object(sub_object) => {
+                let mesh = &sub_object.states[0].mesh;
+                assert!(!mesh.meshes.is_empty());
+                mesh.meshes
+                    .iter()
+                    .map(|part| part.texture.texture_id.and_then(|id| mesh.textures.lookup(id)))
+                    .map(|name| name.map(String::from))
+                    .collect::<Vec<_>>()
+            }
+            node => panic!("Expected object, found {:?}", node),
+        };
+        assert!(
+            textures(&object, 0)
+                .iter()
+                .all(|name| name.as_deref() == Some(TIMETABLE_TEXTURE))
+        );
+        assert!(textures(&object, 1).iter().all(Option::is_none));
+
+        let timetable_image = |object: &LoadedAnimatedObject| match &object.nodes[0] {
+            AnimatedNode::Object(sub_object) => sub_object.timetable_image.clone(),
+            node => panic!("Expected object, found {:?}", node),
+        };
+        assert_eq!(timetable_image(&object), None);
+
+        let path = directory.join("timetable.png");
+        object.set_timetable_texture(&TimetableTexture::Image(Path::new(&path)));
+        let name = path.to_string_lossy();
+        assert!(
+            textures(&object, 0)
+                .iter()
+                .all(|texture| texture.as_deref() == Some(&*name))
+        );
+        assert!(textures(&object, 1).iter().all(Option::is_none));
+        assert_eq!(timetable_image(&object), None);
+
+        // Generated timetables are stored with the object
+        let generated = Arc::new(RgbaImage::new(1, 1));
+        object.set_timetable_texture(&TimetableTexture::Text(Arc::clone(&generated)));
+        assert!(
+            textures(&object, 0)
+                .iter()
+                .all(|texture| texture.as_deref() == Some(TIMETABLE_TEXTURE))
+        );
+        assert_eq!(timetable_image(&object), Some(generated));
+
+        fs::remove_dir_all(&directory)
+            .await
+            .expect("Could not remove directory");
+    }
+
+    #[bve_derive::bve_test]
+    #[async_std::test]
+    async fn missing_file() {
+        let error = load_animated_from_file("does/not/exist.animated")
+            .await
+            .expect_err("Loaded missing file");
+        assert!(matches!(error.kind, LoadErrorKind::Unreadable { .. }));
+    }
+}
@@ -0,0 +1,105 @@
+use crate::{
+    load::mesh::LoadedStaticMesh,
+    parse::{
+        mesh::{
+            instructions::{
+                write_instructions, AddFace, AddVertex, CreateMeshBuilder, Instruction, InstructionData, Sides,
+            },
+            FileType,
+        },
+        Span,
+    },
+};
+use glam::Vec2;
+use itertools::Itertools;
+
+/// Bakes the final geometry of `loaded` into instructions which only use `CreateMeshBuilder`, `AddVertex`, and
+/// `AddFace`/`AddFace2`, one mesh builder per mesh.
+///
+/// All transformations and compound shapes have already been applied. Materials and texture coordinates are not
+/// part of the output.
+#[must_use]
+pub fn bake_instructions(loaded: &LoadedStaticMesh) -> Vec<Instruction> {
+    let mut instructions = Vec::new();
+    let mut push = |data| {
+        instructions.push(Instruction {
+            span: Span::none(),
+            data,
+        })
+    };
+    for mesh in &loaded.meshes {
+        push(InstructionData::CreateMeshBuilder(CreateMeshBuilder));
+        for vertex in &mesh.vertices {
+            push(InstructionData::AddVertex(AddVertex {
+                position: vertex.position,
+                normal: vertex.normal,
+                texture_coord: Vec2::zero(),
+            }));
+        }
+        for (&i1, &i2, &i3) in mesh.indices.iter().tuples() {
+            let double_sided = [i1, i2, i3].iter().any(|&i| mesh.vertices[i].double_sided);
+            push(InstructionData::AddFace(AddFace {
+                indexes: vec![i1, i2, i3],
+                sides: if double_sided { Sides::Two } else { Sides::One },
+            }));
+        }
+    }
+    instructions
+}
+
+/// Bakes `mesh` into a minimal `.csv` file. See [`bake_instructions`].
+#[must_use]
+pub fn bake_to_csv(mesh: &LoadedStaticMesh) -> String {
+    write_instructions(&bake_instructions(mesh), FileType::CSV)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        load::mesh::{bake_to_csv, generate_meshes},
+        parse::mesh::{
+            instructions::{create_instructions, post_process},
+            FileType,
+        },
+    };
+
+    #[bve_derive::bve_test]
+    #[test]
+    fn bake() {
+        let source = indoc::indoc!(
+            r#"
+            CreateMeshBuilder
+            Cube, 1, 1, 1
+            Translate, 0, 2, 0
+            SetColor, 255, 0, 0
+            CreateMeshBuilder
+            AddVertex, 0, 0, 0
+            AddVertex, 1, 0, 0
+            AddVertex, 1, 0, 1
+            AddVertex, 0, 0, 1
+            AddFace2, 0, 1, 2, 3
+        "#
+        );
+        let original = generate_meshes(post_process(create_instructions(source, FileType::CSV)));
+        let baked = bake_to_csv(&original);
+
+        assert!(baked.lines().all(|line| line == "CreateMeshBuilder"
+            || line.starts_with("AddVertex, ")
+            || line.starts_with("AddFace, ")
+            || line.starts_with("AddFace2, ")));
+
+        let reloaded = generate_meshes(post_process(create_instructions(&baked, FileType::CSV)));
+        assert_eq!(reloaded.errors, vec![]);
+        assert_eq!(reloaded.meshes.len(), original.meshes.len());
+        for (reloaded, original) in reloaded.meshes.iter().zip(&original.meshes) {
+            assert_eq!(reloaded.indices.len(), original.indices.len());
+            let positions = |mesh: &crate::load::mesh::Mesh| {
+                mesh.indices
+                    .iter()
+                    .map(|&i| (mesh.vertices[i].position, mesh.vertices[i].double_sided))
+                    .collect::<Vec<_>>()
+            };
+            assert_eq!(positions(reloaded), positions(original));
+        }
+    }
+}
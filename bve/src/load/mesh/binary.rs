@@ -0,0 +1,534 @@
+//! Compact binary format for a [`LoadedStaticMesh`], used by the [`DiskMeshCache`](super::DiskMeshCache).
+//!
+//! All numbers are little endian. The file starts with `BVEM` and [`BINARY_FORMAT_VERSION`]; files with any other
+//! version are rejected. The geometry, materials, texture names, warnings, and errors are all stored.
+
+use crate::{
+    load::mesh::{LoadedStaticMesh, Mesh, MeshText, Texture, TextureSet, Vertex},
+    parse::{
+        mesh::{
+            BlendMode, Glow, GlowAttenuationMode, MeshError, MeshErrorKind, MeshWarning, MeshWarningKind, WrapMode,
+        },
+        Span,
+    },
+    ColorU8RGB, ColorU8RGBA,
+};
+use glam::{Vec2, Vec3A};
+use std::convert::TryInto;
+
+const MAGIC: &[u8; 4] = b"BVEM";
+/// Must be increased whenever the layout of the format changes.
+pub const BINARY_FORMAT_VERSION: u32 = 2;
+
+/// Serializes the geometry, materials, textures, warnings, and errors of `mesh`.
+#[must_use]
+pub fn serialize_mesh(mesh: &LoadedStaticMesh) -> Vec<u8> {
+    let mut w = Writer(Vec::new());
+    w.0.extend_from_slice(MAGIC);
+    w.u32(BINARY_FORMAT_VERSION);
+
+    w.size(mesh.textures.len());
+    for texture in mesh.textures.iter() {
+        w.string(texture);
+    }
+
+    w.size(mesh.meshes.len());
+    for m in &mesh.meshes {
+        w.size(m.vertices.len());
+        for v in &m.vertices {
+            w.vec3(v.position);
+            w.vec3(v.normal);
+            w.rgba(v.color);
+            w.vec2(v.coord);
+            w.bool(v.double_sided);
+        }
+        w.size(m.indices.len());
+        for &i in &m.indices {
+            w.size(i);
+        }
+
+        w.option(m.texture.texture_id, Writer::size);
+        w.option(m.texture.decal_transparent_color, Writer::rgb);
+        w.rgb(m.texture.emission_color);
+        w.option(m.texture.light_map_id, Writer::size);
+        w.option(m.texture.wrap_mode, |w, mode| {
+            w.u8(match mode {
+                WrapMode::ClampClamp => 0,
+                WrapMode::ClampRepeat => 1,
+                WrapMode::RepeatClamp => 2,
+                WrapMode::RepeatRepeat => 3,
+            })
+        });
+        w.bool(m.texture.crossfading);
+
+        w.rgba(m.color);
+        w.u8(match m.blend_mode {
+            BlendMode::Normal => 0,
+            BlendMode::Additive => 1,
+        });
+        w.u8(match m.glow.attenuation_mode {
+            GlowAttenuationMode::DivideExponent2 => 0,
+            GlowAttenuationMode::DivideExponent4 => 1,
+        });
+        w.u16(m.glow.half_distance);
+
+        w.option(m.text.as_ref(), |w, text| {
+            w.string(&text.text);
+            w.rgb(text.color);
+            w.rgb(text.background_color);
+            w.vec2(text.padding);
+            w.option(text.font.as_deref(), Writer::string);
+        });
+    }
+
+    w.size(mesh.warnings.len());
+    for warning in &mesh.warnings {
+        w.span(warning.location);
+        match &warning.kind {
+            MeshWarningKind::UselessInstruction { name } => {
+                w.u8(0);
+                w.string(name);
+            }
+            MeshWarningKind::UnsupportedXTemplate { name } => {
+                w.u8(1);
+                w.string(name);
+            }
+            MeshWarningKind::DuplicateFaceIndex { idx } => {
+                w.u8(2);
+                w.size(*idx);
+            }
+            MeshWarningKind::DegenerateTriangle => w.u8(3),
+            MeshWarningKind::NonFinitePosition => w.u8(4),
+            MeshWarningKind::ZeroLengthNormal => w.u8(5),
+            MeshWarningKind::MalformedEncoding { encoding } => {
+                w.u8(6);
+                w.string(encoding);
+            }
+        }
+    }
+
+    w.size(mesh.errors.len());
+    for error in &mesh.errors {
+        w.span(error.location);
+        match &error.kind {
+            MeshErrorKind::UTF8 { column } => {
+                w.u8(0);
+                w.option(*column, Writer::u64);
+            }
+            MeshErrorKind::OutOfBounds { idx } => {
+                w.u8(1);
+                w.size(*idx);
+            }
+            MeshErrorKind::UnknownInstruction { name } => {
+                w.u8(2);
+                w.string(name);
+            }
+            MeshErrorKind::GenericCSV { msg, msg_english } => {
+                w.u8(3);
+                w.string(msg);
+                w.string(msg_english);
+            }
+            MeshErrorKind::UnknownCSV => w.u8(4),
+            MeshErrorKind::UnreadableMaterialLibrary { file, error } => {
+                w.u8(5);
+                w.string(file);
+                w.string(error);
+            }
+            MeshErrorKind::InvalidXHeader => w.u8(6),
+            MeshErrorKind::UnexpectedXToken { token } => {
+                w.u8(7);
+                w.string(token);
+            }
+            MeshErrorKind::UnexpectedXEnd { template } => {
+                w.u8(8);
+                w.string(template);
+            }
+            MeshErrorKind::UnknownXMaterial { name } => {
+                w.u8(9);
+                w.string(name);
+            }
+        }
+    }
+
+    w.0
+}
+
+/// Deserializes a mesh written by [`serialize_mesh`]. Returns `None` if the data is corrupt, truncated, or from a
+/// different version of the format.
+#[must_use]
+pub fn deserialize_mesh(bytes: &[u8]) -> Option<LoadedStaticMesh> {
+    let mut r = Reader { bytes };
+    if r.take(4)? != MAGIC || r.u32()? != BINARY_FORMAT_VERSION {
+        return None;
+    }
+
+    let mut textures = TextureSet::new();
+    for _ in 0..r.size()? {
+        textures.add(&r.string()?);
+    }
+
+    let mesh_count = r.size()?;
+    let mut meshes = Vec::with_capacity(mesh_count.min(bytes.len()));
+    for _ in 0..mesh_count {
+        let vertex_count = r.size()?;
+        let mut vertices = Vec::with_capacity(vertex_count.min(bytes.len()));
+        for _ in 0..vertex_count {
+            vertices.push(Vertex {
+                position: r.vec3()?,
+                normal: r.vec3()?,
+                color: r.rgba()?,
+                coord: r.vec2()?,
+                double_sided: r.bool()?,
+            });
+        }
+        let index_count = r.size()?;
+        let mut indices = Vec::with_capacity(index_count.min(bytes.len()));
+        for _ in 0..index_count {
+            let index = r.size()?;
+            if index >= vertices.len() {
+                return None;
+            }
+            indices.push(index);
+        }
+
+        let texture = Texture {
+            texture_id: r.option(Reader::size)?,
+            decal_transparent_color: r.option(Reader::rgb)?,
+            emission_color: r.rgb()?,
+            light_map_id: r.option(Reader::size)?,
+            wrap_mode: r.option(|r| match r.u8()? {
+                0 => Some(WrapMode::ClampClamp),
+                1 => Some(WrapMode::ClampRepeat),
+                2 => Some(WrapMode::RepeatClamp),
+                3 => Some(WrapMode::RepeatRepeat),
+                _ => None,
+            })?,
+            crossfading: r.bool()?,
+        };
+
+        let color = r.rgba()?;
+        let blend_mode = match r.u8()? {
+            0 => BlendMode::Normal,
+            1 => BlendMode::Additive,
+            _ => return None,
+        };
+        let attenuation_mode = match r.u8()? {
+            0 => GlowAttenuationMode::DivideExponent2,
+            1 => GlowAttenuationMode::DivideExponent4,
+            _ => return None,
+        };
+        let half_distance = r.u16()?;
+
+        let text = r.option(|r| {
+            Some(MeshText {
+                text: r.string()?,
+                color: r.rgb()?,
+                background_color: r.rgb()?,
+                padding: r.vec2()?,
+                font: r.option(Reader::string)?,
+            })
+        })?;
+
+        meshes.push(Mesh {
+            vertices,
+            indices,
+            texture,
+            color,
+            blend_mode,
+            glow: Glow {
+                attenuation_mode,
+                half_distance,
+            },
+            text,
+        });
+    }
+
+    let warning_count = r.size()?;
+    let mut warnings = Vec::with_capacity(warning_count.min(bytes.len()));
+    for _ in 0..warning_count {
+        let location = r.span()?;
+        let kind = match r.u8()? {
+            0 => MeshWarningKind::UselessInstruction { name: r.string()? },
+            1 => MeshWarningKind::UnsupportedXTemplate { name: r.string()? },
+            2 => MeshWarningKind::DuplicateFaceIndex { idx: r.size()? },
+            3 => MeshWarningKind::DegenerateTriangle,
+            4 => MeshWarningKind::NonFinitePosition,
+            5 => MeshWarningKind::ZeroLengthNormal,
+            6 => MeshWarningKind::MalformedEncoding { encoding: r.string()? },
+            _ => return None,
+        };
+        warnings.push(MeshWarning { kind, location });
+    }
+
+    let error_count = r.size()?;
+    let mut errors = Vec::with_capacity(error_count.min(bytes.len()));
+    for _ in 0..error_count {
+        let location = r.span()?;
+        let kind = match r.u8()? {
+            0 => MeshErrorKind::UTF8 {
+                column: r.option(Reader::u64)?,
+            },
+            1 => MeshErrorKind::OutOfBounds { idx: r.size()? },
+            2 => MeshErrorKind::UnknownInstruction { name: r.string()? },
+            3 => MeshErrorKind::GenericCSV {
+                msg: r.string()?,
+                msg_english: r.string()?,
+            },
+            4 => MeshErrorKind::UnknownCSV,
+            5 => MeshErrorKind::UnreadableMaterialLibrary {
+                file: r.string()?,
+                error: r.string()?,
+            },
+            6 => MeshErrorKind::InvalidXHeader,
+            7 => MeshErrorKind::UnexpectedXToken { token: r.string()? },
+            8 => MeshErrorKind::UnexpectedXEnd { template: r.string()? },
+            9 => MeshErrorKind::UnknownXMaterial { name: r.string()? },
+            _ => return None,
+        };
+        errors.push(MeshError { kind, location });
+    }
+
+    if !r.bytes.is_empty() {
+        return None;
+    }
+
+    Some(LoadedStaticMesh {
+        meshes,
+        textures,
+        warnings,
+        errors,
+    })
+}
+
+struct Writer(Vec<u8>);
+
+impl Writer {
+    fn u8(&mut self, value: u8) {
+        self.0.push(value);
+    }
+
+    fn u16(&mut self, value: u16) {
+        self.0.extend_from_slice(&value.to_le_bytes());
+    }
+
+    fn u32(&mut self, value: u32) {
+        self.0.extend_from_slice(&value.to_le_bytes());
+    }
+
+    fn u64(&mut self, value: u64) {
+        self.0.extend_from_slice(&value.to_le_bytes());
+    }
+
+    /// Lengths and indices are stored as u32 regardless of platform.
+    fn size(&mut self, value: usize) {
+        self.u32(value as u32);
+    }
+
+    fn f32(&mut self, value: f32) {
+        self.0.extend_from_slice(&value.to_le_bytes());
+    }
+
+    fn bool(&mut self, value: bool) {
+        self.u8(value as u8);
+    }
+
+    fn string(&mut self, value: &str) {
+        self.size(value.len());
+        self.0.extend_from_slice(value.as_bytes());
+    }
+
+    fn vec2(&mut self, value: Vec2) {
+        self.f32(value.x());
+        self.f32(value.y());
+    }
+
+    fn vec3(&mut self, value: Vec3A) {
+        self.f32(value.x());
+        self.f32(value.y());
+        self.f32(value.z());
+    }
+
+    fn rgb(&mut self, value: ColorU8RGB) {
+        self.0.extend_from_slice(&value.into_array());
+    }
+
+    fn rgba(&mut self, value: ColorU8RGBA) {
+        self.0.extend_from_slice(&value.into_array());
+    }
+
+    fn span(&mut self, value: Span) {
+        self.option(value.line, Self::u64);
+    }
+
+    fn option<T>(&mut self, value: Option<T>, write: impl FnOnce(&mut Self, T)) {
+        match value {
+            Some(value) => {
+                self.bool(true);
+                write(self, value);
+            }
+            None => self.bool(false),
+        }
+    }
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, count: usize) -> Option<&'a [u8]> {
+        if self.bytes.len() < count {
+            return None;
+        }
+        let (taken, rest) = self.bytes.split_at(count);
+        self.bytes = rest;
+        Some(taken)
+    }
+
+    fn u8(&mut self) -> Option<u8> {
+        Some(self.take(1)?[0])
+    }
+
+    fn u16(&mut self) -> Option<u16> {
+        Some(u16::from_le_bytes(self.take(2)?.try_into().ok()?))
+    }
+
+    fn u32(&mut self) -> Option<u32> {
+        Some(u32::from_le_bytes(self.take(4)?.try_into().ok()?))
+    }
+
+    fn u64(&mut self) -> Option<u64> {
+        Some(u64::from_le_bytes(self.take(8)?.try_into().ok()?))
+    }
+
+    fn size(&mut self) -> Option<usize> {
+        Some(self.u32()? as usize)
+    }
+
+    fn f32(&mut self) -> Option<f32> {
+        Some(f32::from_le_bytes(self.take(4)?.try_into().ok()?))
+    }
+
+    fn bool(&mut self) -> Option<bool> {
+        match self.u8()? {
+            0 => Some(false),
+            1 => Some(true),
+            _ => None,
+        }
+    }
+
+    fn string(&mut self) -> Option<String> {
+        let len = self.size()?;
+        String::from_utf8(self.take(len)?.to_vec()).ok()
+    }
+
+    fn vec2(&mut self) -> Option<Vec2> {
+        Some(Vec2::new(self.f32()?, self.f32()?))
+    }
+
+    fn vec3(&mut self) -> Option<Vec3A> {
+        Some(Vec3A::new(self.f32()?, self.f32()?, self.f32()?))
+    }
+
+    fn rgb(&mut self) -> Option<ColorU8RGB> {
+        Some(ColorU8RGB::new(self.u8()?, self.u8()?, self.u8()?))
+    }
+
+    fn rgba(&mut self) -> Option<ColorU8RGBA> {
+        Some(ColorU8RGBA::new(self.u8()?, self.u8()?, self.u8()?, self.u8()?))
+    }
+
+    fn span(&mut self) -> Option<Span> {
+        Some(Span {
+            line: self.option(Self::u64)?,
+        })
+    }
+
+    /// The outer `Option` is `None` if reading failed, the inner is the value read.
+    fn option<T>(&mut self, read: impl FnOnce(&mut Self) -> Option<T>) -> Option<Option<T>> {
+        if self.bool()? {
+            Some(Some(read(self)?))
+        } else {
+            Some(None)
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        load::mesh::{
+            binary::{deserialize_mesh, serialize_mesh},
+            generate_meshes,
+        },
+        parse::mesh::{
+            instructions::{create_instructions, post_process},
+            FileType,
+        },
+    };
+
+    #[bve_derive::bve_test]
+    #[test]
+    fn round_trip() {
+        let source = indoc::indoc!(
+            r#"
+            CreateMeshBuilder
+            Cube, 1, 2, 3
+            SetColor, 10, 20, 30, 40
+            LoadTexture, day.png, night.png
+            SetDecalTransparentColor, 0, 0, 255
+            SetBlendMode, Additive, 10, DivideExponent2
+            SetWrapMode, ClampRepeat
+            CreateMeshBuilder
+            AddVertex, 0, 0, 0
+            AddVertex, 1, 0, 0
+            AddVertex, 0, 0, 1
+            AddFace2, 0, 1, 2
+            SetTextureCoordinates, 1, 0.5, 0.25
+            SetText, Hello
+            SetFont, Arial
+            LoadLightMap, light.png
+            SetCrossfading, 1
+        "#
+        );
+        let mesh = generate_meshes(post_process(create_instructions(source, FileType::CSV)));
+        assert_eq!(mesh.errors, vec![]);
+
+        let bytes = serialize_mesh(&mesh);
+        assert_eq!(deserialize_mesh(&bytes), Some(mesh));
+    }
+
+    #[bve_derive::bve_test]
+    #[test]
+    fn round_trip_problems() {
+        let source = indoc::indoc!(
+            r#"
+            CreateMeshBuilder
+            AddVertex, 0, 0, 0
+            AddVertex, 1, 0, 0
+            AddFace, 0, 1, 1
+            AddFace, 0, 1, 5
+            GenerateNormals
+            Unknown, 1
+        "#
+        );
+        let mesh = generate_meshes(post_process(create_instructions(source, FileType::CSV)));
+        assert!(!mesh.warnings.is_empty());
+        assert!(!mesh.errors.is_empty());
+
+        let bytes = serialize_mesh(&mesh);
+        assert_eq!(deserialize_mesh(&bytes), Some(mesh));
+    }
+
+    #[bve_derive::bve_test]
+    #[test]
+    fn corrupt() {
+        let mesh = generate_meshes(post_process(create_instructions("Cube, 1, 1, 1", FileType::CSV)));
+        let bytes = serialize_mesh(&mesh);
+
+        assert_eq!(deserialize_mesh(&bytes[..bytes.len() - 1]), None);
+        assert_eq!(deserialize_mesh(&[bytes.as_slice(), &[0]].concat()), None);
+        assert_eq!(deserialize_mesh(b"BVEM\xFF\xFF\xFF\xFF"), None);
+        assert_eq!(deserialize_mesh(b""), None);
+    }
+}
@@ -0,0 +1,225 @@
+//! On disk cache of loaded meshes, so they don't need to be parsed and executed every time they're loaded.
+//!
+//! Entries are keyed by a hash of the source file's contents and the version of the loaders, so editing a file or
+//! changing the loaders makes old entries unreachable. Entries that can't be read are removed.
+
+use crate::load::mesh::{
+    binary::{deserialize_mesh, serialize_mesh, BINARY_FORMAT_VERSION},
+    LoadedStaticMesh,
+};
+use async_std::{
+    fs,
+    path::{Path, PathBuf},
+    prelude::*,
+};
+use log::{debug, trace};
+use std::{ffi::OsStr, fmt, io, process};
+
+/// Must be increased whenever a change to the loaders changes the meshes they produce, so old cache entries are no
+/// longer used.
+pub const MESH_LOADER_VERSION: u32 = 1;
+
+const EXTENSION: &str = "bvem";
+const TEMPORARY_EXTENSION: &str = "tmp";
+
+/// Hash identifying a single source file as loaded by a single version of the loaders.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct MeshCacheKey(u128);
+
+impl MeshCacheKey {
+    /// `format` separates identical files that are loaded differently, like `.csv` and `.b3d` files.
+    #[must_use]
+    pub fn new(format: &str, source: &[u8]) -> Self {
+        let mut hasher = Fnv1a128::new();
+        hasher.write(&MESH_LOADER_VERSION.to_le_bytes());
+        hasher.write(&BINARY_FORMAT_VERSION.to_le_bytes());
+        hasher.write(format.as_bytes());
+        hasher.write(&[0]);
+        hasher.write(source);
+        Self(hasher.0)
+    }
+}
+
+impl fmt::Display for MeshCacheKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:032x}", self.0)
+    }
+}
+
+/// 128-bit FNV-1a. Used instead of [`std::hash::Hasher`]s as it must give the same result in every build.
+struct Fnv1a128(u128);
+
+impl Fnv1a128 {
+    const OFFSET_BASIS: u128 = 0x6c62_272e_07bb_0142_62b8_2175_6295_c58d;
+    const PRIME: u128 = 0x0000_0000_0100_0000_0000_0000_0000_013b;
+
+    const fn new() -> Self {
+        Self(Self::OFFSET_BASIS)
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.0 ^= byte as u128;
+            self.0 = self.0.wrapping_mul(Self::PRIME);
+        }
+    }
+}
+
+/// Directory of cached meshes.
+#[derive(Debug, Clone)]
+pub struct DiskMeshCache {
+    directory: PathBuf,
+}
+
+impl DiskMeshCache {
+    /// The directory is created when the first mesh is inserted.
+    #[must_use]
+    pub fn new(directory: impl Into<PathBuf>) -> Self {
+        Self {
+            directory: directory.into(),
+        }
+    }
+
+    #[must_use]
+    pub fn directory(&self) -> &Path {
+        &self.directory
+    }
+
+    fn entry_path(&self, key: MeshCacheKey) -> PathBuf {
+        self.directory.join(format!("{}.{}", key, EXTENSION))
+    }
+
+    /// Gets the mesh stored under `key`. Entries that are corrupt or from another version of the format are removed.
+    pub async fn get(&self, key: MeshCacheKey) -> Option<LoadedStaticMesh> {
+        let path = self.entry_path(key);
+        let bytes = fs::read(&path).await.ok()?;
+        match deserialize_mesh(&bytes) {
+            Some(mesh) => {
+                trace!("Mesh cache hit {}", key);
+                Some(mesh)
+            }
+            None => {
+                debug!("Removing unreadable mesh cache entry {}", path.display());
+                let _ = fs::remove_file(&path).await;
+                None
+            }
+        }
+    }
+
+    /// Stores `mesh` under `key`.
+    ///
+    /// The mesh's warnings and errors are stored with it, so they're still reported when it's loaded from the cache.
+    ///
+    /// # Errors
+    ///
+    /// Returns Err if the cache directory or the entry could not be written.
+    pub async fn insert(&self, key: MeshCacheKey, mesh: &LoadedStaticMesh) -> io::Result<()> {
+        fs::create_dir_all(&self.directory).await?;
+
+        // Written to a temporary file then moved, so no one can read a partially written entry
+        let path = self.entry_path(key);
+        let temporary = path.with_extension(format!("{}.{}", process::id(), TEMPORARY_EXTENSION));
+        fs::write(&temporary, serialize_mesh(mesh)).await?;
+        fs::rename(&temporary, &path).await?;
+        trace!("Mesh cache insert {}", key);
+        Ok(())
+    }
+
+    /// Removes the entry stored under `key`, if there is one.
+    ///
+    /// # Errors
+    ///
+    /// Returns Err if the entry exists but could not be removed.
+    pub async fn remove(&self, key: MeshCacheKey) -> io::Result<()> {
+        match fs::remove_file(self.entry_path(key)).await {
+            Err(err) if err.kind() != io::ErrorKind::NotFound => Err(err),
+            _ => Ok(()),
+        }
+    }
+
+    /// Removes every entry in the cache. Returns the amount of entries removed.
+    ///
+    /// # Errors
+    ///
+    /// Returns Err if the cache directory could not be read or an entry could not be removed.
+    pub async fn clear(&self) -> io::Result<usize> {
+        let mut entries = match fs::read_dir(&self.directory).await {
+            Ok(entries) => entries,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(0),
+            Err(err) => return Err(err),
+        };
+        let mut removed = 0;
+        while let Some(entry) = entries.next().await {
+            let path = entry?.path();
+            let extension = path.extension().and_then(OsStr::to_str);
+            if extension == Some(EXTENSION) || extension == Some(TEMPORARY_EXTENSION) {
+                fs::remove_file(&path).await?;
+                removed += 1;
+            }
+        }
+        debug!(
+            "Removed {} mesh cache entries from {}",
+            removed,
+            self.directory.display()
+        );
+        Ok(removed)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        load::mesh::{disk_cache::MeshCacheKey, placeholder_mesh, DiskMeshCache},
+        parse::{
+            mesh::{MeshWarning, MeshWarningKind},
+            Span,
+        },
+    };
+    use async_std::fs;
+
+    #[bve_derive::bve_test]
+    #[test]
+    fn keys() {
+        let key = MeshCacheKey::new("csv", b"CreateMeshBuilder");
+        assert_eq!(key, MeshCacheKey::new("csv", b"CreateMeshBuilder"));
+        assert_ne!(key, MeshCacheKey::new("b3d", b"CreateMeshBuilder"));
+        assert_ne!(key, MeshCacheKey::new("csv", b"CreateMeshBuilder\n"));
+        assert_eq!(key.to_string().len(), 32);
+    }
+
+    #[bve_derive::bve_test]
+    #[async_std::test]
+    async fn insert_get_clear() {
+        let directory = std::env::temp_dir().join(format!("bve-disk-cache-test-{}", std::process::id()));
+        let cache = DiskMeshCache::new(directory);
+        let key = MeshCacheKey::new("csv", b"placeholder");
+        let mesh = placeholder_mesh();
+
+        assert_eq!(cache.get(key).await, None);
+        cache.insert(key, &mesh).await.expect("Could not insert");
+        assert_eq!(cache.get(key).await, Some(mesh.clone()));
+
+        // Corrupt entries are misses, and get removed
+        let entry = cache.entry_path(key);
+        fs::write(&entry, b"BVEM").await.expect("Could not corrupt entry");
+        assert_eq!(cache.get(key).await, None);
+        assert!(!entry.exists().await);
+
+        cache.insert(key, &mesh).await.expect("Could not insert");
+        assert_eq!(cache.clear().await.expect("Could not clear"), 1);
+        assert_eq!(cache.get(key).await, None);
+
+        // Meshes with problems are cached along with them
+        let mut broken = mesh;
+        broken.warnings.push(MeshWarning {
+            kind: MeshWarningKind::DegenerateTriangle,
+            location: Span::none(),
+        });
+        cache.insert(key, &broken).await.expect("Could not insert");
+        assert_eq!(cache.get(key).await, Some(broken));
+
+        fs::remove_dir_all(cache.directory())
+            .await
+            .expect("Could not remove cache directory");
+    }
+}
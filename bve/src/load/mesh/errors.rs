@@ -0,0 +1,85 @@
+use crate::{
+    l10n::ForceEnglish,
+    localize,
+    parse::{UserError, UserErrorCategory},
+};
+use async_std::path::PathBuf;
+use std::{error::Error, fmt, io, sync::Arc};
+
+/// Failure to load a mesh, animated, or texture file at all.
+///
+/// Problems inside of a file that could be loaded are reported as [`MeshError`](crate::parse::mesh::MeshError)s
+/// inside the [`LoadedStaticMesh`](super::LoadedStaticMesh) instead.
+#[derive(Debug, Clone)]
+pub struct LoadError {
+    /// File that failed to load.
+    pub path: PathBuf,
+    pub kind: LoadErrorKind,
+}
+
+#[derive(Debug, Clone)]
+pub enum LoadErrorKind {
+    /// File extension isn't one of the mesh formats.
+    UnsupportedFormat { extension: Option<String> },
+    /// File could not be opened or read.
+    Unreadable { error: Arc<io::Error> },
+    /// File is so broken that no part of it could be used.
+    Malformed { error: String },
+    /// Animated file includes itself, directly or through other animated files.
+    RecursiveInclude,
+}
+
+impl LoadError {
+    #[must_use]
+    pub fn from_io(path: impl Into<PathBuf>, error: io::Error) -> Self {
+        Self {
+            path: path.into(),
+            kind: LoadErrorKind::Unreadable { error: Arc::new(error) },
+        }
+    }
+}
+
+impl UserError for LoadError {
+    fn category(&self) -> UserErrorCategory {
+        UserErrorCategory::Error
+    }
+
+    fn line(&self) -> Option<u64> {
+        None
+    }
+
+    fn description(&self, en: ForceEnglish) -> String {
+        let path = self.path.display().to_string();
+        match &self.kind {
+            LoadErrorKind::UnsupportedFormat { extension } => {
+                let extension = extension.as_deref().unwrap_or("");
+                localize!(@en, "load-error-unsupported-format", "path" -> path.as_str(), "extension" -> extension)
+            }
+            LoadErrorKind::Unreadable { error } => {
+                let error = error.to_string();
+                localize!(@en, "load-error-unreadable", "path" -> path.as_str(), "error" -> error.as_str())
+            }
+            LoadErrorKind::Malformed { error } => {
+                localize!(@en, "load-error-malformed", "path" -> path.as_str(), "error" -> error.as_str())
+            }
+            LoadErrorKind::RecursiveInclude => localize!(@en, "load-error-recursive-include", "path" -> path.as_str()),
+        }
+    }
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.description(ForceEnglish::English))
+    }
+}
+
+impl Error for LoadError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match &self.kind {
+            LoadErrorKind::Unreadable { error } => Some(&**error),
+            LoadErrorKind::UnsupportedFormat { .. }
+            | LoadErrorKind::Malformed { .. }
+            | LoadErrorKind::RecursiveInclude => None,
+        }
+    }
+}
@@ -19,6 +19,10 @@ struct MeshBuildContext {
     parsed: LoadedStaticMesh,
     vertices: Vec<Vertex>,
     current_mesh: Mesh,
+    /// Span of the instruction that created each triangle in `current_mesh`.
+    triangle_spans: Vec<Span>,
+    /// Set by `EnableHacks` for the rest of the file.
+    hacks: bool,
 }
 
 impl Default for MeshBuildContext {
@@ -27,6 +31,8 @@ impl Default for MeshBuildContext {
             parsed: LoadedStaticMesh::default(),
             vertices: Vec::default(),
             current_mesh: default_mesh(),
+            triangle_spans: Vec::new(),
+            hacks: false,
         }
     }
 }
@@ -56,6 +62,15 @@ impl Instruction {
             InstructionData::SetTextureCoordinates(_data) => {
                 panic_log!("SetTextureCoordinates instruction cannot be executed, must be postprocessed away");
             }
+            InstructionData::SetWrapMode(data) => data.execute(self.span, ctx),
+            InstructionData::SetText(data) => data.execute(self.span, ctx),
+            InstructionData::SetTextColor(data) => data.execute(self.span, ctx),
+            InstructionData::SetBackgroundColor(data) => data.execute(self.span, ctx),
+            InstructionData::SetTextPadding(data) => data.execute(self.span, ctx),
+            InstructionData::SetFont(data) => data.execute(self.span, ctx),
+            InstructionData::LoadLightMap(data) => data.execute(self.span, ctx),
+            InstructionData::SetCrossfading(data) => data.execute(self.span, ctx),
+            InstructionData::EnableHacks(data) => data.execute(self.span, ctx),
         }
     }
 }
@@ -128,9 +143,48 @@ fn shrink_vertex_list(vertices: &[Vertex], indices: &[usize]) -> (Vec<Vertex>, V
     (new_vertices, new_indices)
 }
 
+fn is_finite(v: Vec3A) -> bool {
+    v.x().is_finite() && v.y().is_finite() && v.z().is_finite()
+}
+
+/// Warns about triangles without area and vertices whose normals couldn't be calculated. Must be run after the
+/// normals are calculated.
+///
+/// Triangles with infinite or NaN positions are skipped, as they were already warned about by the transformation that
+/// caused it.
+fn validate_geometry(mesh: &Mesh, triangle_spans: &[Span]) -> Vec<MeshWarning> {
+    let mut warnings = Vec::new();
+    for ((&i1, &i2, &i3), &span) in mesh.indices.iter().tuples().zip(triangle_spans) {
+        let a = mesh.vertices[i1].position;
+        let b = mesh.vertices[i2].position;
+        let c = mesh.vertices[i3].position;
+        if !(is_finite(a) && is_finite(b) && is_finite(c)) {
+            continue;
+        }
+
+        if (b - a).cross(c - a).length_squared() == 0.0 {
+            warnings.push(MeshWarning {
+                kind: MeshWarningKind::DegenerateTriangle,
+                location: span,
+            });
+        }
+        if [i1, i2, i3].iter().any(|&i| !is_finite(mesh.vertices[i].normal)) {
+            warnings.push(MeshWarning {
+                kind: MeshWarningKind::ZeroLengthNormal,
+                location: span,
+            });
+        }
+    }
+    // A face is made of multiple triangles, don't repeat the same warning for each of them
+    warnings.dedup();
+    warnings
+}
+
 fn run_create_mesh_builder(ctx: &mut MeshBuildContext) {
     if !ctx.current_mesh.vertices.is_empty() && !ctx.current_mesh.indices.is_empty() {
         calculate_normals(&mut ctx.current_mesh);
+        let mut warnings = validate_geometry(&ctx.current_mesh, &ctx.triangle_spans);
+        ctx.parsed.warnings.append(&mut warnings);
         // Distribute the mesh color to the vertices. It is redundant, but when
         // these meshes get combined, this is the easiest and fastest way for shaders
         // to access the data
@@ -142,6 +196,7 @@ fn run_create_mesh_builder(ctx: &mut MeshBuildContext) {
             .push(std::mem::replace(&mut ctx.current_mesh, default_mesh()));
     }
     ctx.vertices.clear();
+    ctx.triangle_spans.clear();
 }
 
 impl Executable for CreateMeshBuilder {
@@ -162,15 +217,30 @@ impl Executable for AddVertex {
 
 fn add_face(ctx: &mut MeshBuildContext, span: Span, sides: Sides, indices: &[usize]) {
     // Validate all indexes are in bounds
+    let mut in_bounds = Vec::with_capacity(indices.len());
     for &idx in indices {
         if idx >= ctx.vertices.len() {
             ctx.parsed.errors.push(MeshError {
                 location: span,
                 kind: MeshErrorKind::OutOfBounds { idx },
             });
-            return;
+            // With hacks on, the rest of the face is still used
+            if !ctx.hacks {
+                return;
+            }
+        } else {
+            in_bounds.push(idx);
         }
     }
+    let indices = &in_bounds;
+
+    let duplicate = indices.iter().enumerate().find(|&(i, idx)| indices[..i].contains(idx));
+    if let Some((_, &idx)) = duplicate {
+        ctx.parsed.warnings.push(MeshWarning {
+            location: span,
+            kind: MeshWarningKind::DuplicateFaceIndex { idx },
+        });
+    }
 
     // Use my indexes to find all vertices that are only mine
     let (mut verts, indices) = shrink_vertex_list(&ctx.vertices, indices);
@@ -189,6 +259,8 @@ fn add_face(ctx: &mut MeshBuildContext, span: Span, sides: Sides, indices: &[usi
 
     ctx.current_mesh.vertices.extend_from_slice(&verts);
     ctx.current_mesh.indices.extend_from_slice(&indices);
+    ctx.triangle_spans
+        .extend(std::iter::repeat(span).take(indices.len() / 3));
 }
 
 impl Executable for AddFace {
@@ -198,12 +270,21 @@ impl Executable for AddFace {
 }
 
 /// Preform a per-vertex transform on all meshes in the [`MeshBuildContext`] depending on [`ApplyTo`]
-fn apply_transform<F>(application: ApplyTo, ctx: &mut MeshBuildContext, mut func: F)
+///
+/// Warns if the transform moved any vertex to an infinite or NaN position.
+fn apply_transform<F>(application: ApplyTo, span: Span, ctx: &mut MeshBuildContext, mut func: F)
 where
     F: FnMut(&mut Vertex),
 {
-    for v in &mut ctx.current_mesh.vertices {
+    let mut made_non_finite = false;
+    let mut transform = |v: &mut Vertex| {
+        let was_finite = is_finite(v.position);
         func(v);
+        made_non_finite |= was_finite && !is_finite(v.position);
+    };
+
+    for v in &mut ctx.current_mesh.vertices {
+        transform(v);
     }
 
     // Handle other meshes
@@ -212,28 +293,35 @@ where
         ApplyTo::AllMeshes => {
             for m in &mut ctx.parsed.meshes {
                 for v in &mut m.vertices {
-                    func(v);
+                    transform(v);
                 }
             }
         }
         ApplyTo::Unset => unreachable!(),
     }
+
+    if made_non_finite {
+        ctx.parsed.warnings.push(MeshWarning {
+            location: span,
+            kind: MeshWarningKind::NonFinitePosition,
+        });
+    }
 }
 
 impl Executable for Translate {
-    fn execute(&self, _span: Span, ctx: &mut MeshBuildContext) {
-        apply_transform(self.application, ctx, |v| v.position += self.value);
+    fn execute(&self, span: Span, ctx: &mut MeshBuildContext) {
+        apply_transform(self.application, span, ctx, |v| v.position += self.value);
     }
 }
 
 impl Executable for Scale {
-    fn execute(&self, _span: Span, ctx: &mut MeshBuildContext) {
-        apply_transform(self.application, ctx, |v| v.position = self.value * v.position);
+    fn execute(&self, span: Span, ctx: &mut MeshBuildContext) {
+        apply_transform(self.application, span, ctx, |v| v.position = self.value * v.position);
     }
 }
 
 impl Executable for Rotate {
-    fn execute(&self, _span: Span, ctx: &mut MeshBuildContext) {
+    fn execute(&self, span: Span, ctx: &mut MeshBuildContext) {
         let axis = if self.axis == Vec3A::zero() {
             Vec3A::unit_x()
         } else {
@@ -242,15 +330,15 @@ impl Executable for Rotate {
 
         let rotation = Mat3::from_axis_angle(Vec3::from(axis), self.angle.to_radians());
 
-        apply_transform(self.application, ctx, |v| {
+        apply_transform(self.application, span, ctx, |v| {
             v.position = rotation * v.position;
         });
     }
 }
 
 impl Executable for Shear {
-    fn execute(&self, _span: Span, ctx: &mut MeshBuildContext) {
-        apply_transform(self.application, ctx, |v| {
+    fn execute(&self, span: Span, ctx: &mut MeshBuildContext) {
+        apply_transform(self.application, span, ctx, |v| {
             let scale = self.ratio * self.direction.dot(v.position);
             v.position += self.shear * scale;
         });
@@ -258,10 +346,10 @@ impl Executable for Shear {
 }
 
 impl Executable for Mirror {
-    fn execute(&self, _span: Span, ctx: &mut MeshBuildContext) {
+    fn execute(&self, span: Span, ctx: &mut MeshBuildContext) {
         let factor = self.directions.map_f32(|b| if b { -1.0_f32 } else { 1.0_f32 });
 
-        apply_transform(self.application, ctx, |v| {
+        apply_transform(self.application, span, ctx, |v| {
             v.position *= factor;
         });
     }
@@ -301,6 +389,63 @@ impl Executable for SetDecalTransparentColor {
     }
 }
 
+impl Executable for SetWrapMode {
+    fn execute(&self, _span: Span, ctx: &mut MeshBuildContext) {
+        ctx.current_mesh.texture.wrap_mode = Some(self.wrap_mode);
+    }
+}
+
+impl Executable for SetText {
+    fn execute(&self, _span: Span, ctx: &mut MeshBuildContext) {
+        ctx.current_mesh.text.get_or_insert_with(MeshText::default).text = self.text.clone();
+    }
+}
+
+impl Executable for SetTextColor {
+    fn execute(&self, _span: Span, ctx: &mut MeshBuildContext) {
+        ctx.current_mesh.text.get_or_insert_with(MeshText::default).color = self.color;
+    }
+}
+
+impl Executable for SetBackgroundColor {
+    fn execute(&self, _span: Span, ctx: &mut MeshBuildContext) {
+        ctx.current_mesh
+            .text
+            .get_or_insert_with(MeshText::default)
+            .background_color = self.color;
+    }
+}
+
+impl Executable for SetTextPadding {
+    fn execute(&self, _span: Span, ctx: &mut MeshBuildContext) {
+        ctx.current_mesh.text.get_or_insert_with(MeshText::default).padding = self.padding;
+    }
+}
+
+impl Executable for SetFont {
+    fn execute(&self, _span: Span, ctx: &mut MeshBuildContext) {
+        ctx.current_mesh.text.get_or_insert_with(MeshText::default).font = Some(self.font.clone());
+    }
+}
+
+impl Executable for LoadLightMap {
+    fn execute(&self, _span: Span, ctx: &mut MeshBuildContext) {
+        ctx.current_mesh.texture.light_map_id = Some(ctx.parsed.textures.add(&self.file));
+    }
+}
+
+impl Executable for SetCrossfading {
+    fn execute(&self, _span: Span, ctx: &mut MeshBuildContext) {
+        ctx.current_mesh.texture.crossfading = self.enabled;
+    }
+}
+
+impl Executable for EnableHacks {
+    fn execute(&self, _span: Span, ctx: &mut MeshBuildContext) {
+        ctx.hacks = true;
+    }
+}
+
 /// Actually execute the instructions provided.
 ///
 /// Errors are taken from [`InstructionList::errors`] and any new ones encountered are appended and put in
@@ -317,16 +462,24 @@ pub fn generate_meshes(instructions: InstructionList) -> LoadedStaticMesh {
         instr.execute(&mut mbc);
     }
     run_create_mesh_builder(&mut mbc);
-    mbc.parsed.warnings = instructions.warnings;
-    mbc.parsed.errors = instructions.errors;
+    // Problems found while parsing come before those found while executing
+    let mut warnings = instructions.warnings;
+    warnings.append(&mut mbc.parsed.warnings);
+    mbc.parsed.warnings = warnings;
+    let mut errors = instructions.errors;
+    errors.append(&mut mbc.parsed.errors);
+    mbc.parsed.errors = errors;
     mbc.parsed
 }
 
 #[cfg(test)]
 mod test {
     use crate::{
-        load::mesh::{execution::generate_meshes, BlendMode, Glow, GlowAttenuationMode},
-        parse::{mesh::instructions::*, Span},
+        load::mesh::{execution::generate_meshes, BlendMode, Glow, GlowAttenuationMode, MeshText, WrapMode},
+        parse::{
+            mesh::{instructions::*, FileType, MeshWarning, MeshWarningKind},
+            Span,
+        },
         ColorU8RGB, ColorU8RGBA,
     };
     use glam::{Vec2, Vec3A};
@@ -663,4 +816,169 @@ mod test {
         assert_eq!(result.textures.len(), 0);
         assert_eq!(result.errors.len(), 0);
     }
+
+    #[bve_derive::bve_test]
+    #[test]
+    fn extended_properties() {
+        let v = generate_instruction_list!(
+            0: CreateMeshBuilder {},
+            1: AddVertex {
+                position: Vec3A::zero(),
+                normal: Vec3A::zero(),
+                texture_coord: Vec2::zero(),
+            },
+            2: AddVertex {
+                position: Vec3A::new(-0.866_025, 0.0, 0.5),
+                normal: Vec3A::zero(),
+                texture_coord: Vec2::zero(),
+            },
+            3: AddVertex {
+                position: Vec3A::new(0.866_025, 0.0, 0.5),
+                normal: Vec3A::zero(),
+                texture_coord: Vec2::zero(),
+            },
+            4: AddFace {
+                indexes: vec![0, 1, 2],
+                sides: Sides::One,
+            },
+            5: LoadTexture {
+                daytime: String::from("day_tex"),
+                nighttime: String::new(),
+            },
+            6: LoadLightMap {
+                file: String::from("light_map"),
+            },
+            7: SetWrapMode {
+                wrap_mode: WrapMode::ClampRepeat,
+            },
+            8: SetCrossfading {
+                enabled: true,
+            },
+            9: SetTextColor {
+                color: ColorU8RGB::new(1, 2, 3),
+            },
+            10: SetText {
+                text: String::from("Platform 1"),
+            },
+            11: SetTextPadding {
+                padding: Vec2::splat(4.0),
+            },
+            12: SetFont {
+                font: String::from("Arial"),
+            }
+        );
+
+        let result = generate_meshes(post_process(v));
+        assert_eq!(result.meshes.len(), 1);
+        let mesh = &result.meshes[0];
+        assert_eq!(mesh.texture.texture_id, Some(0));
+        assert_eq!(mesh.texture.light_map_id, Some(1));
+        assert_eq!(result.textures.lookup(1), Some("light_map"));
+        assert_eq!(mesh.texture.wrap_mode, Some(WrapMode::ClampRepeat));
+        assert!(mesh.texture.crossfading);
+        assert_eq!(
+            mesh.text,
+            Some(MeshText {
+                text: String::from("Platform 1"),
+                color: ColorU8RGB::new(1, 2, 3),
+                background_color: ColorU8RGB::splat(255),
+                padding: Vec2::splat(4.0),
+                font: Some(String::from("Arial")),
+            })
+        );
+        assert_eq!(result.errors.len(), 0);
+    }
+
+    #[bve_derive::bve_test]
+    #[test]
+    fn hacks_keep_partial_faces() {
+        let build = |hacks: bool| {
+            let mut v = generate_instruction_list!(
+                0: CreateMeshBuilder {},
+                1: AddVertex {
+                    position: Vec3A::zero(),
+                    normal: Vec3A::zero(),
+                    texture_coord: Vec2::zero(),
+                },
+                2: AddVertex {
+                    position: Vec3A::new(-0.866_025, 0.0, 0.5),
+                    normal: Vec3A::zero(),
+                    texture_coord: Vec2::zero(),
+                },
+                3: AddVertex {
+                    position: Vec3A::new(0.866_025, 0.0, 0.5),
+                    normal: Vec3A::zero(),
+                    texture_coord: Vec2::zero(),
+                },
+                4: AddFace {
+                    indexes: vec![0, 1, 7, 2],
+                    sides: Sides::One,
+                }
+            );
+            if hacks {
+                v.instructions.insert(0, Instruction {
+                    data: InstructionData::EnableHacks(EnableHacks),
+                    span: Span::none(),
+                });
+            }
+            generate_meshes(post_process(v))
+        };
+
+        let without = build(false);
+        assert_eq!(without.meshes.len(), 0);
+        assert_eq!(without.errors.len(), 1);
+
+        let with = build(true);
+        assert_eq!(with.meshes.len(), 1);
+        assert_eq!(with.meshes[0].indices, vec![0, 1, 2]);
+        assert_eq!(with.errors.len(), 1);
+    }
+
+    #[bve_derive::bve_test]
+    #[test]
+    fn validation() {
+        let source = indoc::indoc!(
+            r#"
+            CreateMeshBuilder
+            AddVertex, 0, 0, 0
+            AddVertex, 1, 0, 0
+            AddVertex, 2, 0, 0
+            AddVertex, 0, 0, 1
+            AddFace, 0, 1, 2
+            AddFace, 0, 1, 3, 0
+            CreateMeshBuilder
+            AddVertex, 0, 0, 0
+            AddVertex, 1, 0, 0
+            AddVertex, 0, 0, 1
+            AddFace, 0, 1, 2
+            Scale, 1e30, 1e30, 1e30
+            Scale, 1e30, 1e30, 1e30
+            Translate, 1, 0, 0
+        "#
+        );
+        let result = generate_meshes(post_process(create_instructions(source, FileType::CSV)));
+        assert_eq!(result.errors, vec![]);
+        assert_eq!(result.warnings, vec![
+            MeshWarning {
+                kind: MeshWarningKind::DuplicateFaceIndex { idx: 0 },
+                location: Span::from_line(7),
+            },
+            MeshWarning {
+                kind: MeshWarningKind::DegenerateTriangle,
+                location: Span::from_line(6),
+            },
+            MeshWarning {
+                kind: MeshWarningKind::ZeroLengthNormal,
+                location: Span::from_line(6),
+            },
+            MeshWarning {
+                kind: MeshWarningKind::DegenerateTriangle,
+                location: Span::from_line(7),
+            },
+            MeshWarning {
+                kind: MeshWarningKind::NonFinitePosition,
+                location: Span::from_line(14),
+            },
+        ]);
+    }
 }
@@ -1,19 +1,38 @@
 use crate::{
-    filesystem::read_convert_utf8,
+    filesystem::convert_to_utf8_checked,
     load::mesh::execution::generate_meshes,
-    parse::mesh::{
-        instructions::{create_instructions, post_process},
-        BlendMode, FileType, Glow, GlowAttenuationMode, MeshError, MeshWarning,
+    parse::{
+        mesh::{
+            instructions::{
+                create_instructions, post_process, CreateMeshBuilder, Cube, Instruction, InstructionData,
+                InstructionList, SetColor,
+            },
+            BlendMode, FileType, Glow, GlowAttenuationMode, MeshError, MeshWarning, MeshWarningKind, WrapMode,
+        },
+        Span,
     },
     ColorU8RGB, ColorU8RGBA,
 };
-use async_std::path::Path;
+use async_std::{fs::read, path::Path};
+pub use bake::*;
+pub use binary::*;
+pub use disk_cache::*;
+pub use errors::*;
 pub use execution::*;
 use glam::{Vec2, Vec3A};
 use indexmap::IndexSet;
+use log::warn;
+pub use optimize::*;
 use std::{ffi::OsStr, ops::Deref};
 
+mod bake;
+mod binary;
+mod disk_cache;
+mod errors;
 mod execution;
+pub mod obj;
+mod optimize;
+pub mod x;
 
 /// A single static object.
 ///
@@ -117,6 +136,36 @@ pub struct Texture {
     pub texture_id: Option<usize>,
     pub decal_transparent_color: Option<ColorU8RGB>,
     pub emission_color: ColorU8RGB,
+    /// Index to get the light map's name in Object's [`TextureSet`]
+    pub light_map_id: Option<usize>,
+    /// If `None`, the wrap mode is chosen based on the texture coordinates.
+    pub wrap_mode: Option<WrapMode>,
+    /// Blend between the daytime and nighttime textures instead of switching.
+    pub crossfading: bool,
+}
+
+/// Text rendered onto a mesh's texture with `SetText` and its related instructions.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MeshText {
+    pub text: String,
+    pub color: ColorU8RGB,
+    pub background_color: ColorU8RGB,
+    /// unit: pixels
+    pub padding: Vec2,
+    /// If `None`, the default font is used.
+    pub font: Option<String>,
+}
+
+impl Default for MeshText {
+    fn default() -> Self {
+        Self {
+            text: String::new(),
+            color: ColorU8RGB::splat(0),
+            background_color: ColorU8RGB::splat(255),
+            padding: Vec2::zero(),
+            font: None,
+        }
+    }
 }
 
 /// A mesh corresponds to a single `CreateMeshBuilder` and contains
@@ -130,6 +179,7 @@ pub struct Mesh {
     pub color: ColorU8RGBA,
     pub blend_mode: BlendMode,
     pub glow: Glow,
+    pub text: Option<MeshText>,
 }
 
 #[must_use]
@@ -141,6 +191,9 @@ pub const fn default_mesh() -> Mesh {
             texture_id: None,
             emission_color: ColorU8RGB::splat(0),
             decal_transparent_color: None,
+            light_map_id: None,
+            wrap_mode: None,
+            crossfading: false,
         },
         color: ColorU8RGBA::splat(255),
         blend_mode: BlendMode::Normal,
@@ -148,6 +201,7 @@ pub const fn default_mesh() -> Mesh {
             attenuation_mode: GlowAttenuationMode::DivideExponent4,
             half_distance: 0,
         },
+        text: None,
     }
 }
 
@@ -209,20 +263,191 @@ impl Vertex {
     }
 }
 
-pub async fn load_mesh_from_file(file: impl AsRef<Path>) -> Option<LoadedStaticMesh> {
+/// File formats that can be loaded as a static mesh.
+enum MeshFormat {
+    /// B3D or CSV
+    Csv(FileType),
+    Obj,
+    X,
+}
+
+impl MeshFormat {
+    fn from_path(path: &Path) -> Result<Self, LoadError> {
+        let ext = path
+            .extension()
+            .map(OsStr::to_string_lossy)
+            .as_deref()
+            .map(str::to_lowercase);
+        match ext.as_deref() {
+            Some("b3d") => Ok(Self::Csv(FileType::B3D)),
+            Some("csv") => Ok(Self::Csv(FileType::CSV)),
+            Some("obj") => Ok(Self::Obj),
+            Some("x") => Ok(Self::X),
+            _ => Err(LoadError {
+                path: path.into(),
+                kind: LoadErrorKind::UnsupportedFormat { extension: ext },
+            }),
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            Self::Csv(FileType::B3D) => "b3d",
+            Self::Csv(FileType::CSV) => "csv",
+            Self::Obj => "obj",
+            Self::X => "x",
+        }
+    }
+}
+
+/// Loads a static mesh from a b3d, csv, obj, or x file.
+///
+/// # Errors
+///
+/// Returns Err if the file could not be loaded at all. Problems within the file are reported inside the mesh.
+pub async fn load_mesh_from_file(file: impl AsRef<Path>) -> Result<LoadedStaticMesh, LoadError> {
     let path = file.as_ref();
-    let ext = path
-        .extension()
-        .map(OsStr::to_string_lossy)
-        .as_deref()
-        .map(str::to_lowercase);
-    let file_type = match ext.as_deref() {
-        Some("b3d") => FileType::B3D,
-        Some("csv") => FileType::CSV,
-        _ => return None, // TODO: Use result not option
+    let format = MeshFormat::from_path(path)?;
+    let bytes = read(path).await.map_err(|err| LoadError::from_io(path, err))?;
+    load_mesh_from_bytes(path, &format, bytes).await
+}
+
+/// Same as [`load_mesh_from_file`], but checks `cache` for the mesh before loading it, and adds it to `cache` after.
+///
+/// Obj files aren't cached, as their materials are in other files.
+///
+/// # Errors
+///
+/// Returns Err if the file could not be loaded at all. Problems within the file are reported inside the mesh.
+pub async fn load_mesh_from_file_cached(
+    file: impl AsRef<Path>,
+    cache: &DiskMeshCache,
+) -> Result<LoadedStaticMesh, LoadError> {
+    let path = file.as_ref();
+    let format = MeshFormat::from_path(path)?;
+    if let MeshFormat::Obj = format {
+        return load_mesh_from_file(path).await;
+    }
+
+    let bytes = read(path).await.map_err(|err| LoadError::from_io(path, err))?;
+    let key = MeshCacheKey::new(format.name(), &bytes);
+    if let Some(mesh) = cache.get(key).await {
+        return Ok(mesh);
+    }
+
+    let mesh = load_mesh_from_bytes(path, &format, bytes).await?;
+    if let Err(err) = cache.insert(key, &mesh).await {
+        warn!("Could not add {} to the mesh cache: {}", path.display(), err);
+    }
+    Ok(mesh)
+}
+
+/// `path` is only used for errors and to find files the mesh refers to.
+async fn load_mesh_from_bytes(path: &Path, format: &MeshFormat, bytes: Vec<u8>) -> Result<LoadedStaticMesh, LoadError> {
+    let (result, malformed_encoding) = convert_to_utf8_checked(bytes);
+
+    let mut instructions = match format {
+        MeshFormat::Csv(file_type) => create_instructions(&result, *file_type),
+        MeshFormat::Obj => obj::create_instructions_from_obj_file(path, &result)
+            .await
+            .map_err(|err| LoadError {
+                path: path.into(),
+                kind: LoadErrorKind::Malformed { error: err.to_string() },
+            })?,
+        MeshFormat::X => x::create_instructions_from_x(&result),
+    };
+    if let Some(encoding) = malformed_encoding {
+        instructions.warnings.insert(0, MeshWarning {
+            kind: MeshWarningKind::MalformedEncoding {
+                encoding: String::from(encoding.name()),
+            },
+            location: Span::none(),
+        });
+    }
+
+    Ok(generate_meshes(post_process(instructions)))
+}
+
+/// Mesh to show in place of one that failed to load: a 1m magenta cube.
+#[must_use]
+pub fn placeholder_mesh() -> LoadedStaticMesh {
+    let instructions = vec![
+        InstructionData::CreateMeshBuilder(CreateMeshBuilder),
+        InstructionData::Cube(Cube {
+            half_dim: Vec3A::splat(0.5),
+        }),
+        InstructionData::SetColor(SetColor {
+            color: ColorU8RGBA::new(255, 0, 255, 255),
+        }),
+    ];
+    generate_meshes(post_process(InstructionList {
+        instructions: instructions
+            .into_iter()
+            .map(|data| Instruction {
+                span: Span::none(),
+                data,
+            })
+            .collect(),
+        warnings: Vec::new(),
+        errors: Vec::new(),
+    }))
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        load::mesh::{load_mesh_from_file, placeholder_mesh, LoadErrorKind},
+        parse::{
+            mesh::{MeshWarning, MeshWarningKind},
+            Span,
+        },
+        ColorU8RGBA,
     };
 
-    let result = read_convert_utf8(path).await.ok()?; // TODO: Use result not option
+    #[bve_derive::bve_test]
+    #[async_std::test]
+    async fn load_errors() {
+        let error = load_mesh_from_file("object.png")
+            .await
+            .expect_err("Loaded unsupported format");
+        assert_eq!(error.path.to_str(), Some("object.png"));
+        assert!(matches!(error.kind, LoadErrorKind::UnsupportedFormat { extension: Some(ref ext) } if ext == "png"));
+
+        let error = load_mesh_from_file("does/not/exist.csv")
+            .await
+            .expect_err("Loaded missing file");
+        assert!(matches!(error.kind, LoadErrorKind::Unreadable { .. }));
+    }
+
+    #[bve_derive::bve_test]
+    #[async_std::test]
+    async fn load_malformed_encoding() {
+        let path = async_std::path::PathBuf::from(
+            std::env::temp_dir().join(format!("bve-malformed-{}.csv", std::process::id())),
+        );
+        // Byte order mark for UTF-16LE followed by an unpaired surrogate
+        async_std::fs::write(&path, [0xFF_u8, 0xFE, 0x00, 0xD8])
+            .await
+            .expect("Could not write mesh");
+        let result = load_mesh_from_file(&path).await;
+        async_std::fs::remove_file(&path).await.expect("Could not remove mesh");
+        let mesh = result.expect("Malformed text should be replaced, not rejected");
+        assert_eq!(mesh.meshes.len(), 0);
+        assert_eq!(mesh.warnings[0], MeshWarning {
+            kind: MeshWarningKind::MalformedEncoding {
+                encoding: String::from("UTF-16LE"),
+            },
+            location: Span::none(),
+        });
+    }
 
-    Some(generate_meshes(post_process(create_instructions(&result, file_type))))
+    #[bve_derive::bve_test]
+    #[test]
+    fn placeholder() {
+        let mesh = placeholder_mesh();
+        assert_eq!(mesh.errors, vec![]);
+        assert_eq!(mesh.meshes.len(), 1);
+        assert_eq!(mesh.meshes[0].indices.len(), 36);
+        assert_eq!(mesh.meshes[0].color, ColorU8RGBA::new(255, 0, 255, 255));
+    }
 }
@@ -0,0 +1,302 @@
+//! Wavefront OBJ meshes.
+//!
+//! OBJ files are translated into the same [`InstructionList`] that B3D/CSV files produce, so they go through
+//! the same post-processing and mesh generation.
+
+use crate::{
+    filesystem::read_convert_utf8,
+    parse::{
+        mesh::{
+            instructions::{
+                AddFace, AddVertex, CreateMeshBuilder, Instruction, InstructionData, InstructionList, LoadTexture,
+                SetBlendMode, SetColor, SetEmissiveColor, Sides,
+            },
+            BlendMode, GlowAttenuationMode, MeshError, MeshErrorKind,
+        },
+        Span,
+    },
+    ColorU8RGB, ColorU8RGBA,
+};
+use async_std::path::Path;
+use glam::{Vec2, Vec3A};
+use obj::{Material, Obj, ObjData, ObjError, ObjMaterial};
+use std::{
+    collections::HashMap,
+    io::{self, Cursor},
+    path::PathBuf,
+};
+
+/// Parse the obj file at `path` with contents `source`, loading all the material libraries it references.
+///
+/// Material libraries that cannot be loaded are reported as errors and the groups using them fall back to the default
+/// material.
+///
+/// # Errors
+///
+/// Returns Err if the obj itself is malformed.
+pub async fn create_instructions_from_obj_file(path: &Path, source: &str) -> Result<InstructionList, ObjError> {
+    let data = ObjData::load_buf(&mut Cursor::new(source))?;
+
+    let directory = path.parent().unwrap_or_else(|| Path::new(""));
+    let mut libraries = HashMap::with_capacity(data.material_libs.len());
+    for library in &data.material_libs {
+        let contents = read_convert_utf8(directory.join(&library.filename)).await;
+        libraries.insert(library.filename.clone(), contents);
+    }
+
+    let mut obj = Obj {
+        data,
+        path: PathBuf::from(directory.as_os_str()),
+    };
+    let errors = load_materials(&mut obj, libraries);
+
+    let mut instructions = create_instructions_from_obj(&obj.data);
+    instructions.errors.extend(errors);
+    Ok(instructions)
+}
+
+/// Resolves the material references of `obj` using already-read material libraries.
+fn load_materials(obj: &mut Obj, mut libraries: HashMap<String, io::Result<String>>) -> Vec<MeshError> {
+    let result = obj.load_mtls_fn(|_, filename| {
+        libraries
+            .remove(filename)
+            .unwrap_or_else(|| Err(io::Error::from(io::ErrorKind::NotFound)))
+            .map(Cursor::new)
+    });
+
+    match result {
+        Ok(()) => Vec::new(),
+        Err(failures) => failures
+            .0
+            .into_iter()
+            .map(|(file, error)| MeshError {
+                kind: MeshErrorKind::UnreadableMaterialLibrary {
+                    file,
+                    error: error.to_string(),
+                },
+                location: Span::none(),
+            })
+            .collect(),
+    }
+}
+
+/// Converts already-parsed obj data into mesh instructions. Every group becomes its own mesh.
+///
+/// The obj is converted from a right handed to a left handed coordinate system by negating z, which also requires
+/// reversing the winding order of faces. Texture coordinates are flipped vertically.
+#[must_use]
+pub fn create_instructions_from_obj(data: &ObjData) -> InstructionList {
+    let mut instructions = Vec::new();
+
+    for group in data.objects.iter().flat_map(|o| &o.groups) {
+        if group.polys.is_empty() {
+            continue;
+        }
+
+        push(&mut instructions, InstructionData::CreateMeshBuilder(CreateMeshBuilder));
+        if let Some(ObjMaterial::Mtl(material)) = &group.material {
+            push_material(&mut instructions, material);
+        }
+
+        let mut translation = HashMap::new();
+        for poly in &group.polys {
+            let mut indexes: Vec<usize> = poly
+                .0
+                .iter()
+                .map(|tuple| {
+                    let key = (tuple.0, tuple.1, tuple.2);
+                    let next_index = translation.len();
+                    *translation.entry(key).or_insert_with(|| {
+                        push(&mut instructions, InstructionData::AddVertex(vertex(data, key)));
+                        next_index
+                    })
+                })
+                .collect();
+            indexes.reverse();
+
+            push(
+                &mut instructions,
+                InstructionData::AddFace(AddFace {
+                    indexes,
+                    sides: Sides::One,
+                }),
+            );
+        }
+    }
+
+    InstructionList {
+        instructions,
+        warnings: Vec::new(),
+        errors: Vec::new(),
+    }
+}
+
+fn push(instructions: &mut Vec<Instruction>, data: InstructionData) {
+    instructions.push(Instruction {
+        span: Span::none(),
+        data,
+    });
+}
+
+fn vertex(data: &ObjData, (position, texture, normal): (usize, Option<usize>, Option<usize>)) -> AddVertex {
+    let [x, y, z] = data.position[position];
+    let normal = normal.map_or_else(Vec3A::zero, |idx| {
+        let [x, y, z] = data.normal[idx];
+        Vec3A::new(x, y, -z)
+    });
+    let texture_coord = texture.map_or_else(Vec2::zero, |idx| {
+        let [u, v] = data.texture[idx];
+        Vec2::new(u, 1.0 - v)
+    });
+
+    AddVertex {
+        position: Vec3A::new(x, y, -z),
+        normal,
+        texture_coord,
+    }
+}
+
+/// Maps an mtl material onto the mesh's properties.
+///
+/// - `Kd` and `d`/`Tr` become the mesh color.
+/// - `map_Kd` becomes the daytime texture.
+/// - `Ke` becomes the emissive color.
+/// - A material with an emissive color, but no diffuse color or texture, only adds light so uses additive blending.
+fn push_material(instructions: &mut Vec<Instruction>, material: &Material) {
+    let alpha = material.d.or_else(|| material.tr.map(|tr| 1.0 - tr)).unwrap_or(1.0);
+    let [r, g, b] = material.kd.unwrap_or([1.0; 3]);
+    push(
+        instructions,
+        InstructionData::SetColor(SetColor {
+            color: ColorU8RGBA::new(to_u8(r), to_u8(g), to_u8(b), to_u8(alpha)),
+        }),
+    );
+
+    if let Some(texture) = &material.map_kd {
+        push(
+            instructions,
+            InstructionData::LoadTexture(LoadTexture {
+                daytime: texture.clone(),
+                nighttime: String::new(),
+            }),
+        );
+    }
+
+    if let Some([r, g, b]) = material.ke {
+        let emissive = ColorU8RGB::new(to_u8(r), to_u8(g), to_u8(b));
+        if emissive != ColorU8RGB::splat(0) {
+            push(
+                instructions,
+                InstructionData::SetEmissiveColor(SetEmissiveColor { color: emissive }),
+            );
+
+            let diffuse = material.kd.map_or(false, |kd| kd != [0.0; 3]) || material.map_kd.is_some();
+            if !diffuse {
+                push(
+                    instructions,
+                    InstructionData::SetBlendMode(SetBlendMode {
+                        blend_mode: BlendMode::Additive,
+                        glow_half_distance: 0,
+                        glow_attenuation_mode: GlowAttenuationMode::DivideExponent4,
+                    }),
+                );
+            }
+        }
+    }
+}
+
+fn to_u8(value: f32) -> u8 {
+    (value.max(0.0).min(1.0) * 255.0).round() as u8
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        load::mesh::{generate_meshes, obj::create_instructions_from_obj, BlendMode},
+        parse::mesh::instructions::post_process,
+        ColorU8RGB, ColorU8RGBA,
+    };
+    use glam::{Vec2, Vec3A};
+    use obj::{Obj, ObjData};
+    use std::{io::Cursor, path::PathBuf};
+
+    const QUAD: &str = "
+mtllib quad.mtl
+v 0 0 0
+v 1 0 0
+v 1 1 0
+v 0 1 0
+vt 0 0
+vt 1 0
+vt 1 1
+vt 0 1
+g textured
+usemtl textured
+f 1/1 2/2 3/3 4/4
+g glowing
+usemtl glowing
+f 1/1 2/2 3/3
+";
+
+    const MATERIALS: &str = "
+newmtl textured
+Kd 1 0.5 0
+d 0.5
+map_Kd quad.png
+
+newmtl glowing
+Kd 0 0 0
+Ke 1 1 0
+";
+
+    fn load(source: &str, materials: &str) -> ObjData {
+        let data = ObjData::load_buf(&mut Cursor::new(source)).expect("Unable to parse obj");
+        let mut obj = Obj {
+            data,
+            path: PathBuf::new(),
+        };
+        obj.load_mtls_fn(|_, _| Ok(Cursor::new(materials.to_owned())))
+            .expect("Unable to parse mtl");
+        obj.data
+    }
+
+    #[bve_derive::bve_test]
+    #[test]
+    fn geometry() {
+        let data = load(QUAD, MATERIALS);
+        let result = generate_meshes(post_process(create_instructions_from_obj(&data)));
+        assert_eq!(result.errors, vec![]);
+        assert_eq!(result.meshes.len(), 2);
+
+        let mesh = &result.meshes[0];
+        assert_eq!(mesh.vertices.len(), 4);
+        assert_eq!(mesh.indices.len(), 6);
+        let corner = mesh
+            .vertices
+            .iter()
+            .find(|v| v.position == Vec3A::new(0.0, 1.0, 0.0))
+            .expect("Missing vertex");
+        assert_eq!(corner.coord, Vec2::new(0.0, 0.0));
+        // Reversing the winding keeps the face pointing the same way once z is flipped
+        assert_eq!(corner.normal, Vec3A::new(0.0, 0.0, -1.0));
+    }
+
+    #[bve_derive::bve_test]
+    #[test]
+    fn materials() {
+        let data = load(QUAD, MATERIALS);
+        let result = generate_meshes(post_process(create_instructions_from_obj(&data)));
+
+        let textured = &result.meshes[0];
+        assert_eq!(textured.color, ColorU8RGBA::new(255, 128, 0, 128));
+        assert_eq!(textured.texture.texture_id, Some(0));
+        assert_eq!(result.textures.lookup(0), Some("quad.png"));
+        assert_eq!(textured.blend_mode, BlendMode::Normal);
+
+        let glowing = &result.meshes[1];
+        assert_eq!(glowing.color, ColorU8RGBA::new(0, 0, 0, 255));
+        assert_eq!(glowing.texture.texture_id, None);
+        assert_eq!(glowing.texture.emission_color, ColorU8RGB::new(255, 255, 0));
+        assert_eq!(glowing.blend_mode, BlendMode::Additive);
+    }
+}
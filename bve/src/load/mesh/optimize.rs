@@ -0,0 +1,498 @@
+//! Optional optimizations of a [`LoadedStaticMesh`] before it is uploaded.
+//!
+//! Static objects made for BVE are usually made of many tiny meshes, with every face having its own vertices. This
+//! merges adjacent meshes that render identically, welds vertices that are the same, and orders triangles so the GPU's
+//! vertex cache gets reused.
+
+use crate::load::mesh::{LoadedStaticMesh, Mesh, Vertex};
+use glam::Vec3A;
+use std::{
+    collections::{HashMap, VecDeque},
+    fmt,
+};
+
+/// Welding tolerance that is well below anything visible.
+pub const DEFAULT_WELD_TOLERANCE: f32 = 0.0001;
+
+/// Which optimizations [`optimize_mesh`] runs.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct OptimizationSettings {
+    /// Merge meshes with the same texture, color, blend mode, and glow.
+    pub merge_meshes: bool,
+    /// Weld vertices whose attributes are all within the given distance of each other.
+    pub weld_tolerance: Option<f32>,
+    /// Reorder triangles and vertices for vertex cache locality.
+    pub reorder_indices: bool,
+}
+
+impl Default for OptimizationSettings {
+    #[must_use]
+    fn default() -> Self {
+        Self {
+            merge_meshes: true,
+            weld_tolerance: Some(DEFAULT_WELD_TOLERANCE),
+            reorder_indices: true,
+        }
+    }
+}
+
+/// Size of a [`LoadedStaticMesh`].
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct MeshStatistics {
+    pub meshes: usize,
+    pub vertices: usize,
+    pub triangles: usize,
+    /// Average cache miss ratio: vertex shader invocations per triangle with a 16 entry FIFO vertex cache.
+    ///
+    /// 3.0 means no vertex is ever reused, 0.5 is the best possible for a large regular grid.
+    pub acmr: f32,
+}
+
+impl MeshStatistics {
+    #[must_use]
+    pub fn from_mesh(mesh: &LoadedStaticMesh) -> Self {
+        let triangles = mesh.meshes.iter().map(|m| m.indices.len() / 3).sum();
+        let misses: usize = mesh.meshes.iter().map(|m| cache_misses(&m.indices)).sum();
+        Self {
+            meshes: mesh.meshes.len(),
+            vertices: mesh.meshes.iter().map(|m| m.vertices.len()).sum(),
+            triangles,
+            acmr: if triangles == 0 {
+                0.0
+            } else {
+                misses as f32 / triangles as f32
+            },
+        }
+    }
+}
+
+impl fmt::Display for MeshStatistics {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} meshes, {} vertices, {} triangles, {:.3} ACMR",
+            self.meshes, self.vertices, self.triangles, self.acmr
+        )
+    }
+}
+
+/// Statistics from before and after [`optimize_mesh`].
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct OptimizationStatistics {
+    pub before: MeshStatistics,
+    pub after: MeshStatistics,
+}
+
+impl fmt::Display for OptimizationStatistics {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "before: {}; after: {}", self.before, self.after)
+    }
+}
+
+/// Optimizes `mesh` in place. Meshes are drawn in the same order as before, so blended meshes look the same, but the
+/// triangles within a mesh may be drawn in a different order.
+pub fn optimize_mesh(mesh: &mut LoadedStaticMesh, settings: OptimizationSettings) -> OptimizationStatistics {
+    let before = MeshStatistics::from_mesh(mesh);
+
+    if settings.merge_meshes {
+        merge_meshes(&mut mesh.meshes);
+    }
+    for m in &mut mesh.meshes {
+        if let Some(tolerance) = settings.weld_tolerance {
+            weld_vertices(m, tolerance);
+        }
+        if settings.reorder_indices {
+            m.indices = reorder_triangles(&m.indices, m.vertices.len());
+            reorder_vertices(m);
+        }
+    }
+
+    OptimizationStatistics {
+        before,
+        after: MeshStatistics::from_mesh(mesh),
+    }
+}
+
+/// Meshes render identically if everything but their geometry is the same. Meshes with text get their own texture,
+/// so are never merged.
+fn same_material(a: &Mesh, b: &Mesh) -> bool {
+    a.text.is_none()
+        && b.text.is_none()
+        && a.texture == b.texture
+        && a.color == b.color
+        && a.blend_mode == b.blend_mode
+        && a.glow == b.glow
+}
+
+/// Merges each mesh into the mesh before it if they have the same material. Meshes further apart aren't merged, as
+/// that would draw them before the meshes in between, which shows when they're blended.
+fn merge_meshes(meshes: &mut Vec<Mesh>) {
+    let mut merged: Vec<Mesh> = Vec::with_capacity(meshes.len());
+    for mesh in meshes.drain(..) {
+        match merged.last_mut().filter(|m| same_material(m, &mesh)) {
+            Some(target) => {
+                let offset = target.vertices.len();
+                target.vertices.extend_from_slice(&mesh.vertices);
+                target.indices.extend(mesh.indices.iter().map(|i| i + offset));
+            }
+            None => merged.push(mesh),
+        }
+    }
+    *meshes = merged;
+}
+
+fn within(a: f32, b: f32, tolerance: f32) -> bool {
+    (a - b).abs() <= tolerance
+}
+
+fn within_vec3(a: Vec3A, b: Vec3A, tolerance: f32) -> bool {
+    within(a.x(), b.x(), tolerance) && within(a.y(), b.y(), tolerance) && within(a.z(), b.z(), tolerance)
+}
+
+fn can_weld(a: &Vertex, b: &Vertex, tolerance: f32) -> bool {
+    within_vec3(a.position, b.position, tolerance)
+        && within_vec3(a.normal, b.normal, tolerance)
+        && within(a.coord.x(), b.coord.x(), tolerance)
+        && within(a.coord.y(), b.coord.y(), tolerance)
+        && a.color == b.color
+        && a.double_sided == b.double_sided
+}
+
+type GridCell = (i64, i64, i64);
+
+/// Replaces all vertices within `tolerance` of an earlier vertex with that vertex, then removes triangles which
+/// became degenerate.
+fn weld_vertices(mesh: &mut Mesh, tolerance: f32) {
+    // Vertices are sorted into a grid of `cell_size` cubes, so only the surrounding cells need to be searched.
+    let cell_size = tolerance.max(f32::EPSILON);
+    let cell = |position: Vec3A| -> GridCell {
+        let scaled = position / cell_size;
+        (
+            scaled.x().round() as i64,
+            scaled.y().round() as i64,
+            scaled.z().round() as i64,
+        )
+    };
+
+    let mut grid: HashMap<GridCell, Vec<usize>> = HashMap::new();
+    let mut vertices: Vec<Vertex> = Vec::with_capacity(mesh.vertices.len());
+    let mut remap = Vec::with_capacity(mesh.vertices.len());
+    for vertex in &mesh.vertices {
+        let (x, y, z) = cell(vertex.position);
+        let existing = (-1..=1)
+            .flat_map(|dx| (-1..=1).flat_map(move |dy| (-1..=1).map(move |dz| (x + dx, y + dy, z + dz))))
+            .filter_map(|key| grid.get(&key))
+            .flatten()
+            .copied()
+            .find(|&idx| can_weld(&vertices[idx], vertex, tolerance));
+        match existing {
+            Some(idx) => remap.push(idx),
+            None => {
+                grid.entry((x, y, z)).or_default().push(vertices.len());
+                remap.push(vertices.len());
+                vertices.push(*vertex);
+            }
+        }
+    }
+
+    let mut indices = Vec::with_capacity(mesh.indices.len());
+    for triangle in mesh.indices.chunks_exact(3) {
+        let (a, b, c) = (remap[triangle[0]], remap[triangle[1]], remap[triangle[2]]);
+        if a != b && b != c && a != c {
+            indices.extend_from_slice(&[a, b, c]);
+        }
+    }
+
+    mesh.vertices = vertices;
+    mesh.indices = indices;
+}
+
+/// Size of the FIFO cache used to calculate [`MeshStatistics::acmr`].
+const STATISTICS_CACHE_SIZE: usize = 16;
+
+fn cache_misses(indices: &[usize]) -> usize {
+    let mut cache = VecDeque::with_capacity(STATISTICS_CACHE_SIZE);
+    let mut misses = 0;
+    for &index in indices {
+        if !cache.contains(&index) {
+            misses += 1;
+            if cache.len() == STATISTICS_CACHE_SIZE {
+                cache.pop_front();
+            }
+            cache.push_back(index);
+        }
+    }
+    misses
+}
+
+// Tom Forsyth's Linear-Speed Vertex Cache Optimisation
+// https://tomforsyth1000.github.io/papers/fast_vert_cache_opt.html
+const CACHE_SIZE: usize = 32;
+const CACHE_DECAY_POWER: f32 = 1.5;
+const LAST_TRIANGLE_SCORE: f32 = 0.75;
+const VALENCE_BOOST_SCALE: f32 = 2.0;
+const VALENCE_BOOST_POWER: f32 = 0.5;
+
+/// How much adding a triangle using this vertex next is worth.
+fn vertex_score(cache_position: Option<usize>, remaining_triangles: usize) -> f32 {
+    if remaining_triangles == 0 {
+        return -1.0;
+    }
+    let cache_score = match cache_position {
+        None => 0.0,
+        // The last triangle's vertices get a fixed score so the same triangle isn't favoured twice
+        Some(position) if position < 3 => LAST_TRIANGLE_SCORE,
+        Some(position) => {
+            let scale = 1.0 / (CACHE_SIZE - 3) as f32;
+            (1.0 - (position - 3) as f32 * scale).powf(CACHE_DECAY_POWER)
+        }
+    };
+    // Boost vertices with few triangles left, so lone triangles don't get left behind
+    let valence_score = VALENCE_BOOST_SCALE * (remaining_triangles as f32).powf(-VALENCE_BOOST_POWER);
+    cache_score + valence_score
+}
+
+/// Reorders the triangles in `indices` so vertices get reused while still in the vertex cache.
+fn reorder_triangles(indices: &[usize], vertex_count: usize) -> Vec<usize> {
+    let triangle_count = indices.len() / 3;
+
+    let mut vertex_triangles = vec![Vec::new(); vertex_count];
+    for (triangle, vertices) in indices.chunks_exact(3).enumerate() {
+        for &vertex in vertices {
+            vertex_triangles[vertex].push(triangle);
+        }
+    }
+    let mut remaining: Vec<usize> = vertex_triangles.iter().map(Vec::len).collect();
+    let mut cache_position: Vec<Option<usize>> = vec![None; vertex_count];
+    let mut vertex_scores: Vec<f32> = remaining.iter().map(|&r| vertex_score(None, r)).collect();
+    let triangle_score = |triangle: usize, vertex_scores: &[f32]| -> f32 {
+        indices[triangle * 3..triangle * 3 + 3]
+            .iter()
+            .map(|&v| vertex_scores[v])
+            .sum()
+    };
+    let mut triangle_scores: Vec<f32> = (0..triangle_count).map(|t| triangle_score(t, &vertex_scores)).collect();
+    let mut emitted = vec![false; triangle_count];
+
+    let mut cache: Vec<usize> = Vec::with_capacity(CACHE_SIZE + 3);
+    let mut output = Vec::with_capacity(triangle_count * 3);
+    // Triangles before this have all been emitted, used when the cache has nothing left to offer
+    let mut scan_start = 0;
+
+    let mut next = best_triangle(0..triangle_count, &triangle_scores, &emitted);
+    while let Some(triangle) = next {
+        emitted[triangle] = true;
+        let vertices = &indices[triangle * 3..triangle * 3 + 3];
+        output.extend_from_slice(vertices);
+
+        // Move this triangle's vertices to the front of the cache
+        let mut new_cache: Vec<usize> = vertices.to_vec();
+        new_cache.extend(cache.iter().copied().filter(|v| !vertices.contains(v)));
+        for &vertex in vertices {
+            remaining[vertex] -= 1;
+        }
+        for &evicted in new_cache.iter().skip(CACHE_SIZE) {
+            cache_position[evicted] = None;
+            vertex_scores[evicted] = vertex_score(None, remaining[evicted]);
+        }
+        new_cache.truncate(CACHE_SIZE);
+        for (position, &vertex) in new_cache.iter().enumerate() {
+            cache_position[vertex] = Some(position);
+            vertex_scores[vertex] = vertex_score(Some(position), remaining[vertex]);
+        }
+        cache = new_cache;
+
+        for &vertex in &cache {
+            for &t in &vertex_triangles[vertex] {
+                if !emitted[t] {
+                    triangle_scores[t] = triangle_score(t, &vertex_scores);
+                }
+            }
+        }
+
+        let candidates = cache.iter().flat_map(|&v| vertex_triangles[v].iter().copied());
+        next = best_triangle(candidates, &triangle_scores, &emitted);
+        if next.is_none() {
+            while scan_start < triangle_count && emitted[scan_start] {
+                scan_start += 1;
+            }
+            next = best_triangle(scan_start..triangle_count, &triangle_scores, &emitted);
+        }
+    }
+
+    output
+}
+
+/// Highest scoring triangle out of `candidates` which hasn't been emitted yet.
+fn best_triangle(candidates: impl Iterator<Item = usize>, scores: &[f32], emitted: &[bool]) -> Option<usize> {
+    candidates.filter(|&t| !emitted[t]).fold(None, |best, t| match best {
+        Some(b) if scores[b] >= scores[t] => Some(b),
+        _ => Some(t),
+    })
+}
+
+/// Orders vertices by their first use in the indices, removing unused vertices.
+fn reorder_vertices(mesh: &mut Mesh) {
+    let old_vertices = &mesh.vertices;
+    let mut remap = vec![None; old_vertices.len()];
+    let mut vertices = Vec::with_capacity(old_vertices.len());
+    for index in &mut mesh.indices {
+        let old_index = *index;
+        *index = *remap[old_index].get_or_insert_with(|| {
+            vertices.push(old_vertices[old_index]);
+            vertices.len() - 1
+        });
+    }
+    mesh.vertices = vertices;
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        load::mesh::{generate_meshes, optimize::cache_misses, optimize_mesh, LoadedStaticMesh, OptimizationSettings},
+        parse::mesh::{
+            instructions::{create_instructions, post_process},
+            BlendMode, FileType,
+        },
+    };
+    use glam::Vec3A;
+    use itertools::Itertools;
+
+    fn load(source: &str) -> LoadedStaticMesh {
+        generate_meshes(post_process(create_instructions(source, FileType::CSV)))
+    }
+
+    /// Sorted list of triangles by position, independent of vertex and triangle order.
+    fn triangles(mesh: &LoadedStaticMesh) -> Vec<[[u32; 3]; 3]> {
+        let mut triangles = mesh
+            .meshes
+            .iter()
+            .flat_map(|m| {
+                m.indices.iter().tuples().map(move |(&a, &b, &c)| {
+                    let p = |i: usize| {
+                        let v: Vec3A = m.vertices[i].position;
+                        [v.x().to_bits(), v.y().to_bits(), v.z().to_bits()]
+                    };
+                    [p(a), p(b), p(c)]
+                })
+            })
+            .collect::<Vec<_>>();
+        triangles.sort();
+        triangles
+    }
+
+    const GRID: &str = indoc::indoc!(
+        r#"
+        CreateMeshBuilder
+        AddVertex, 0, 0, 0
+        AddVertex, 1, 0, 0
+        AddVertex, 1, 0, 1
+        AddVertex, 0, 0, 1
+        AddFace, 0, 1, 2, 3
+        CreateMeshBuilder
+        AddVertex, 1, 0, 0
+        AddVertex, 2, 0, 0
+        AddVertex, 2, 0, 1
+        AddVertex, 1, 0, 1
+        AddFace, 0, 1, 2, 3
+        CreateMeshBuilder
+        AddVertex, 0, 0, 1
+        AddVertex, 1, 0, 1
+        AddVertex, 1, 0, 2
+        AddVertex, 0, 0, 2
+        AddFace, 0, 1, 2, 3
+        SetColor, 255, 0, 0
+    "#
+    );
+
+    #[bve_derive::bve_test]
+    #[test]
+    fn merge_and_weld() {
+        let original = load(GRID);
+        let mut mesh = original.clone();
+        let stats = optimize_mesh(&mut mesh, OptimizationSettings::default());
+
+        assert_eq!(stats.before.meshes, 3);
+        assert_eq!(stats.before.vertices, 12);
+        assert_eq!(stats.before.triangles, 6);
+        // The red mesh can't be merged with the other two
+        assert_eq!(stats.after.meshes, 2);
+        // The two white quads share an edge
+        assert_eq!(stats.after.vertices, 6 + 4);
+        assert_eq!(stats.after.triangles, 6);
+        assert!(stats.after.acmr < stats.before.acmr);
+        assert_eq!(triangles(&mesh), triangles(&original));
+    }
+
+    #[bve_derive::bve_test]
+    #[test]
+    fn merge_keeps_draw_order() {
+        let mut mesh = load(indoc::indoc!(
+            r#"
+            CreateMeshBuilder
+            Cube, 1, 1, 1
+            CreateMeshBuilder
+            Cube, 1, 1, 1
+            SetBlendMode, Additive
+            CreateMeshBuilder
+            Cube, 1, 1, 1
+        "#
+        ));
+        optimize_mesh(&mut mesh, OptimizationSettings::default());
+        // The first and last cube match, but merging them would draw the last one before the additive one
+        assert_eq!(mesh.meshes.len(), 3);
+        assert_eq!(mesh.meshes[1].blend_mode, BlendMode::Additive);
+    }
+
+    #[bve_derive::bve_test]
+    #[test]
+    fn disabled() {
+        let original = load(GRID);
+        let mut mesh = original.clone();
+        let stats = optimize_mesh(&mut mesh, OptimizationSettings {
+            merge_meshes: false,
+            weld_tolerance: None,
+            reorder_indices: false,
+        });
+        assert_eq!(stats.before, stats.after);
+        assert_eq!(mesh, original);
+    }
+
+    #[bve_derive::bve_test]
+    #[test]
+    fn weld_tolerance() {
+        let mut mesh = load("CreateMeshBuilder\nCube, 1, 1, 1\nCreateMeshBuilder\nCube, 1.00001, 1, 1");
+        optimize_mesh(&mut mesh, OptimizationSettings {
+            merge_meshes: true,
+            weld_tolerance: Some(0.001),
+            reorder_indices: false,
+        });
+        // Each cube face has its own normal, so only the vertices of the matching faces are welded
+        assert_eq!(mesh.meshes.len(), 1);
+        assert_eq!(mesh.meshes[0].vertices.len(), 24);
+        assert_eq!(mesh.meshes[0].indices.len(), 72);
+    }
+
+    #[bve_derive::bve_test]
+    #[test]
+    fn reorder() {
+        // Triangle strip of a long grid, with the triangles in a cache hostile order
+        let width = 64;
+        let mut source = String::from("CreateMeshBuilder\n");
+        for x in 0..=width {
+            source.push_str(&format!("AddVertex, {}, 0, 0\nAddVertex, {}, 0, 1\n", x, x));
+        }
+        for x in (0..width).step_by(2).chain((1..width).step_by(2)) {
+            let i = x * 2;
+            source.push_str(&format!("AddFace, {}, {}, {}, {}\n", i, i + 2, i + 3, i + 1));
+        }
+        let original = load(&source);
+        let mut mesh = original.clone();
+        let stats = optimize_mesh(&mut mesh, OptimizationSettings::default());
+
+        assert_eq!(stats.after.vertices, (width + 1) * 2);
+        assert!(stats.after.acmr < 1.1, "{}", stats);
+        assert!(cache_misses(&mesh.meshes[0].indices) < cache_misses(&original.meshes[0].indices));
+        assert_eq!(triangles(&mesh), triangles(&original));
+    }
+}
@@ -0,0 +1,530 @@
+//! DirectX `.x` meshes in the text format.
+//!
+//! Like obj files, these are translated into an [`InstructionList`]. Each material used by a `Mesh` becomes its own
+//! mesh, as materials are per-mesh properties in BVE. Frame transforms are applied to the vertices directly.
+
+use crate::{
+    load::mesh::x::syntax::{parse_x, XData, XObject},
+    parse::{
+        mesh::{
+            instructions::{
+                AddFace, AddVertex, CreateMeshBuilder, Instruction, InstructionData, InstructionList, LoadTexture,
+                SetColor, SetEmissiveColor, Sides,
+            },
+            MeshError, MeshErrorKind, MeshWarning, MeshWarningKind,
+        },
+        Span,
+    },
+    ColorU8RGB, ColorU8RGBA,
+};
+use glam::{Mat4, Vec2, Vec3, Vec3A};
+use std::collections::{BTreeMap, HashMap};
+
+mod syntax;
+
+/// Parses a text `.x` file into mesh instructions.
+#[must_use]
+pub fn create_instructions_from_x(input: &str) -> InstructionList {
+    let mut errors = Vec::new();
+    let objects = parse_x(input, &mut errors);
+
+    let mut ctx = XContext {
+        materials: HashMap::new(),
+        instructions: Vec::new(),
+        warnings: Vec::new(),
+        errors,
+    };
+    for object in &objects {
+        ctx.collect_materials(object);
+    }
+    for object in &objects {
+        ctx.object(object, Mat4::identity());
+    }
+
+    InstructionList {
+        instructions: ctx.instructions,
+        warnings: ctx.warnings,
+        errors: ctx.errors,
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct XMaterial {
+    color: ColorU8RGBA,
+    emissive: ColorU8RGB,
+    texture: Option<String>,
+}
+
+impl Default for XMaterial {
+    fn default() -> Self {
+        Self {
+            color: ColorU8RGBA::splat(255),
+            emissive: ColorU8RGB::splat(0),
+            texture: None,
+        }
+    }
+}
+
+/// Normals of a mesh, with a separate list of indices into them for each face.
+struct XNormals {
+    normals: Vec<Vec3A>,
+    faces: Vec<Vec<usize>>,
+}
+
+struct XContext {
+    /// Named materials, which can be referenced from any material list.
+    materials: HashMap<String, XMaterial>,
+    instructions: Vec<Instruction>,
+    warnings: Vec<MeshWarning>,
+    errors: Vec<MeshError>,
+}
+
+impl XContext {
+    fn collect_materials(&mut self, object: &XObject) {
+        if object.is("Material") {
+            if let Some(name) = &object.name {
+                match read_material(object) {
+                    Ok(material) => {
+                        self.materials.insert(name.clone(), material);
+                    }
+                    Err(error) => self.errors.push(error),
+                }
+            }
+        } else {
+            for child in object.children() {
+                self.collect_materials(child);
+            }
+        }
+    }
+
+    fn object(&mut self, object: &XObject, transform: Mat4) {
+        if object.is("Frame") {
+            self.frame(object, transform);
+        } else if object.is("Mesh") {
+            if let Err(error) = self.mesh(object, transform) {
+                self.errors.push(error);
+            }
+        } else if !(object.is("Header") || object.is("Material") || object.is("FrameTransformMatrix")) {
+            self.warnings.push(MeshWarning {
+                kind: MeshWarningKind::UnsupportedXTemplate {
+                    name: object.template.clone(),
+                },
+                location: object.span,
+            });
+        }
+    }
+
+    fn frame(&mut self, frame: &XObject, transform: Mat4) {
+        let mut local = Mat4::identity();
+        for matrix in frame.children().filter(|c| c.is("FrameTransformMatrix")) {
+            let mut reader = DataReader::new(matrix);
+            let mut values = [0.0; 16];
+            let result: Result<(), MeshError> = try {
+                for value in &mut values {
+                    *value = reader.number()?;
+                }
+            };
+            match result {
+                // Matrices are stored row major, for use with row vectors, which is the same layout as column major
+                // for use with column vectors.
+                Ok(()) => local = Mat4::from_cols_array(&values),
+                Err(error) => self.errors.push(error),
+            }
+        }
+
+        let transform = transform * local;
+        for child in frame.children() {
+            self.object(child, transform);
+        }
+    }
+
+    fn mesh(&mut self, mesh: &XObject, transform: Mat4) -> Result<(), MeshError> {
+        let mut reader = DataReader::new(mesh);
+
+        let vertex_count = reader.count()?;
+        let positions = (0..vertex_count)
+            .map(|_| reader.vec3().map(|v| transform.transform_point3(v).into()))
+            .collect::<Result<Vec<Vec3A>, _>>()?;
+
+        let face_count = reader.count()?;
+        let faces = (0..face_count).map(|_| reader.face()).collect::<Result<Vec<_>, _>>()?;
+
+        let mut materials = Vec::new();
+        let mut face_materials = Vec::new();
+        let mut coords = Vec::new();
+        let mut normals = None;
+        for child in mesh.children() {
+            let result: Result<(), MeshError> = try {
+                if child.is("MeshMaterialList") {
+                    let (list_materials, list_faces) = self.material_list(child)?;
+                    materials = list_materials;
+                    face_materials = list_faces;
+                } else if child.is("MeshTextureCoords") {
+                    let mut reader = DataReader::new(child);
+                    let count = reader.count()?;
+                    coords = (0..count)
+                        .map(|_| Ok::<_, MeshError>(Vec2::new(reader.number()?, reader.number()?)))
+                        .collect::<Result<_, _>>()?;
+                } else if child.is("MeshNormals") {
+                    normals = Some(read_normals(child, transform)?);
+                } else {
+                    self.warnings.push(MeshWarning {
+                        kind: MeshWarningKind::UnsupportedXTemplate {
+                            name: child.template.clone(),
+                        },
+                        location: child.span,
+                    });
+                }
+            };
+            if let Err(error) = result {
+                self.errors.push(error);
+            }
+        }
+
+        // Normals only apply if they line up with the faces
+        let normals = normals
+            .filter(|n| n.faces.len() == faces.len() && n.faces.iter().zip(&faces).all(|(n, f)| n.len() == f.len()));
+
+        // Group the faces by material. Faces past the end of the material indices use the last index.
+        let mut groups: BTreeMap<Option<usize>, Vec<usize>> = BTreeMap::new();
+        for face_idx in 0..faces.len() {
+            let material = face_materials.get(face_idx).or_else(|| face_materials.last()).copied();
+            let material = match material {
+                Some(idx) if idx >= materials.len() => {
+                    self.errors.push(MeshError {
+                        kind: MeshErrorKind::OutOfBounds { idx },
+                        location: mesh.span,
+                    });
+                    None
+                }
+                material => material,
+            };
+            groups.entry(material).or_default().push(face_idx);
+        }
+
+        for (material, group_faces) in groups {
+            self.push(InstructionData::CreateMeshBuilder(CreateMeshBuilder));
+            if let Some(material) = material {
+                self.push_material(&materials[material]);
+            }
+
+            // Vertices are split when they are used with differing normals
+            let mut translation = HashMap::new();
+            for face_idx in group_faces {
+                let mut indexes = Vec::with_capacity(faces[face_idx].len());
+                for (corner, &vertex) in faces[face_idx].iter().enumerate() {
+                    if vertex >= positions.len() {
+                        self.errors.push(MeshError {
+                            kind: MeshErrorKind::OutOfBounds { idx: vertex },
+                            location: mesh.span,
+                        });
+                        continue;
+                    }
+                    let normal_idx = normals.as_ref().map(|n| n.faces[face_idx][corner]);
+
+                    let next_index = translation.len();
+                    let index = *translation.entry((vertex, normal_idx)).or_insert(next_index);
+                    if index == next_index {
+                        let normal = normals.as_ref().and_then(|n| n.normals.get(normal_idx?).copied());
+                        self.push(InstructionData::AddVertex(AddVertex {
+                            position: positions[vertex],
+                            normal: normal.unwrap_or_else(Vec3A::zero),
+                            texture_coord: coords.get(vertex).copied().unwrap_or_else(Vec2::zero),
+                        }));
+                    }
+                    indexes.push(index);
+                }
+                self.push(InstructionData::AddFace(AddFace {
+                    indexes,
+                    sides: Sides::One,
+                }));
+            }
+        }
+
+        Ok(())
+    }
+
+    fn material_list(&mut self, list: &XObject) -> Result<(Vec<XMaterial>, Vec<usize>), MeshError> {
+        let mut reader = DataReader::new(list);
+        let _material_count = reader.count()?;
+        let face_count = reader.count()?;
+        let face_materials = (0..face_count).map(|_| reader.count()).collect::<Result<_, _>>()?;
+
+        let mut materials = Vec::new();
+        for data in &list.data {
+            match data {
+                XData::Object(object) if object.is("Material") => match read_material(object) {
+                    Ok(material) => materials.push(material),
+                    Err(error) => {
+                        self.errors.push(error);
+                        materials.push(XMaterial::default());
+                    }
+                },
+                XData::Reference(name, span) => match self.materials.get(name) {
+                    Some(material) => materials.push(material.clone()),
+                    None => {
+                        self.errors.push(MeshError {
+                            kind: MeshErrorKind::UnknownXMaterial { name: name.clone() },
+                            location: *span,
+                        });
+                        materials.push(XMaterial::default());
+                    }
+                },
+                _ => {}
+            }
+        }
+
+        Ok((materials, face_materials))
+    }
+
+    fn push(&mut self, data: InstructionData) {
+        self.instructions.push(Instruction {
+            span: Span::none(),
+            data,
+        });
+    }
+
+    fn push_material(&mut self, material: &XMaterial) {
+        self.push(InstructionData::SetColor(SetColor { color: material.color }));
+        if material.emissive != ColorU8RGB::splat(0) {
+            self.push(InstructionData::SetEmissiveColor(SetEmissiveColor {
+                color: material.emissive,
+            }));
+        }
+        if let Some(texture) = &material.texture {
+            self.push(InstructionData::LoadTexture(LoadTexture {
+                daytime: texture.clone(),
+                nighttime: String::new(),
+            }));
+        }
+    }
+}
+
+fn read_material(material: &XObject) -> Result<XMaterial, MeshError> {
+    let mut reader = DataReader::new(material);
+    let color = ColorU8RGBA::new(reader.color()?, reader.color()?, reader.color()?, reader.color()?);
+    let _power = reader.number()?;
+    let _specular = reader.vec3()?;
+    let emissive = ColorU8RGB::new(reader.color()?, reader.color()?, reader.color()?);
+
+    let texture = material.children().find(|c| c.is("TextureFilename")).and_then(|t| {
+        t.data.iter().find_map(|d| match d {
+            XData::String(filename, _) => Some(filename.replace("\\\\", "\\")),
+            _ => None,
+        })
+    });
+
+    Ok(XMaterial {
+        color,
+        emissive,
+        texture,
+    })
+}
+
+fn read_normals(normals: &XObject, transform: Mat4) -> Result<XNormals, MeshError> {
+    let mut reader = DataReader::new(normals);
+    let normal_count = reader.count()?;
+    let normals = (0..normal_count)
+        .map(|_| reader.vec3().map(|v| transform.transform_vector3(v).normalize().into()))
+        .collect::<Result<_, _>>()?;
+    let face_count = reader.count()?;
+    let faces = (0..face_count).map(|_| reader.face()).collect::<Result<_, _>>()?;
+    Ok(XNormals { normals, faces })
+}
+
+/// Reads the numbers out of an object in order.
+struct DataReader<'a> {
+    object: &'a XObject,
+    numbers: Box<dyn Iterator<Item = f32> + 'a>,
+}
+
+impl<'a> DataReader<'a> {
+    fn new(object: &'a XObject) -> Self {
+        Self {
+            object,
+            numbers: Box::new(object.data.iter().filter_map(|d| match d {
+                XData::Number(number, _) => Some(*number),
+                _ => None,
+            })),
+        }
+    }
+
+    fn number(&mut self) -> Result<f32, MeshError> {
+        let object = self.object;
+        self.numbers.next().ok_or_else(|| MeshError {
+            kind: MeshErrorKind::UnexpectedXEnd {
+                template: object.template.clone(),
+            },
+            location: object.span,
+        })
+    }
+
+    fn count(&mut self) -> Result<usize, MeshError> {
+        self.number().map(|n| n.max(0.0) as usize)
+    }
+
+    fn color(&mut self) -> Result<u8, MeshError> {
+        self.number().map(|n| (n.max(0.0).min(1.0) * 255.0).round() as u8)
+    }
+
+    fn vec3(&mut self) -> Result<Vec3, MeshError> {
+        Ok(Vec3::new(self.number()?, self.number()?, self.number()?))
+    }
+
+    fn face(&mut self) -> Result<Vec<usize>, MeshError> {
+        let count = self.count()?;
+        (0..count).map(|_| self.count()).collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        load::mesh::{generate_meshes, x::create_instructions_from_x},
+        parse::{
+            mesh::{instructions::post_process, MeshError, MeshErrorKind, MeshWarning, MeshWarningKind},
+            Span,
+        },
+        ColorU8RGB, ColorU8RGBA,
+    };
+    use glam::{Vec2, Vec3A};
+
+    const TWO_QUADS: &str = indoc::indoc!(
+        r#"
+        xof 0303txt 0032
+        Material Red {
+            1.0;0.0;0.0;1.0;;
+            0.0;
+            0.0;0.0;0.0;;
+            0.5;0.0;0.0;;
+        }
+        Frame Root {
+            FrameTransformMatrix {
+                1.0,0.0,0.0,0.0,
+                0.0,1.0,0.0,0.0,
+                0.0,0.0,1.0,0.0,
+                10.0,0.0,0.0,1.0;;
+            }
+            Mesh Quads {
+                6;
+                0.0;0.0;0.0;,
+                1.0;0.0;0.0;,
+                1.0;1.0;0.0;,
+                0.0;1.0;0.0;,
+                0.0;2.0;0.0;,
+                1.0;2.0;0.0;;
+                2;
+                4;0,1,2,3;,
+                4;3,2,5,4;;
+                MeshMaterialList {
+                    2;
+                    2;
+                    0,
+                    1;;
+                    { Red }
+                    Material {
+                        1.0;1.0;1.0;0.5;;
+                        0.0;
+                        0.0;0.0;0.0;;
+                        0.0;0.0;0.0;;
+                        TextureFilename { "wood.png"; }
+                    }
+                }
+                MeshTextureCoords {
+                    6;
+                    0.0;1.0;,
+                    1.0;1.0;,
+                    1.0;0.0;,
+                    0.0;0.0;,
+                    0.0;0.0;,
+                    1.0;0.0;;
+                }
+                MeshNormals {
+                    1;
+                    0.0;0.0;-1.0;;
+                    2;
+                    4;0,0,0,0;,
+                    4;0,0,0,0;;
+                }
+            }
+        }
+    "#
+    );
+
+    #[bve_derive::bve_test]
+    #[test]
+    fn frames_and_materials() {
+        let result = generate_meshes(post_process(create_instructions_from_x(TWO_QUADS)));
+        assert_eq!(result.errors, vec![]);
+        assert_eq!(result.warnings, vec![]);
+        assert_eq!(result.meshes.len(), 2);
+
+        let red = &result.meshes[0];
+        assert_eq!(red.vertices.len(), 4);
+        assert_eq!(red.indices.len(), 6);
+        assert_eq!(red.color, ColorU8RGBA::new(255, 0, 0, 255));
+        assert_eq!(red.texture.emission_color, ColorU8RGB::new(128, 0, 0));
+        assert_eq!(red.texture.texture_id, None);
+        // Frame transform is applied
+        assert!(red.vertices.iter().all(|v| v.position.x() >= 10.0));
+        let corner = red
+            .vertices
+            .iter()
+            .find(|v| v.position == Vec3A::new(10.0, 0.0, 0.0))
+            .expect("Missing vertex");
+        assert_eq!(corner.coord, Vec2::new(0.0, 1.0));
+
+        let wood = &result.meshes[1];
+        assert_eq!(wood.color, ColorU8RGBA::new(255, 255, 255, 128));
+        assert_eq!(wood.texture.texture_id, Some(0));
+        assert_eq!(result.textures.lookup(0), Some("wood.png"));
+    }
+
+    #[bve_derive::bve_test]
+    #[test]
+    fn errors() {
+        let result = create_instructions_from_x(indoc::indoc!(
+            r#"
+            xof 0303txt 0032
+            AnimationSet {}
+            Mesh {
+                3;
+                0.0;0.0;0.0;,
+                1.0;0.0;0.0;,
+                1.0;1.0;0.0;;
+                1;
+                3;0,1,5;;
+                MeshMaterialList { 1; 1; 0;; { Missing } }
+            }
+            Mesh {
+                3;
+                0.0;0.0;0.0;;
+            }
+        "#
+        ));
+        assert_eq!(result.warnings, vec![MeshWarning {
+            kind: MeshWarningKind::UnsupportedXTemplate {
+                name: String::from("AnimationSet")
+            },
+            location: Span::from_line(2),
+        }]);
+        assert_eq!(result.errors, vec![
+            MeshError {
+                kind: MeshErrorKind::UnknownXMaterial {
+                    name: String::from("Missing")
+                },
+                location: Span::from_line(10),
+            },
+            MeshError {
+                kind: MeshErrorKind::OutOfBounds { idx: 5 },
+                location: Span::from_line(3),
+            },
+            MeshError {
+                kind: MeshErrorKind::UnexpectedXEnd {
+                    template: String::from("Mesh")
+                },
+                location: Span::from_line(12),
+            },
+        ]);
+    }
+}
@@ -0,0 +1,368 @@
+//! Tokenizer and generic object tree for the text `.x` format.
+//!
+//! The format is a tree of data objects which look like `Template Name { data... }`. The layout of the data
+//! is defined by the template, so this stage only separates it into numbers, strings, references, and child objects.
+//! Commas and semicolons are treated as whitespace, as many exporters are loose about which they use.
+
+use crate::parse::{
+    mesh::{MeshError, MeshErrorKind},
+    Span,
+};
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Identifier(String),
+    Number(f32),
+    String(String),
+    Guid,
+    Open,
+    Close,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct SpannedToken {
+    token: Token,
+    span: Span,
+}
+
+/// A single data object.
+#[derive(Debug, Clone, PartialEq)]
+pub struct XObject {
+    pub template: String,
+    pub name: Option<String>,
+    pub span: Span,
+    pub data: Vec<XData>,
+}
+
+impl XObject {
+    /// Case insensitive comparison to the template name.
+    pub fn is(&self, template: &str) -> bool {
+        self.template.eq_ignore_ascii_case(template)
+    }
+
+    pub fn children(&self) -> impl Iterator<Item = &Self> {
+        self.data.iter().filter_map(|d| match d {
+            XData::Object(o) => Some(o),
+            _ => None,
+        })
+    }
+}
+
+/// Members of a data object.
+#[derive(Debug, Clone, PartialEq)]
+pub enum XData {
+    Number(f32, Span),
+    String(String, Span),
+    /// Reference to an object defined elsewhere with `{ Name }`.
+    Reference(String, Span),
+    Object(XObject),
+}
+
+/// Parses a text `.x` file into its top level objects. Template definitions are skipped.
+pub fn parse_x(input: &str, errors: &mut Vec<MeshError>) -> Vec<XObject> {
+    let mut lines = input.lines();
+    let header_valid = lines.next().map_or(false, |header| {
+        header.starts_with("xof ") && header.get(8..11) == Some("txt")
+    });
+    if !header_valid {
+        errors.push(MeshError {
+            kind: MeshErrorKind::InvalidXHeader,
+            location: Span::from_line(1),
+        });
+        return Vec::new();
+    }
+
+    let tokens = tokenize(lines, errors);
+    let mut parser = Parser {
+        tokens: &tokens,
+        position: 0,
+        errors,
+    };
+
+    let mut objects = Vec::new();
+    while let Some(token) = parser.next() {
+        match &token.token {
+            Token::Identifier(ident) if ident == "template" => parser.skip_template(),
+            Token::Identifier(ident) => {
+                if let Some(object) = parser.object(ident.clone(), token.span) {
+                    objects.push(object);
+                }
+            }
+            _ => parser.unexpected(token),
+        }
+    }
+    objects
+}
+
+/// Tokenizes all lines after the header. Line numbers start at 2 to account for it.
+fn tokenize<'a>(lines: impl Iterator<Item = &'a str>, errors: &mut Vec<MeshError>) -> Vec<SpannedToken> {
+    let mut tokens = Vec::new();
+    for (line_idx, line) in lines.enumerate() {
+        let span = Span::from_line(line_idx + 2);
+        let mut push = |token| tokens.push(SpannedToken { token, span });
+
+        let mut idx = 0;
+        while let Some(c) = line[idx..].chars().next() {
+            let rest = &line[idx..];
+            match c {
+                '#' => break,
+                '/' if rest.starts_with("//") => break,
+                '{' => {
+                    push(Token::Open);
+                    idx += 1;
+                }
+                '}' => {
+                    push(Token::Close);
+                    idx += 1;
+                }
+                '"' => match rest[1..].find('"') {
+                    Some(end) => {
+                        push(Token::String(rest[1..=end].into()));
+                        idx += end + 2;
+                    }
+                    None => {
+                        errors.push(MeshError {
+                            kind: MeshErrorKind::UnexpectedXToken { token: rest.into() },
+                            location: span,
+                        });
+                        break;
+                    }
+                },
+                '<' => {
+                    push(Token::Guid);
+                    idx += rest.find('>').map_or(rest.len(), |end| end + 1);
+                }
+                c if is_separator(c) => idx += c.len_utf8(),
+                _ => {
+                    let end = rest
+                        .find(|c: char| is_separator(c) || "{}\"<".contains(c))
+                        .unwrap_or_else(|| rest.len());
+                    let word = &rest[..end];
+                    let numeric = c.is_ascii_digit() || c == '-' || c == '+' || c == '.';
+                    push(match word.parse() {
+                        Ok(number) if numeric => Token::Number(number),
+                        _ => Token::Identifier(word.into()),
+                    });
+                    idx += end;
+                }
+            }
+        }
+    }
+    tokens
+}
+
+fn is_separator(c: char) -> bool {
+    c.is_whitespace() || c == ',' || c == ';'
+}
+
+struct Parser<'a> {
+    tokens: &'a [SpannedToken],
+    position: usize,
+    errors: &'a mut Vec<MeshError>,
+}
+
+impl<'a> Parser<'a> {
+    fn next(&mut self) -> Option<&'a SpannedToken> {
+        let token = self.tokens.get(self.position);
+        self.position += 1;
+        token
+    }
+
+    fn peek(&self, offset: usize) -> Option<&'a Token> {
+        self.tokens.get(self.position + offset).map(|t| &t.token)
+    }
+
+    fn unexpected(&mut self, token: &SpannedToken) {
+        let token_text = match &token.token {
+            Token::Identifier(ident) => ident.clone(),
+            Token::Number(number) => number.to_string(),
+            Token::String(string) => format!("\"{}\"", string),
+            Token::Guid => String::from("<guid>"),
+            Token::Open => String::from("{"),
+            Token::Close => String::from("}"),
+        };
+        self.errors.push(MeshError {
+            kind: MeshErrorKind::UnexpectedXToken { token: token_text },
+            location: token.span,
+        });
+    }
+
+    fn skip_template(&mut self) {
+        let mut depth = 0_usize;
+        while let Some(token) = self.next() {
+            match token.token {
+                Token::Open => depth += 1,
+                Token::Close => {
+                    depth = depth.saturating_sub(1);
+                    if depth == 0 {
+                        return;
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Parses an object whose template identifier has already been consumed.
+    fn object(&mut self, template: String, span: Span) -> Option<XObject> {
+        let name = match self.peek(0) {
+            Some(Token::Identifier(name)) => {
+                self.position += 1;
+                Some(name.clone())
+            }
+            _ => None,
+        };
+
+        match self.next() {
+            Some(SpannedToken { token: Token::Open, .. }) => {}
+            Some(token) => {
+                self.unexpected(token);
+                return None;
+            }
+            None => {
+                self.errors.push(MeshError {
+                    kind: MeshErrorKind::UnexpectedXEnd { template },
+                    location: span,
+                });
+                return None;
+            }
+        }
+
+        let mut data = Vec::new();
+        loop {
+            let token = match self.next() {
+                Some(token) => token,
+                None => {
+                    self.errors.push(MeshError {
+                        kind: MeshErrorKind::UnexpectedXEnd { template },
+                        location: span,
+                    });
+                    return None;
+                }
+            };
+            match &token.token {
+                Token::Close => break,
+                Token::Guid => {}
+                Token::Number(number) => data.push(XData::Number(*number, token.span)),
+                Token::String(string) => data.push(XData::String(string.clone(), token.span)),
+                Token::Open => {
+                    if let Some(reference) = self.reference() {
+                        data.push(XData::Reference(reference, token.span));
+                    }
+                }
+                Token::Identifier(ident) => match (self.peek(0), self.peek(1)) {
+                    (Some(Token::Open), _) | (Some(Token::Identifier(_)), Some(Token::Open)) => {
+                        if let Some(object) = self.object(ident.clone(), token.span) {
+                            data.push(XData::Object(object));
+                        }
+                    }
+                    _ => self.unexpected(token),
+                },
+            }
+        }
+
+        Some(XObject {
+            template,
+            name,
+            span,
+            data,
+        })
+    }
+
+    /// Parses a reference whose opening brace has already been consumed.
+    fn reference(&mut self) -> Option<String> {
+        let mut name = None;
+        while let Some(token) = self.next() {
+            match &token.token {
+                Token::Close => return name,
+                Token::Identifier(ident) if name.is_none() => name = Some(ident.clone()),
+                Token::Guid => {}
+                _ => self.unexpected(token),
+            }
+        }
+        name
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        load::mesh::x::syntax::{parse_x, XData, XObject},
+        parse::{
+            mesh::{MeshError, MeshErrorKind},
+            Span,
+        },
+    };
+
+    #[bve_derive::bve_test]
+    #[test]
+    fn objects() {
+        let mut errors = Vec::new();
+        let objects = parse_x(
+            indoc::indoc!(
+                r#"
+                xof 0303txt 0032
+                template Header {
+                    <3D82AB43-62DA-11cf-AB39-0020AF71E433>
+                    WORD major;
+                }
+                Header { 1; 0; 1; }
+                // Comment
+                Material Red {
+                    1.0;0.0;0.0;1.0;;
+                    TextureFilename { "red.png"; }
+                }
+                Mesh { 1;, 2.5; { Red } }
+            "#
+            ),
+            &mut errors,
+        );
+        assert_eq!(errors, vec![]);
+        assert_eq!(objects.len(), 3);
+
+        assert!(objects[0].is("header"));
+        assert_eq!(objects[1].name.as_deref(), Some("Red"));
+        assert_eq!(objects[1].data[3], XData::Number(1.0, Span::from_line(9)));
+        let texture = objects[1].children().next().expect("Missing texture");
+        assert_eq!(texture, &XObject {
+            template: String::from("TextureFilename"),
+            name: None,
+            span: Span::from_line(10),
+            data: vec![XData::String(String::from("red.png"), Span::from_line(10))],
+        });
+        assert_eq!(
+            objects[2].data[2],
+            XData::Reference(String::from("Red"), Span::from_line(12))
+        );
+    }
+
+    #[bve_derive::bve_test]
+    #[test]
+    fn errors() {
+        let mut errors = Vec::new();
+        let objects = parse_x("xof 0303bin 0032\n", &mut errors);
+        assert_eq!(objects, vec![]);
+        assert_eq!(errors, vec![MeshError {
+            kind: MeshErrorKind::InvalidXHeader,
+            location: Span::from_line(1),
+        }]);
+
+        let mut errors = Vec::new();
+        let objects = parse_x("xof 0303txt 0032\nMesh {\n 1; junk;\n", &mut errors);
+        assert_eq!(objects, vec![]);
+        assert_eq!(errors, vec![
+            MeshError {
+                kind: MeshErrorKind::UnexpectedXToken {
+                    token: String::from("junk")
+                },
+                location: Span::from_line(3),
+            },
+            MeshError {
+                kind: MeshErrorKind::UnexpectedXEnd {
+                    template: String::from("Mesh")
+                },
+                location: Span::from_line(2),
+            }
+        ]);
+    }
+}
@@ -1 +1,4 @@
+pub mod animated;
 pub mod mesh;
+pub mod route;
+pub mod texture;
@@ -0,0 +1,438 @@
+use crate::{
+    load::route::RouteUnits,
+    parse::route::ir::{OptionsCantBehaviorMode, ParsedCommand, ParsedDirective},
+};
+use glam::{Mat4, Vec3};
+use std::cmp::Ordering;
+
+/// unit: mm
+const DEFAULT_GAUGE: f32 = 1435.0;
+/// unit: m
+const DEFAULT_BLOCK_LENGTH: f32 = 25.0;
+
+/// Where the rail is at a track position, and which way it faces.
+///
+/// World space has x to the right, y up and z forwards from the start of the route.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct TrackPose {
+    /// Center of the rail.
+    pub position: Vec3,
+    /// Unit vector along the rail, towards increasing track positions.
+    pub direction: Vec3,
+    /// Unit vector up from the rail, tilted by cant.
+    pub up: Vec3,
+    /// Unit vector to the right of the rail, tilted by cant.
+    pub side: Vec3,
+}
+
+impl TrackPose {
+    /// Transform from the space of an object placed on the rail into world space.
+    #[must_use]
+    pub fn transform(&self) -> Mat4 {
+        Mat4::from_cols(
+            self.side.extend(0.0),
+            self.up.extend(0.0),
+            self.direction.extend(0.0),
+            self.position.extend(1.0),
+        )
+    }
+}
+
+/// Stretch of rail over which the alignment doesn't change.
+#[derive(Debug, Copy, Clone, PartialEq)]
+struct Segment {
+    /// unit: m
+    start: f32,
+    /// Position of the rail at `start`.
+    origin: Vec3,
+    /// Horizontal angle of the rail at `start`, from +z towards +x. unit: radians
+    yaw: f32,
+    /// Angle of the rail above the horizontal. unit: radians
+    pitch: f32,
+    /// Positive curves right, negative curves left, zero is straight. unit: m
+    radius: f32,
+}
+
+impl Segment {
+    /// Position and yaw of the rail `distance` meters past `start`.
+    fn advance(&self, distance: f32) -> (Vec3, f32) {
+        let (pitch_sin, pitch_cos) = self.pitch.sin_cos();
+        let horizontal = distance * pitch_cos;
+        let (right, forward, yaw) = if self.radius == 0.0 {
+            (0.0, horizontal, self.yaw)
+        } else {
+            let angle = horizontal / self.radius;
+            let (angle_sin, angle_cos) = angle.sin_cos();
+            (
+                self.radius * (1.0 - angle_cos),
+                self.radius * angle_sin,
+                self.yaw + angle,
+            )
+        };
+        let (yaw_sin, yaw_cos) = self.yaw.sin_cos();
+        let offset = Vec3::new(
+            right * yaw_cos + forward * yaw_sin,
+            distance * pitch_sin,
+            forward * yaw_cos - right * yaw_sin,
+        );
+        (self.origin + offset, yaw)
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum AlignmentChange {
+    Curve {
+        /// unit: m
+        radius: f32,
+        /// unit: mm
+        cant: f32,
+    },
+    Turn {
+        ratio: f32,
+    },
+    Pitch {
+        /// unit: per mille
+        rate: f32,
+    },
+}
+
+/// Shape of the player's rail, built from `Track.Curve`, `Track.Turn` and `Track.Pitch`.
+///
+/// The rail starts at the origin facing +z. Each command changes the rail from its track position on. Cant is signed,
+/// with positive cant raising the left rail. When it changes, it moves linearly to the new value over the block
+/// before the `Track.Curve`, the same as the track followers of OpenBVE.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RailAlignment {
+    /// Sorted by start, never empty.
+    segments: Vec<Segment>,
+    /// Position and cant set by each `Track.Curve`, sorted by position. unit: m, mm
+    cants: Vec<(f32, f32)>,
+    /// Distance over which cant changes. unit: m
+    cant_transition: f32,
+    /// unit: mm
+    gauge: f32,
+}
+
+impl Default for RailAlignment {
+    fn default() -> Self {
+        Self {
+            segments: vec![Segment {
+                start: 0.0,
+                origin: Vec3::zero(),
+                yaw: 0.0,
+                pitch: 0.0,
+                radius: 0.0,
+            }],
+            cants: Vec::new(),
+            cant_transition: DEFAULT_BLOCK_LENGTH,
+            gauge: DEFAULT_GAUGE,
+        }
+    }
+}
+
+impl RailAlignment {
+    /// Build the alignment from every `Track.Curve`, `Track.Turn` and `Track.Pitch` in a route.
+    ///
+    /// Unit options, `Options.BlockLength`, `Options.CantBehavior` and `Route.Gauge` are honored as they are
+    /// encountered. With unsigned cant behavior, cant always raises the outer rail of the curve.
+    #[must_use]
+    pub fn from_directives(directives: &[ParsedDirective]) -> Self {
+        let mut units = RouteUnits::default();
+        let mut signed_cant = false;
+        let mut alignment = Self::default();
+        let mut changes = Vec::new();
+
+        for directive in directives {
+            let position = units.position_meters(&directive.position);
+            match &directive.command {
+                ParsedCommand::OptionsUnitOfLength(command) => units.apply_unit_of_length(command),
+                ParsedCommand::OptionsBlockLength(command) => {
+                    alignment.cant_transition = units.position_meters(&[command.length]);
+                }
+                ParsedCommand::OptionsCantBehavior(command) => {
+                    signed_cant = command.mode == OptionsCantBehaviorMode::Signed;
+                }
+                ParsedCommand::RouteGauge(command) => alignment.gauge = command.gauge,
+                ParsedCommand::TrackCurve(command) => {
+                    let radius = units.position_meters(&[command.curve]);
+                    let cant = if signed_cant {
+                        command.cant
+                    } else if radius == 0.0 {
+                        0.0
+                    } else {
+                        command.cant.abs().copysign(radius)
+                    };
+                    changes.push((position, AlignmentChange::Curve { radius, cant }));
+                }
+                ParsedCommand::TrackTurn(command) => {
+                    changes.push((position, AlignmentChange::Turn { ratio: command.turn }));
+                }
+                ParsedCommand::TrackPitch(command) => {
+                    // The slope in per mille, despite the name
+                    changes.push((position, AlignmentChange::Pitch { rate: command.accuracy }));
+                }
+                _ => {}
+            }
+        }
+
+        // Stable, so changes at the same position apply in file order
+        changes.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(Ordering::Equal));
+        if let Some(&(first, _)) = changes.first() {
+            alignment.segments[0].start = first.min(0.0);
+        }
+        for (position, change) in changes {
+            alignment.apply(position, change);
+        }
+        alignment
+    }
+
+    fn apply(&mut self, position: f32, change: AlignmentChange) {
+        let current = self.segments[self.segments.len() - 1];
+        let (origin, yaw) = current.advance(position - current.start);
+        let mut next = Segment {
+            start: position,
+            origin,
+            yaw,
+            ..current
+        };
+        match change {
+            AlignmentChange::Curve { radius, cant } => {
+                next.radius = radius;
+                self.cants.push((position, cant));
+            }
+            AlignmentChange::Turn { ratio } => next.yaw += ratio.atan(),
+            AlignmentChange::Pitch { rate } => next.pitch = (rate / 1000.0).atan(),
+        }
+
+        if current.start == position {
+            let last = self.segments.len() - 1;
+            self.segments[last] = next;
+        } else {
+            self.segments.push(next);
+        }
+    }
+
+    /// Cant of the rail at `position` meters. Positive raises the left rail. unit: mm
+    #[must_use]
+    pub fn cant_at(&self, position: f32) -> f32 {
+        let next = self.cants.iter().take_while(|&&(start, _)| start <= position).count();
+        let current = next.checked_sub(1).map_or(0.0, |index| self.cants[index].1);
+        match self.cants.get(next) {
+            Some(&(start, cant)) if position > start - self.cant_transition => {
+                let progress = (position - (start - self.cant_transition)) / self.cant_transition;
+                current + (cant - current) * progress
+            }
+            _ => current,
+        }
+    }
+
+    /// Position and orientation of the rail at `position` meters.
+    #[must_use]
+    pub fn pose_at(&self, position: f32) -> TrackPose {
+        let index = self
+            .segments
+            .iter()
+            .take_while(|segment| segment.start <= position)
+            .count()
+            .saturating_sub(1);
+        let segment = &self.segments[index];
+        let (origin, yaw) = segment.advance(position - segment.start);
+
+        let (yaw_sin, yaw_cos) = yaw.sin_cos();
+        let (pitch_sin, pitch_cos) = segment.pitch.sin_cos();
+        let direction = Vec3::new(yaw_sin * pitch_cos, pitch_sin, yaw_cos * pitch_cos);
+        let level_up = Vec3::new(-yaw_sin * pitch_sin, pitch_cos, -yaw_cos * pitch_sin);
+        let level_side = Vec3::new(yaw_cos, 0.0, -yaw_sin);
+
+        // Raising the left rail rolls the rail to the right
+        let roll = (self.cant_at(position) / self.gauge).max(-1.0).min(1.0).asin();
+        let (roll_sin, roll_cos) = roll.sin_cos();
+        TrackPose {
+            position: origin,
+            direction,
+            up: level_up * roll_cos + level_side * roll_sin,
+            side: level_side * roll_cos - level_up * roll_sin,
+        }
+    }
+
+    /// Pose of something `distance` meters along the rail from `position`, such as an object moved by a track follower
+    /// function.
+    #[must_use]
+    pub fn follow(&self, position: f32, distance: f32) -> TrackPose {
+        self.pose_at(position + distance)
+    }
+}
+
+/// Where an object was placed on the rail, for moving it with track follower functions.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct TrackPlacement<'a> {
+    pub alignment: &'a RailAlignment,
+    /// Track position the object was placed at. unit: m
+    pub position: f32,
+}
+
+impl TrackPlacement<'_> {
+    /// Transform in the space of the placed object that moves it `distance` meters along the rail, turning and
+    /// rolling it with the curves and cant it passes.
+    #[must_use]
+    pub fn follow(&self, distance: f32) -> Mat4 {
+        if distance == 0.0 {
+            return Mat4::identity();
+        }
+        let placed = self.alignment.pose_at(self.position).transform();
+        placed.inverse() * self.alignment.follow(self.position, distance).transform()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::parse::route::ir::{
+        OptionsBlockLength, OptionsCantBehavior, OptionsUnitOfLength, RouteGauge, TrackCurve, TrackPitch, TrackTurn,
+    };
+    use smallvec::smallvec;
+    use std::f32::consts::{FRAC_1_SQRT_2, FRAC_PI_2, FRAC_PI_4};
+
+    fn directive(position: f32, command: ParsedCommand) -> ParsedDirective {
+        ParsedDirective {
+            command,
+            position: smallvec![position],
+        }
+    }
+
+    fn curve(position: f32, curve: f32, cant: f32) -> ParsedDirective {
+        directive(position, ParsedCommand::TrackCurve(TrackCurve { curve, cant }))
+    }
+
+    fn assert_close(actual: Vec3, expected: Vec3) {
+        assert!((actual - expected).length() < 0.001, "{:?} != {:?}", actual, expected);
+    }
+
+    fn assert_orthonormal(pose: &TrackPose) {
+        for axis in &[pose.direction, pose.up, pose.side] {
+            assert!((axis.length() - 1.0).abs() < 0.001, "{:?}", pose);
+        }
+        assert_close(pose.side.cross(pose.up), pose.direction);
+    }
+
+    #[bve_derive::bve_test]
+    #[test]
+    fn straight() {
+        let alignment = RailAlignment::from_directives(&[]);
+        let pose = alignment.pose_at(100.0);
+        assert_close(pose.position, Vec3::new(0.0, 0.0, 100.0));
+        assert_close(pose.direction, Vec3::unit_z());
+        assert_close(pose.up, Vec3::unit_y());
+        assert_close(pose.side, Vec3::unit_x());
+        assert_eq!(alignment.follow(50.0, 50.0), pose);
+        assert_close(
+            pose.transform().transform_point3(Vec3::new(1.0, 2.0, 3.0)),
+            Vec3::new(1.0, 2.0, 103.0),
+        );
+    }
+
+    #[bve_derive::bve_test]
+    #[test]
+    fn curves() {
+        let radius = 200.0;
+        let quarter = radius * FRAC_PI_2;
+        let alignment = RailAlignment::from_directives(&[
+            curve(100.0, radius, 0.0),
+            curve(100.0 + quarter, -radius, 0.0),
+            curve(100.0 + quarter * 2.0, 0.0, 0.0),
+        ]);
+
+        let pose = alignment.pose_at(100.0 + quarter / 2.0);
+        assert_close(
+            pose.position,
+            Vec3::new(radius * (1.0 - FRAC_1_SQRT_2), 0.0, 100.0 + radius * FRAC_1_SQRT_2),
+        );
+        assert_close(pose.direction, Vec3::new(FRAC_1_SQRT_2, 0.0, FRAC_1_SQRT_2));
+        assert_orthonormal(&pose);
+
+        // Quarter circle right, facing +x
+        let pose = alignment.pose_at(100.0 + quarter);
+        assert_close(pose.position, Vec3::new(radius, 0.0, 100.0 + radius));
+        assert_close(pose.direction, Vec3::unit_x());
+        assert_close(pose.side, -Vec3::unit_z());
+
+        // Quarter circle left, facing +z again
+        let pose = alignment.pose_at(100.0 + quarter * 2.0 + 10.0);
+        assert_close(pose.position, Vec3::new(radius * 2.0, 0.0, 100.0 + radius * 2.0 + 10.0));
+        assert_close(pose.direction, Vec3::unit_z());
+    }
+
+    #[bve_derive::bve_test]
+    #[test]
+    fn turn_and_pitch() {
+        let alignment = RailAlignment::from_directives(&[
+            directive(
+                0.0,
+                ParsedCommand::OptionsUnitOfLength(OptionsUnitOfLength {
+                    factors: smallvec![2.0],
+                }),
+            ),
+            directive(50.0, ParsedCommand::TrackTurn(TrackTurn { turn: 1.0 })),
+            directive(50.0, ParsedCommand::TrackPitch(TrackPitch { accuracy: 1000.0 })),
+        ]);
+
+        // 45 degrees right and 45 degrees up, from 100m
+        let pose = alignment.pose_at(110.0);
+        let diagonal = 10.0 * FRAC_1_SQRT_2;
+        assert_close(
+            pose.position,
+            Vec3::new(diagonal * FRAC_1_SQRT_2, diagonal, 100.0 + diagonal * FRAC_1_SQRT_2),
+        );
+        assert_close(pose.direction, Vec3::new(0.5, FRAC_1_SQRT_2, 0.5));
+        assert_close(pose.side, Vec3::new(FRAC_1_SQRT_2, 0.0, -FRAC_1_SQRT_2));
+        assert_orthonormal(&pose);
+    }
+
+    #[bve_derive::bve_test]
+    #[test]
+    fn cant() {
+        let gauge = 1000.0;
+        let directives = |signed| {
+            vec![
+                directive(0.0, ParsedCommand::RouteGauge(RouteGauge { gauge })),
+                directive(
+                    0.0,
+                    ParsedCommand::OptionsBlockLength(OptionsBlockLength { length: 10.0 }),
+                ),
+                directive(
+                    0.0,
+                    ParsedCommand::OptionsCantBehavior(OptionsCantBehavior {
+                        mode: if signed {
+                            OptionsCantBehaviorMode::Signed
+                        } else {
+                            OptionsCantBehaviorMode::Unsigned
+                        },
+                    }),
+                ),
+                curve(100.0, -500.0, gauge * FRAC_1_SQRT_2),
+                curve(200.0, 0.0, 0.0),
+            ]
+        };
+
+        // Unsigned cant raises the outer rail, which is the right rail of a left curve
+        let alignment = RailAlignment::from_directives(&directives(false));
+        assert_eq!(alignment.cant_at(90.0), 0.0);
+        assert!((alignment.cant_at(95.0) + gauge * FRAC_1_SQRT_2 / 2.0).abs() < 0.001);
+        assert!((alignment.cant_at(150.0) + gauge * FRAC_1_SQRT_2).abs() < 0.001);
+        assert!((alignment.cant_at(195.0) + gauge * FRAC_1_SQRT_2 / 2.0).abs() < 0.001);
+        assert_eq!(alignment.cant_at(200.0), 0.0);
+
+        // Rolled 45 degrees left, to the inside of the curve
+        let pose = alignment.pose_at(100.0);
+        let (sin, cos) = FRAC_PI_4.sin_cos();
+        assert_close(pose.up, Vec3::new(-sin, cos, 0.0));
+        assert_close(pose.side, Vec3::new(cos, sin, 0.0));
+        assert_orthonormal(&pose);
+
+        // Signed cant is kept as given
+        let alignment = RailAlignment::from_directives(&directives(true));
+        let pose = alignment.pose_at(100.0);
+        assert_close(pose.up, Vec3::new(sin, cos, 0.0));
+        assert_close(pose.side, Vec3::new(cos, -sin, 0.0));
+    }
+}
@@ -0,0 +1,62 @@
+//! Simulation-ready data derived from a parsed route.
+
+use crate::parse::route::ir::{OptionsUnitOfLength, OptionsUnitOfSpeed};
+use smallvec::SmallVec;
+
+pub use alignment::*;
+pub use pretrain::*;
+pub use timetable::*;
+
+mod alignment;
+mod pretrain;
+mod timetable;
+
+/// Conversion factors set by `Options.UnitOfLength` and `Options.UnitOfSpeed`.
+///
+/// Route files can change their units at any point, so this is updated as the directive stream is walked.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RouteUnits {
+    /// Meters per unit for each component of a track position.
+    pub length_factors: SmallVec<[f32; 2]>,
+    /// km/h per unit of speed.
+    pub speed_factor: f32,
+}
+
+impl Default for RouteUnits {
+    fn default() -> Self {
+        Self {
+            length_factors: SmallVec::from_slice(&[1.0]),
+            speed_factor: 1.0,
+        }
+    }
+}
+
+impl RouteUnits {
+    /// Use the length factors of an `Options.UnitOfLength` for every track position after it.
+    pub fn apply_unit_of_length(&mut self, command: &OptionsUnitOfLength) {
+        self.length_factors = command.factors.clone();
+    }
+
+    /// Use the speed factor of an `Options.UnitOfSpeed` for every speed after it.
+    pub fn apply_unit_of_speed(&mut self, command: &OptionsUnitOfSpeed) {
+        self.speed_factor = command.factor;
+    }
+
+    /// Convert a multi-component track position into meters.
+    ///
+    /// Components without a matching length factor are ignored.
+    #[must_use]
+    pub fn position_meters(&self, position: &[f32]) -> f32 {
+        position
+            .iter()
+            .zip(self.length_factors.iter())
+            .map(|(component, factor)| component * factor)
+            .sum()
+    }
+
+    /// Convert a speed in route units into meters per second.
+    #[must_use]
+    pub fn speed_meters_per_second(&self, speed: f32) -> f32 {
+        speed * self.speed_factor / 3.6
+    }
+}
@@ -0,0 +1,327 @@
+use crate::{
+    load::route::RouteUnits,
+    parse::route::ir::{ParsedCommand, ParsedDirective},
+};
+use std::cmp::Ordering;
+
+/// A single `Track.PreTrain` entry. The pre-train must be at `position` by `time`.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct PreTrainPoint {
+    /// unit: m
+    pub position: f32,
+    /// unit: seconds since midnight
+    pub time: f32,
+}
+
+/// Where the pre-train is at a given moment.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct PreTrainState {
+    /// unit: m
+    pub position: f32,
+    /// Index of the `Track.Section` block the pre-train is in. `None` if it is before the first section.
+    pub section: Option<usize>,
+}
+
+/// Movement between two consecutive [`PreTrainPoint`]s, after the speed limit has been applied.
+#[derive(Debug, Copy, Clone, PartialEq)]
+struct Leg {
+    start_position: f32,
+    end_position: f32,
+    departure: f32,
+    arrival: f32,
+}
+
+/// Timed movement of the preceding AI train, built from `Track.PreTrain` and `Train.Velocity`.
+///
+/// Before the first point's time the pre-train waits at the first point. Between points it moves at a constant speed,
+/// limited by `Train.Velocity`. If the limit makes it late, it leaves the next point as soon as it arrives. Once it
+/// reaches the last point it leaves the route and is no longer reported.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct PreTrainSchedule {
+    points: Vec<PreTrainPoint>,
+    legs: Vec<Leg>,
+    /// Sorted start positions of every `Track.Section` block. unit: m
+    section_starts: Vec<f32>,
+    /// unit: m/s
+    max_speed: Option<f32>,
+}
+
+impl PreTrainSchedule {
+    /// Create a schedule from raw points.
+    ///
+    /// Points are sorted by time. A `max_speed` of `None` lets the pre-train move as fast as its schedule requires.
+    #[must_use]
+    pub fn new(mut points: Vec<PreTrainPoint>, max_speed: Option<f32>, mut section_starts: Vec<f32>) -> Self {
+        points.sort_by(|a, b| a.time.partial_cmp(&b.time).unwrap_or(Ordering::Equal));
+        section_starts.sort_by(|a, b| a.partial_cmp(b).unwrap_or(Ordering::Equal));
+
+        let max_speed = max_speed.filter(|&speed| speed > 0.0);
+
+        let mut legs = Vec::with_capacity(points.len().saturating_sub(1));
+        let mut ready = points.first().map_or(0.0, |p| p.time);
+        for pair in points.windows(2) {
+            let (from, to) = (pair[0], pair[1]);
+            let departure = ready.max(from.time);
+            let distance = (to.position - from.position).abs();
+            let mut arrival = to.time.max(departure);
+            if let Some(max_speed) = max_speed {
+                if distance > (arrival - departure) * max_speed {
+                    arrival = departure + distance / max_speed;
+                }
+            }
+            legs.push(Leg {
+                start_position: from.position,
+                end_position: to.position,
+                departure,
+                arrival,
+            });
+            ready = arrival;
+        }
+
+        Self {
+            points,
+            legs,
+            section_starts,
+            max_speed,
+        }
+    }
+
+    /// Build the schedule from every `Track.PreTrain`, `Train.Velocity` and `Track.Section` in a route.
+    ///
+    /// Unit options are honored as they are encountered.
+    #[must_use]
+    pub fn from_directives(directives: &[ParsedDirective]) -> Self {
+        let mut units = RouteUnits::default();
+        let mut points = Vec::new();
+        let mut section_starts = Vec::new();
+        let mut max_speed = None;
+
+        for directive in directives {
+            match &directive.command {
+                ParsedCommand::OptionsUnitOfLength(command) => units.apply_unit_of_length(command),
+                ParsedCommand::OptionsUnitOfSpeed(command) => units.apply_unit_of_speed(command),
+                ParsedCommand::TrainVelocity(command) => {
+                    max_speed = Some(units.speed_meters_per_second(command.max_ai_speed));
+                }
+                ParsedCommand::TrackPreTrain(command) => points.push(PreTrainPoint {
+                    position: units.position_meters(&directive.position),
+                    time: command.time.as_seconds() as f32,
+                }),
+                ParsedCommand::TrackSection(_) => section_starts.push(units.position_meters(&directive.position)),
+                _ => {}
+            }
+        }
+
+        Self::new(points, max_speed, section_starts)
+    }
+
+    /// Returns true if the route has no pre-train.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.points.is_empty()
+    }
+
+    /// The schedule's points, sorted by time.
+    #[must_use]
+    pub fn points(&self) -> &[PreTrainPoint] {
+        &self.points
+    }
+
+    /// Maximum speed of the pre-train in m/s, if limited.
+    #[must_use]
+    pub const fn max_speed(&self) -> Option<f32> {
+        self.max_speed
+    }
+
+    /// Position of the pre-train at `time` seconds since midnight.
+    ///
+    /// Returns `None` if there is no pre-train or it has already left the route.
+    #[must_use]
+    pub fn position_at(&self, time: f32) -> Option<f32> {
+        let first = self.points.first()?;
+        if time <= first.time {
+            return Some(first.position);
+        }
+
+        let idx = match self
+            .legs
+            .binary_search_by(|leg| leg.arrival.partial_cmp(&time).unwrap_or(Ordering::Equal))
+        {
+            Ok(idx) | Err(idx) => idx,
+        };
+        let leg = self.legs.get(idx)?;
+
+        if time <= leg.departure {
+            Some(leg.start_position)
+        } else {
+            let duration = leg.arrival - leg.departure;
+            let t = if duration > 0.0 {
+                (time - leg.departure) / duration
+            } else {
+                1.0
+            };
+            Some(leg.start_position + (leg.end_position - leg.start_position) * t)
+        }
+    }
+
+    /// Index of the `Track.Section` block containing `position`, or `None` if it is before the first block.
+    #[must_use]
+    pub fn section_at_position(&self, position: f32) -> Option<usize> {
+        self.section_starts
+            .iter()
+            .take_while(|&&start| start <= position)
+            .count()
+            .checked_sub(1)
+    }
+
+    /// Index of the `Track.Section` block occupied by the pre-train at `time`.
+    #[must_use]
+    pub fn section_at(&self, time: f32) -> Option<usize> {
+        self.section_at_position(self.position_at(time)?)
+    }
+
+    /// Position and section of the pre-train at `time`.
+    #[must_use]
+    pub fn state_at(&self, time: f32) -> Option<PreTrainState> {
+        let position = self.position_at(time)?;
+        Some(PreTrainState {
+            position,
+            section: self.section_at_position(position),
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        parse::route::ir::{OptionsUnitOfLength, TrackPreTrain, TrackSection, TrainVelocity},
+        Time,
+    };
+    use smallvec::smallvec;
+
+    fn point(position: f32, time: f32) -> PreTrainPoint {
+        PreTrainPoint { position, time }
+    }
+
+    fn assert_close(value: Option<f32>, expected: f32) {
+        let value = value.expect("Expected a pre-train position");
+        assert!((value - expected).abs() < 0.001, "{} != {}", value, expected);
+    }
+
+    #[bve_derive::bve_test]
+    #[test]
+    fn empty_schedule() {
+        let schedule = PreTrainSchedule::new(vec![], None, vec![0.0]);
+        assert!(schedule.is_empty());
+        assert_eq!(schedule.position_at(0.0), None);
+        assert_eq!(schedule.section_at(0.0), None);
+    }
+
+    #[bve_derive::bve_test]
+    #[test]
+    fn linear_interpolation() {
+        let schedule = PreTrainSchedule::new(vec![point(0.0, 100.0), point(1000.0, 200.0)], None, vec![]);
+        assert_close(schedule.position_at(0.0), 0.0);
+        assert_close(schedule.position_at(100.0), 0.0);
+        assert_close(schedule.position_at(150.0), 500.0);
+        assert_close(schedule.position_at(200.0), 1000.0);
+        assert_eq!(schedule.position_at(200.1), None);
+    }
+
+    #[bve_derive::bve_test]
+    #[test]
+    fn unsorted_points() {
+        let schedule = PreTrainSchedule::new(
+            vec![point(2000.0, 300.0), point(0.0, 100.0), point(1000.0, 200.0)],
+            None,
+            vec![],
+        );
+        assert_close(schedule.position_at(150.0), 500.0);
+        assert_close(schedule.position_at(250.0), 1500.0);
+    }
+
+    #[bve_derive::bve_test]
+    #[test]
+    fn speed_limit_delays_train() {
+        // 1000m in 10s would be 100m/s, the limit is 10m/s so it takes 100s
+        let schedule = PreTrainSchedule::new(
+            vec![point(0.0, 0.0), point(1000.0, 10.0), point(1500.0, 200.0)],
+            Some(10.0),
+            vec![],
+        );
+        assert_close(schedule.position_at(10.0), 100.0);
+        assert_close(schedule.position_at(100.0), 1000.0);
+        // Departs immediately on arrival, 500m over the remaining 100s
+        assert_close(schedule.position_at(150.0), 1250.0);
+        assert_close(schedule.position_at(200.0), 1500.0);
+    }
+
+    #[bve_derive::bve_test]
+    #[test]
+    fn sections() {
+        let schedule = PreTrainSchedule::new(vec![point(0.0, 0.0), point(300.0, 30.0)], None, vec![200.0, 0.0, 100.0]);
+        assert_eq!(schedule.section_at_position(-1.0), None);
+        assert_eq!(schedule.section_at(0.0), Some(0));
+        assert_eq!(schedule.section_at(15.0), Some(1));
+        assert_eq!(
+            schedule.state_at(25.0),
+            Some(PreTrainState {
+                position: 250.0,
+                section: Some(2),
+            })
+        );
+        assert_eq!(schedule.section_at(31.0), None);
+    }
+
+    #[bve_derive::bve_test]
+    #[test]
+    fn from_directives() {
+        let time = |hours, minutes| Time {
+            hours,
+            minutes,
+            seconds: 0,
+        };
+        let directives = vec![
+            ParsedDirective {
+                command: ParsedCommand::OptionsUnitOfLength(OptionsUnitOfLength {
+                    factors: smallvec![2.0],
+                }),
+                position: smallvec![0.0],
+            },
+            ParsedDirective {
+                command: ParsedCommand::TrainVelocity(TrainVelocity { max_ai_speed: 18.0 }),
+                position: smallvec![0.0],
+            },
+            ParsedDirective {
+                command: ParsedCommand::TrackSection(TrackSection {
+                    sections: smallvec![0, 2],
+                }),
+                position: smallvec![0.0],
+            },
+            ParsedDirective {
+                command: ParsedCommand::TrackPreTrain(TrackPreTrain { time: time(10, 0) }),
+                position: smallvec![0.0],
+            },
+            ParsedDirective {
+                command: ParsedCommand::TrackSection(TrackSection {
+                    sections: smallvec![0, 2],
+                }),
+                position: smallvec![100.0],
+            },
+            ParsedDirective {
+                command: ParsedCommand::TrackPreTrain(TrackPreTrain { time: time(10, 1) }),
+                position: smallvec![250.0],
+            },
+        ];
+
+        let schedule = PreTrainSchedule::from_directives(&directives);
+        assert_eq!(schedule.points(), &[point(0.0, 36000.0), point(500.0, 36060.0)]);
+        assert_close(schedule.max_speed(), 5.0);
+        // 500m at 5m/s takes 100s, arriving after the scheduled time
+        assert_close(schedule.position_at(36050.0), 250.0);
+        assert_close(schedule.position_at(36100.0), 500.0);
+        assert_eq!(schedule.section_at(36010.0), Some(0));
+        assert_eq!(schedule.section_at(36050.0), Some(1));
+    }
+}
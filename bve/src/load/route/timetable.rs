@@ -0,0 +1,465 @@
+use crate::{
+    load::route::RouteUnits,
+    parse::route::ir::{ArrivalTimeState, DepartureTimeState, ParsedCommand, ParsedDirective, TimetableSuffix},
+    Time,
+};
+use async_std::path::{Path, PathBuf};
+use image::{Rgba, RgbaImage};
+use once_cell::sync::OnceCell;
+use std::{cmp::Ordering, collections::BTreeMap, sync::Arc};
+
+/// Images given to a single timetable index by `Train.Timetable`.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct TimetableImages {
+    /// Joined onto the train folder.
+    pub day: Option<PathBuf>,
+    /// Joined onto the train folder.
+    pub night: Option<PathBuf>,
+}
+
+impl TimetableImages {
+    /// The night image if `night` is set, otherwise the day image. Falls back to the other one if it is missing.
+    #[must_use]
+    pub fn image(&self, night: bool) -> Option<&Path> {
+        let (wanted, other) = if night {
+            (&self.night, &self.day)
+        } else {
+            (&self.day, &self.night)
+        };
+        wanted.as_deref().or_else(|| other.as_deref())
+    }
+}
+
+/// A station as it appears in the timetable.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TimetableStation {
+    pub name: String,
+    /// unit: m
+    pub position: f32,
+    pub arrival: Option<Time>,
+    pub departure: Option<Time>,
+    /// The player's train doesn't stop here.
+    pub passes: bool,
+}
+
+/// What to show on objects with a [`TextureOverride::Timetable`](crate::parse::animated::TextureOverride::Timetable).
+#[derive(Debug, Clone, PartialEq)]
+pub enum TimetableTexture<'a> {
+    /// Image given by `Train.Timetable`, joined onto the train folder.
+    Image(&'a Path),
+    /// Generated from the stations of the route, as there is no image. Only rendered once per route.
+    Text(Arc<RgbaImage>),
+}
+
+/// Every timetable of a route, built from `Train.Timetable` and `Track.Sta`.
+///
+/// Timetable 0 is shown from the start of the route. Each station with a timetable index switches to that timetable
+/// once the train reaches it.
+#[derive(Debug, Default, Clone)]
+pub struct RouteTimetables {
+    images: BTreeMap<u64, TimetableImages>,
+    /// Position each timetable is switched to at, sorted by position. unit: m
+    changes: Vec<(f32, u64)>,
+    /// Sorted by position.
+    stations: Vec<TimetableStation>,
+    /// Timetable generated from `stations`, rendered the first time it's needed.
+    rendered: OnceCell<Arc<RgbaImage>>,
+}
+
+impl PartialEq for RouteTimetables {
+    /// The generated timetable only depends on the stations, so isn't compared.
+    fn eq(&self, other: &Self) -> bool {
+        self.images == other.images && self.changes == other.changes && self.stations == other.stations
+    }
+}
+
+impl RouteTimetables {
+    /// Build the timetables from every `Train.Timetable` and `Track.Sta` in a route.
+    ///
+    /// Images are given relative to the train folder, so are joined onto `train_folder`. Unit options are honored as
+    /// they are encountered.
+    #[must_use]
+    pub fn from_directives(directives: &[ParsedDirective], train_folder: &Path) -> Self {
+        let mut units = RouteUnits::default();
+        let mut images: BTreeMap<u64, TimetableImages> = BTreeMap::new();
+        let mut changes = Vec::new();
+        let mut stations = Vec::new();
+
+        for directive in directives {
+            match &directive.command {
+                ParsedCommand::OptionsUnitOfLength(command) => units.apply_unit_of_length(command),
+                ParsedCommand::TrainTimetable(command) => {
+                    let filename = command.filename.trim();
+                    if filename.is_empty() {
+                        continue;
+                    }
+                    let entry = images.entry(command.timetable_index).or_default();
+                    let image = match command.timetable_suffix {
+                        TimetableSuffix::Day => &mut entry.day,
+                        TimetableSuffix::Night => &mut entry.night,
+                    };
+                    *image = Some(train_folder.join(filename.replace('\\', "/")));
+                }
+                ParsedCommand::TrackSta(command) => {
+                    let position = units.position_meters(&directive.position);
+                    if let Some(index) = command.timetable_index {
+                        changes.push((position, index));
+                    }
+                    let (arrival, passes) = match &command.arrival_time {
+                        ArrivalTimeState::Player(time) => (*time, false),
+                        ArrivalTimeState::AiStop | ArrivalTimeState::AllPass => (None, true),
+                    };
+                    let departure = match &command.departure_time {
+                        DepartureTimeState::Regular(time)
+                        | DepartureTimeState::Terminal(time)
+                        | DepartureTimeState::ChangeEnds(time)
+                        | DepartureTimeState::Jump { time, .. } => *time,
+                    };
+                    stations.push(TimetableStation {
+                        name: command.name.to_string(),
+                        position,
+                        arrival,
+                        departure,
+                        passes,
+                    });
+                }
+                _ => {}
+            }
+        }
+
+        // Stable, so stations at the same position stay in file order
+        changes.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(Ordering::Equal));
+        stations.sort_by(|a, b| a.position.partial_cmp(&b.position).unwrap_or(Ordering::Equal));
+
+        Self {
+            images,
+            changes,
+            stations,
+            rendered: OnceCell::new(),
+        }
+    }
+
+    /// Images of the timetable with the given index, if it has any.
+    #[must_use]
+    pub fn images(&self, index: u64) -> Option<&TimetableImages> {
+        self.images.get(&index)
+    }
+
+    /// Every station of the route, sorted by position.
+    #[must_use]
+    pub fn stations(&self) -> &[TimetableStation] {
+        &self.stations
+    }
+
+    /// Index of the timetable shown when the train is at `position` meters.
+    #[must_use]
+    pub fn index_at(&self, position: f32) -> u64 {
+        self.changes
+            .iter()
+            .take_while(|&&(start, _)| start <= position)
+            .last()
+            .map_or(0, |&(_, index)| index)
+    }
+
+    /// Image shown when the train is at `position` meters, if the active timetable has one.
+    #[must_use]
+    pub fn image_at(&self, position: f32, night: bool) -> Option<&Path> {
+        self.images(self.index_at(position))?.image(night)
+    }
+
+    /// Texture shown when the train is at `position` meters. If the active timetable has no image, one is rendered
+    /// from the stations of the route. It is only rendered the first time, and shared after that.
+    #[must_use]
+    pub fn texture_at(&self, position: f32, night: bool) -> TimetableTexture<'_> {
+        match self.image_at(position, night) {
+            Some(path) => TimetableTexture::Image(path),
+            None => TimetableTexture::Text(Arc::clone(
+                self.rendered.get_or_init(|| Arc::new(render_timetable(&self.stations))),
+            )),
+        }
+    }
+}
+
+/// Longest station name shown, in characters. Longer names are cut off.
+const MAX_NAME_LENGTH: usize = 24;
+const TIME_LENGTH: usize = 8;
+const COLUMN_GAP: usize = 2;
+
+/// Lines of the generated timetable: a header, then one line per station.
+#[must_use]
+pub fn timetable_lines(stations: &[TimetableStation]) -> Vec<String> {
+    let name_length = stations
+        .iter()
+        .map(|station| station.name.chars().count())
+        .chain(std::iter::once("STATION".len()))
+        .max()
+        .unwrap_or(0)
+        .min(MAX_NAME_LENGTH);
+
+    let line = |name: &str, arrival: &str, departure: &str| {
+        let name: String = name.chars().take(name_length).collect();
+        let text = format!(
+            "{:width$}{:gap$}{:>time$}{:gap$}{:>time$}",
+            name,
+            "",
+            arrival,
+            "",
+            departure,
+            width = name_length,
+            gap = COLUMN_GAP,
+            time = TIME_LENGTH
+        );
+        text.trim_end().to_uppercase()
+    };
+    let format_time = |time: Option<Time>| {
+        time.map_or_else(String::new, |time| {
+            format!("{:02}:{:02}:{:02}", time.hours, time.minutes, time.seconds)
+        })
+    };
+
+    let mut lines = Vec::with_capacity(stations.len() + 1);
+    lines.push(line("Station", "Arr", "Dep"));
+    for station in stations {
+        let arrival = if station.passes {
+            String::from("pass")
+        } else {
+            format_time(station.arrival)
+        };
+        lines.push(line(&station.name, &arrival, &format_time(station.departure)));
+    }
+    lines
+}
+
+const GLYPH_WIDTH: u32 = 5;
+const GLYPH_HEIGHT: u32 = 7;
+/// Size of each character including the space around it. unit: glyph pixels
+const CELL_WIDTH: u32 = 6;
+const CELL_HEIGHT: u32 = 10;
+/// Image pixels per glyph pixel.
+const SCALE: u32 = 2;
+/// Empty space around the text. unit: cells
+const MARGIN: u32 = 1;
+const BACKGROUND: Rgba<u8> = Rgba([255, 255, 255, 255]);
+const FOREGROUND: Rgba<u8> = Rgba([0, 0, 0, 255]);
+
+/// Renders a plain text timetable of `stations` on the CPU, for timetables without an image.
+///
+/// Uses a built in 5x7 font with only uppercase ascii letters, digits and some punctuation. Anything else is drawn as
+/// `?`.
+#[must_use]
+pub fn render_timetable(stations: &[TimetableStation]) -> RgbaImage {
+    let lines = timetable_lines(stations);
+    let columns = lines.iter().map(|line| line.chars().count()).max().unwrap_or(0) as u32;
+    let rows = lines.len() as u32;
+
+    let mut image = RgbaImage::from_pixel(
+        (columns + MARGIN * 2) * CELL_WIDTH * SCALE,
+        (rows + MARGIN * 2) * CELL_HEIGHT * SCALE,
+        BACKGROUND,
+    );
+    for (row, line) in lines.iter().enumerate() {
+        for (column, c) in line.chars().enumerate() {
+            let origin_x = (column as u32 + MARGIN) * CELL_WIDTH * SCALE;
+            let origin_y = (row as u32 + MARGIN) * CELL_HEIGHT * SCALE;
+            let glyph = glyph(c);
+            for (y, &bits) in glyph.iter().enumerate() {
+                for x in 0..GLYPH_WIDTH {
+                    if bits & (1 << (GLYPH_WIDTH - 1 - x)) == 0 {
+                        continue;
+                    }
+                    for dy in 0..SCALE {
+                        for dx in 0..SCALE {
+                            let pixel_x = origin_x + x * SCALE + dx;
+                            let pixel_y = origin_y + y as u32 * SCALE + dy;
+                            image.put_pixel(pixel_x, pixel_y, FOREGROUND);
+                        }
+                    }
+                }
+            }
+        }
+    }
+    image
+}
+
+/// Rows of a character, top first. The lowest 5 bits of each row are the pixels, with the highest bit on the left.
+fn glyph(c: char) -> [u8; GLYPH_HEIGHT as usize] {
+    match c.to_ascii_uppercase() {
+        ' ' => [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00],
+        '0' => [0x0E, 0x11, 0x13, 0x15, 0x19, 0x11, 0x0E],
+        '1' => [0x04, 0x0C, 0x04, 0x04, 0x04, 0x04, 0x0E],
+        '2' => [0x0E, 0x11, 0x01, 0x02, 0x04, 0x08, 0x1F],
+        '3' => [0x1F, 0x02, 0x04, 0x02, 0x01, 0x11, 0x0E],
+        '4' => [0x02, 0x06, 0x0A, 0x12, 0x1F, 0x02, 0x02],
+        '5' => [0x1F, 0x10, 0x1E, 0x01, 0x01, 0x11, 0x0E],
+        '6' => [0x06, 0x08, 0x10, 0x1E, 0x11, 0x11, 0x0E],
+        '7' => [0x1F, 0x01, 0x02, 0x04, 0x08, 0x08, 0x08],
+        '8' => [0x0E, 0x11, 0x11, 0x0E, 0x11, 0x11, 0x0E],
+        '9' => [0x0E, 0x11, 0x11, 0x0F, 0x01, 0x02, 0x0C],
+        'A' => [0x0E, 0x11, 0x11, 0x1F, 0x11, 0x11, 0x11],
+        'B' => [0x1E, 0x11, 0x11, 0x1E, 0x11, 0x11, 0x1E],
+        'C' => [0x0E, 0x11, 0x10, 0x10, 0x10, 0x11, 0x0E],
+        'D' => [0x1C, 0x12, 0x11, 0x11, 0x11, 0x12, 0x1C],
+        'E' => [0x1F, 0x10, 0x10, 0x1E, 0x10, 0x10, 0x1F],
+        'F' => [0x1F, 0x10, 0x10, 0x1E, 0x10, 0x10, 0x10],
+        'G' => [0x0E, 0x11, 0x10, 0x17, 0x11, 0x11, 0x0F],
+        'H' => [0x11, 0x11, 0x11, 0x1F, 0x11, 0x11, 0x11],
+        'I' => [0x0E, 0x04, 0x04, 0x04, 0x04, 0x04, 0x0E],
+        'J' => [0x07, 0x02, 0x02, 0x02, 0x02, 0x12, 0x0C],
+        'K' => [0x11, 0x12, 0x14, 0x18, 0x14, 0x12, 0x11],
+        'L' => [0x10, 0x10, 0x10, 0x10, 0x10, 0x10, 0x1F],
+        'M' => [0x11, 0x1B, 0x15, 0x15, 0x11, 0x11, 0x11],
+        'N' => [0x11, 0x11, 0x19, 0x15, 0x13, 0x11, 0x11],
+        'O' => [0x0E, 0x11, 0x11, 0x11, 0x11, 0x11, 0x0E],
+        'P' => [0x1E, 0x11, 0x11, 0x1E, 0x10, 0x10, 0x10],
+        'Q' => [0x0E, 0x11, 0x11, 0x11, 0x15, 0x12, 0x0D],
+        'R' => [0x1E, 0x11, 0x11, 0x1E, 0x14, 0x12, 0x11],
+        'S' => [0x0F, 0x10, 0x10, 0x0E, 0x01, 0x01, 0x1E],
+        'T' => [0x1F, 0x04, 0x04, 0x04, 0x04, 0x04, 0x04],
+        'U' => [0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x0E],
+        'V' => [0x11, 0x11, 0x11, 0x11, 0x11, 0x0A, 0x04],
+        'W' => [0x11, 0x11, 0x11, 0x15, 0x15, 0x15, 0x0A],
+        'X' => [0x11, 0x11, 0x0A, 0x04, 0x0A, 0x11, 0x11],
+        'Y' => [0x11, 0x11, 0x11, 0x0A, 0x04, 0x04, 0x04],
+        'Z' => [0x1F, 0x01, 0x02, 0x04, 0x08, 0x10, 0x1F],
+        ':' => [0x00, 0x0C, 0x0C, 0x00, 0x0C, 0x0C, 0x00],
+        '-' => [0x00, 0x00, 0x00, 0x1F, 0x00, 0x00, 0x00],
+        '.' => [0x00, 0x00, 0x00, 0x00, 0x00, 0x0C, 0x0C],
+        ',' => [0x00, 0x00, 0x00, 0x00, 0x0C, 0x04, 0x08],
+        '/' => [0x00, 0x01, 0x02, 0x04, 0x08, 0x10, 0x00],
+        '(' => [0x02, 0x04, 0x08, 0x08, 0x08, 0x04, 0x02],
+        ')' => [0x08, 0x04, 0x02, 0x02, 0x02, 0x04, 0x08],
+        '\'' => [0x0C, 0x04, 0x08, 0x00, 0x00, 0x00, 0x00],
+        '&' => [0x0C, 0x12, 0x14, 0x08, 0x15, 0x12, 0x0D],
+        _ => [0x0E, 0x11, 0x01, 0x02, 0x04, 0x00, 0x04],
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::parse::route::ir::{
+        ForcedRedSingleMode, OptionsUnitOfLength, SystemAtsMode, TrackSta, TrackStation, TrainTimetable,
+    };
+    use smallvec::smallvec;
+
+    fn time(hours: u64, minutes: u8) -> Time {
+        Time {
+            hours,
+            minutes,
+            seconds: 0,
+        }
+    }
+
+    fn timetable(index: u64, suffix: TimetableSuffix, filename: &str) -> ParsedDirective {
+        ParsedDirective {
+            command: ParsedCommand::TrainTimetable(TrainTimetable {
+                timetable_index: index,
+                timetable_suffix: suffix,
+                filename: filename.into(),
+            }),
+            position: smallvec![0.0],
+        }
+    }
+
+    fn station(position: f32, name: &str, arrival: ArrivalTimeState, timetable_index: Option<u64>) -> ParsedDirective {
+        let mut command: TrackSta = TrackStation {
+            name: name.into(),
+            arrival_time: arrival,
+            departure_time: DepartureTimeState::Regular(Some(time(10, 5))),
+            forced_red_signal: ForcedRedSingleMode::Unaffected,
+            system: SystemAtsMode::ATS,
+            departure_sound: "".into(),
+        }
+        .into();
+        command.timetable_index = timetable_index;
+        ParsedDirective {
+            command: ParsedCommand::TrackSta(command),
+            position: smallvec![position],
+        }
+    }
+
+    fn route() -> RouteTimetables {
+        RouteTimetables::from_directives(
+            &[
+                ParsedDirective {
+                    command: ParsedCommand::OptionsUnitOfLength(OptionsUnitOfLength {
+                        factors: smallvec![2.0],
+                    }),
+                    position: smallvec![0.0],
+                },
+                timetable(0, TimetableSuffix::Day, "tt\\day0.png"),
+                timetable(0, TimetableSuffix::Night, "night0.png"),
+                timetable(1, TimetableSuffix::Night, "night1.png"),
+                timetable(2, TimetableSuffix::Day, "  "),
+                station(500.0, "Terminus", ArrivalTimeState::Player(Some(time(10, 30))), Some(2)),
+                station(0.0, "Origin", ArrivalTimeState::Player(None), None),
+                station(100.0, "Halt", ArrivalTimeState::AllPass, Some(1)),
+            ],
+            Path::new("/train"),
+        )
+    }
+
+    #[bve_derive::bve_test]
+    #[test]
+    fn active_image() {
+        let timetables = route();
+        assert_eq!(timetables.index_at(0.0), 0);
+        assert_eq!(timetables.index_at(199.0), 0);
+        assert_eq!(timetables.index_at(200.0), 1);
+        assert_eq!(timetables.index_at(1000.0), 2);
+
+        assert_eq!(timetables.image_at(0.0, false), Some(Path::new("/train/tt/day0.png")));
+        assert_eq!(timetables.image_at(0.0, true), Some(Path::new("/train/night0.png")));
+        // Missing day image falls back to the night one
+        assert_eq!(timetables.image_at(300.0, false), Some(Path::new("/train/night1.png")));
+        // Empty filenames are ignored
+        assert_eq!(timetables.images(2), None);
+        assert_eq!(timetables.image_at(1000.0, false), None);
+
+        assert_eq!(
+            timetables.texture_at(0.0, true),
+            TimetableTexture::Image(Path::new("/train/night0.png"))
+        );
+        let generated = |position: f32| match timetables.texture_at(position, true) {
+            TimetableTexture::Text(image) => image,
+            TimetableTexture::Image(path) => panic!("Expected a generated timetable, got {:?}", path),
+        };
+        let image = generated(1000.0);
+        assert_eq!(*image, render_timetable(timetables.stations()));
+        // Rendered once and shared
+        assert!(Arc::ptr_eq(&image, &generated(2000.0)));
+    }
+
+    #[bve_derive::bve_test]
+    #[test]
+    fn lines() {
+        let timetables = route();
+        let names: Vec<_> = timetables
+            .stations()
+            .iter()
+            .map(|station| station.name.as_str())
+            .collect();
+        assert_eq!(names, vec!["Origin", "Halt", "Terminus"]);
+        assert_eq!(timetable_lines(timetables.stations()), vec![
+            "STATION        ARR       DEP",
+            "ORIGIN              10:05:00",
+            "HALT          PASS  10:05:00",
+            "TERMINUS  10:30:00  10:05:00",
+        ]);
+    }
+
+    #[bve_derive::bve_test]
+    #[test]
+    fn render() {
+        let stations = route().stations;
+        let image = render_timetable(&stations);
+        let cell_width = CELL_WIDTH * SCALE;
+        let cell_height = CELL_HEIGHT * SCALE;
+        assert_eq!(image.dimensions(), ((28 + 2) * cell_width, (4 + 2) * cell_height));
+        assert_eq!(*image.get_pixel(0, 0), BACKGROUND);
+        // Top row of the S in STATION is 0b01111
+        assert_eq!(*image.get_pixel(cell_width, cell_height), BACKGROUND);
+        assert_eq!(*image.get_pixel(cell_width + SCALE, cell_height), FOREGROUND);
+        assert_eq!(*image.get_pixel(cell_width + SCALE * 5, cell_height), BACKGROUND);
+    }
+}
@@ -0,0 +1,277 @@
+//! CPU implementation of the texture preprocessing done before textures are uploaded.
+//!
+//! Textures are decoded, the decal transparent color of the mesh is turned into alpha, and a full chain of mipmaps is
+//! generated. Filtering happens in linear space, the same as the compute shaders in `bve-render`, so clients that
+//! can't run those shaders get the same results.
+
+use crate::ColorU8RGB;
+use image::{guess_format, ImageResult, Rgba, RgbaImage};
+use once_cell::sync::Lazy;
+use std::io::Cursor;
+
+/// How a texture should be preprocessed.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub struct TextureSettings {
+    /// Texels of exactly this color become transparent. Comes from
+    /// [`Texture::decal_transparent_color`](crate::load::mesh::Texture::decal_transparent_color).
+    pub decal_transparent_color: Option<ColorU8RGB>,
+    /// Generate the full chain of mipmaps instead of only the base level.
+    pub mipmaps: bool,
+}
+
+/// A preprocessed texture.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LoadedTexture {
+    /// Every mip level, starting with the full size image. Each level is half the size of the one before it, rounded
+    /// down, ending when either dimension would reach zero.
+    pub mips: Vec<RgbaImage>,
+}
+
+impl LoadedTexture {
+    /// The full size image.
+    #[must_use]
+    pub fn base(&self) -> &RgbaImage {
+        &self.mips[0]
+    }
+}
+
+/// Decodes and preprocesses a texture file. The format is guessed from the contents.
+///
+/// # Errors
+///
+/// Returns Err if the format is unknown or the image could not be decoded.
+pub fn load_texture(data: &[u8], settings: TextureSettings) -> ImageResult<LoadedTexture> {
+    Ok(process_texture(decode_texture(data)?, settings))
+}
+
+/// Decodes a texture file into rgba. The format is guessed from the contents.
+///
+/// # Errors
+///
+/// Returns Err if the format is unknown or the image could not be decoded.
+pub fn decode_texture(data: &[u8]) -> ImageResult<RgbaImage> {
+    let format = guess_format(data)?;
+    Ok(image::load(Cursor::new(data), format)?.into_rgba())
+}
+
+/// Applies `settings` to an already decoded image.
+#[must_use]
+pub fn process_texture(mut image: RgbaImage, settings: TextureSettings) -> LoadedTexture {
+    if let Some(color) = settings.decal_transparent_color {
+        image = apply_decal_transparency(&image, color);
+    }
+    let mips = if settings.mipmaps {
+        generate_mipmaps(image)
+    } else {
+        vec![image]
+    };
+    LoadedTexture { mips }
+}
+
+/// Makes every texel of exactly `color` fully transparent.
+///
+/// The color of the transparent texels is replaced with the average of their opaque neighbors, so the key color
+/// doesn't bleed into the visible edges when the texture is filtered. Transparent texels with no opaque neighbors
+/// become transparent black.
+#[must_use]
+pub fn apply_decal_transparency(image: &RgbaImage, color: ColorU8RGB) -> RgbaImage {
+    let key = color.into_array();
+    let is_transparent = |&Rgba([r, g, b, a]): &Rgba<u8>| a == 0 || [r, g, b] == key;
+
+    let (width, height) = image.dimensions();
+    let mut output = image.clone();
+    for (x, y, texel) in output.enumerate_pixels_mut() {
+        if !is_transparent(image.get_pixel(x, y)) {
+            continue;
+        }
+
+        let mut sum = [0.0_f32; 3];
+        let mut count = 0_u32;
+        for &(dx, dy) in &NEIGHBORS {
+            let nx = x as i64 + dx;
+            let ny = y as i64 + dy;
+            if nx < 0 || ny < 0 || nx >= width as i64 || ny >= height as i64 {
+                continue;
+            }
+            let neighbor = image.get_pixel(nx as u32, ny as u32);
+            if is_transparent(neighbor) {
+                continue;
+            }
+            for (total, &channel) in sum.iter_mut().zip(neighbor.0.iter()) {
+                *total += srgb_to_linear(channel);
+            }
+            count += 1;
+        }
+
+        *texel = if count == 0 {
+            Rgba([0, 0, 0, 0])
+        } else {
+            let [r, g, b] = sum;
+            let count = count as f32;
+            Rgba([
+                linear_to_srgb(r / count),
+                linear_to_srgb(g / count),
+                linear_to_srgb(b / count),
+                0,
+            ])
+        };
+    }
+    output
+}
+
+/// Texture to show in place of one that failed to load: a single magenta texel, the same color as the placeholder
+/// mesh.
+#[must_use]
+pub fn placeholder_texture() -> RgbaImage {
+    RgbaImage::from_pixel(1, 1, Rgba([255, 0, 255, 255]))
+}
+
+const NEIGHBORS: [(i64, i64); 8] = [(-1, -1), (0, -1), (1, -1), (-1, 0), (1, 0), (-1, 1), (0, 1), (1, 1)];
+
+/// Generates the full chain of mipmaps for `image`, including `image` itself as the first level.
+///
+/// Each texel is the average of the 2x2 block of texels above it. When a dimension is odd, the last row or column of
+/// the level above is dropped.
+#[must_use]
+pub fn generate_mipmaps(image: RgbaImage) -> Vec<RgbaImage> {
+    let mut mips = vec![image];
+    loop {
+        let parent = &mips[mips.len() - 1];
+        let (width, height) = (parent.width() / 2, parent.height() / 2);
+        if width == 0 || height == 0 {
+            break;
+        }
+        let child = RgbaImage::from_fn(width, height, |x, y| {
+            let texels = [
+                parent.get_pixel(x * 2, y * 2),
+                parent.get_pixel(x * 2 + 1, y * 2),
+                parent.get_pixel(x * 2, y * 2 + 1),
+                parent.get_pixel(x * 2 + 1, y * 2 + 1),
+            ];
+            let mut sum = [0.0_f32; 4];
+            for &&Rgba([r, g, b, a]) in &texels {
+                sum[0] += srgb_to_linear(r);
+                sum[1] += srgb_to_linear(g);
+                sum[2] += srgb_to_linear(b);
+                sum[3] += a as f32 / 255.0;
+            }
+            Rgba([
+                linear_to_srgb(sum[0] / 4.0),
+                linear_to_srgb(sum[1] / 4.0),
+                linear_to_srgb(sum[2] / 4.0),
+                unorm_to_u8(sum[3] / 4.0),
+            ])
+        });
+        mips.push(child);
+    }
+    mips
+}
+
+static SRGB_TO_LINEAR: Lazy<[f32; 256]> = Lazy::new(|| {
+    let mut table = [0.0; 256];
+    for (value, linear) in table.iter_mut().enumerate() {
+        let srgb = value as f32 / 255.0;
+        *linear = if srgb <= 0.04045 {
+            srgb / 12.92
+        } else {
+            ((srgb + 0.055) / 1.055).powf(2.4)
+        };
+    }
+    table
+});
+
+fn srgb_to_linear(value: u8) -> f32 {
+    SRGB_TO_LINEAR[value as usize]
+}
+
+fn linear_to_srgb(value: f32) -> u8 {
+    let srgb = if value <= 0.003_130_8 {
+        value * 12.92
+    } else {
+        1.055 * value.powf(1.0 / 2.4) - 0.055
+    };
+    unorm_to_u8(srgb)
+}
+
+fn unorm_to_u8(value: f32) -> u8 {
+    (value.max(0.0).min(1.0) * 255.0).round() as u8
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        load::texture::{apply_decal_transparency, generate_mipmaps, load_texture, process_texture, TextureSettings},
+        ColorU8RGB,
+    };
+    use image::{ImageOutputFormat, Rgba, RgbaImage};
+
+    const BLUE: Rgba<u8> = Rgba([0, 0, 255, 255]);
+    const RED: Rgba<u8> = Rgba([255, 0, 0, 255]);
+    const GREEN: Rgba<u8> = Rgba([0, 255, 0, 255]);
+
+    fn from_texels(width: u32, texels: &[Rgba<u8>]) -> RgbaImage {
+        let height = texels.len() as u32 / width;
+        RgbaImage::from_fn(width, height, |x, y| texels[(y * width + x) as usize])
+    }
+
+    #[bve_derive::bve_test]
+    #[test]
+    fn decal_transparency() {
+        let source = from_texels(3, &[RED, BLUE, GREEN, BLUE, BLUE, BLUE, BLUE, BLUE, BLUE]);
+        let result = apply_decal_transparency(&source, ColorU8RGB::new(0, 0, 255));
+
+        // Opaque texels are untouched
+        assert_eq!(*result.get_pixel(0, 0), RED);
+        assert_eq!(*result.get_pixel(2, 0), GREEN);
+        // Transparent texels take the color of their opaque neighbors
+        assert_eq!(*result.get_pixel(0, 1), Rgba([255, 0, 0, 0]));
+        assert_eq!(*result.get_pixel(2, 1), Rgba([0, 255, 0, 0]));
+        // Averaged in linear space
+        assert_eq!(*result.get_pixel(1, 0), Rgba([188, 188, 0, 0]));
+        assert_eq!(*result.get_pixel(1, 1), Rgba([188, 188, 0, 0]));
+        // No opaque neighbors
+        assert_eq!(*result.get_pixel(1, 2), Rgba([0, 0, 0, 0]));
+    }
+
+    #[bve_derive::bve_test]
+    #[test]
+    fn mipmaps() {
+        let source = from_texels(4, &[RED; 8]);
+        let mips = generate_mipmaps(source.clone());
+        let dimensions: Vec<_> = mips.iter().map(RgbaImage::dimensions).collect();
+        assert_eq!(dimensions, vec![(4, 2), (2, 1)]);
+        assert_eq!(mips[0], source);
+        assert!(mips[1].pixels().all(|&texel| texel == RED));
+
+        // Odd dimensions drop the last row and column
+        let mips = generate_mipmaps(from_texels(3, &[RED, GREEN, BLUE, GREEN, RED, BLUE, BLUE, BLUE, BLUE]));
+        assert_eq!(mips.len(), 2);
+        assert_eq!(*mips[1].get_pixel(0, 0), Rgba([188, 188, 0, 255]));
+
+        let mips = generate_mipmaps(from_texels(2, &[RED, Rgba([255, 0, 0, 0]), RED, RED]));
+        assert_eq!(*mips[1].get_pixel(0, 0), Rgba([255, 0, 0, 191]));
+    }
+
+    #[bve_derive::bve_test]
+    #[test]
+    fn pipeline() {
+        let source = from_texels(2, &[RED, BLUE, BLUE, BLUE]);
+        let mut encoded = Vec::new();
+        image::DynamicImage::ImageRgba8(source.clone())
+            .write_to(&mut encoded, ImageOutputFormat::Png)
+            .expect("Could not encode png");
+
+        let texture = load_texture(&encoded, TextureSettings {
+            decal_transparent_color: Some(ColorU8RGB::new(0, 0, 255)),
+            mipmaps: true,
+        })
+        .expect("Could not load texture");
+        assert_eq!(texture.mips.len(), 2);
+        let bled = Rgba([255, 0, 0, 0]);
+        assert_eq!(*texture.base(), from_texels(2, &[RED, bled, bled, bled]));
+        assert_eq!(*texture.mips[1].get_pixel(0, 0), Rgba([255, 0, 0, 64]));
+
+        let untouched = process_texture(source.clone(), TextureSettings::default());
+        assert_eq!(untouched.mips, vec![source]);
+    }
+}
@@ -1,6 +1,7 @@
 use crate::parse::{
-    kvp::{KVPSymbols, ANIMATED_LIKE},
-    KVPFileParser,
+    kvp::{parse_kvp_file, FromKVPFile, KVPFile, KVPGenericWarning, KVPSymbols, ANIMATED_LIKE},
+    util::strip_comments,
+    KVPFileParser, ParserResult,
 };
 pub use sections::*;
 
@@ -9,4 +10,64 @@ mod sections;
 impl KVPFileParser for ParsedAnimatedObject {
     const COMMENT: char = ';';
     const SYMBOLS: KVPSymbols = ANIMATED_LIKE;
+
+    fn parse_from_kvp(input: &str) -> ParserResult<Self, KVPGenericWarning, ()> {
+        let lower = strip_comments(input, Self::COMMENT).to_lowercase();
+        let kvp_file = parse_kvp_file(&lower, Self::SYMBOLS);
+
+        let (mut output, warnings) = Self::from_kvp_file(&kvp_file);
+        output.change_state_sound_objects = state_change_sound_objects(&kvp_file);
+        ParserResult {
+            output,
+            warnings,
+            errors: vec![],
+        }
+    }
+}
+
+/// Finds which `[Object]` section each `[StateChangeSound]` section belongs to, which is the last one before it.
+fn state_change_sound_objects(kvp_file: &KVPFile<'_>) -> Vec<Option<usize>> {
+    let mut objects = 0_usize;
+    let mut owners = Vec::new();
+    for section in &kvp_file.sections {
+        match section.name {
+            Some("object") => objects += 1,
+            Some("statechangesound") => owners.push(objects.checked_sub(1)),
+            _ => {}
+        }
+    }
+    owners
+}
+
+#[cfg(test)]
+mod test {
+    use crate::parse::{animated::ParsedAnimatedObject, FileParser};
+
+    #[bve_derive::bve_test]
+    #[test]
+    fn sound_owners() {
+        let source = indoc::indoc!(
+            r#"
+            [StateChangeSound]
+            FileName = early.wav
+            [Object]
+            States = a.b3d
+            [StateChangeSound] ; First object
+            FileName = a.wav
+            [Sound]
+            FileName = loop.wav
+            [statechangesound]
+            FileName = b.wav
+            [Object]
+            States = b.b3d
+            [Object]
+            States = c.b3d
+            [StateChangeSound]
+            FileName = c.wav
+        "#
+        );
+        let parsed = ParsedAnimatedObject::parse_from(source).output;
+        assert_eq!(parsed.change_state_sounds.len(), 4);
+        assert_eq!(parsed.change_state_sound_objects, vec![None, Some(0), Some(0), Some(2)]);
+    }
 }
@@ -1,4 +1,8 @@
 use bve_derive::FromKVPFile;
+pub use includes::Includes;
+pub use object::{AnimatedObject, Damping, RefreshRate, TextureOverride};
+pub use sound::AnimatedSound;
+pub use state_change_sound::{AnimatedStateChangeSound, PlayOn};
 
 mod includes;
 mod object;
@@ -15,4 +19,8 @@ pub struct ParsedAnimatedObject {
     pub sounds: Vec<sound::AnimatedSound>,
     #[kvp(rename = "statechangesound")]
     pub change_state_sounds: Vec<state_change_sound::AnimatedStateChangeSound>,
+    /// Index into `objects` of the `[Object]` section each of `change_state_sounds` belongs to, which is the last one
+    /// before it. `None` if no object comes before the sound.
+    #[kvp(skip)]
+    pub change_state_sound_objects: Vec<Option<usize>>,
 }
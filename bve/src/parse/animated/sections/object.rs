@@ -1,4 +1,8 @@
-use crate::parse::{function_scripts::ParsedFunctionScript, kvp::FromKVPValue, PrettyPrintResult};
+use crate::parse::{
+    function_scripts::{ParsedFunctionScript, RPNFunctionScript},
+    kvp::FromKVPValue,
+    PrettyPrintResult,
+};
 use bve_derive::{FromKVPSection, FromKVPValue};
 use glam::{Vec2, Vec3A};
 use std::io;
@@ -8,22 +12,29 @@ pub struct AnimatedObject {
     pub position: Vec3A,
     #[kvp(variadic)]
     pub states: Vec<String>,
+    #[kvp(alternate_alias = "statefunctionrpn", alternate = "Option<RPNFunctionScript>")]
     pub state_function: Option<ParsedFunctionScript>,
 
     pub translate_x_direction: Vec3A,
     pub translate_y_direction: Vec3A,
     pub translate_z_direction: Vec3A,
 
+    #[kvp(alternate_alias = "translatexfunctionrpn", alternate = "Option<RPNFunctionScript>")]
     pub translate_x_function: Option<ParsedFunctionScript>,
+    #[kvp(alternate_alias = "translateyfunctionrpn", alternate = "Option<RPNFunctionScript>")]
     pub translate_y_function: Option<ParsedFunctionScript>,
+    #[kvp(alternate_alias = "translatezfunctionrpn", alternate = "Option<RPNFunctionScript>")]
     pub translate_z_function: Option<ParsedFunctionScript>,
 
     pub rotate_x_direction: Vec3A,
     pub rotate_y_direction: Vec3A,
     pub rotate_z_direction: Vec3A,
 
+    #[kvp(alternate_alias = "rotatexfunctionrpn", alternate = "Option<RPNFunctionScript>")]
     pub rotate_x_function: Option<ParsedFunctionScript>,
+    #[kvp(alternate_alias = "rotateyfunctionrpn", alternate = "Option<RPNFunctionScript>")]
     pub rotate_y_function: Option<ParsedFunctionScript>,
+    #[kvp(alternate_alias = "rotatezfunctionrpn", alternate = "Option<RPNFunctionScript>")]
     pub rotate_z_function: Option<ParsedFunctionScript>,
 
     pub rotate_x_damping: Option<Damping>,
@@ -33,9 +44,21 @@ pub struct AnimatedObject {
     pub texture_shift_x_direction: Vec2,
     pub texture_shift_y_direction: Vec2,
 
+    #[kvp(
+        alternate_alias = "textureshiftxfunctionrpn",
+        alternate = "Option<RPNFunctionScript>"
+    )]
     pub texture_shift_x_function: Option<ParsedFunctionScript>,
+    #[kvp(
+        alternate_alias = "textureshiftyfunctionrpn",
+        alternate = "Option<RPNFunctionScript>"
+    )]
     pub texture_shift_y_function: Option<ParsedFunctionScript>,
 
+    #[kvp(
+        alternate_alias = "trackfollowerfunctionrpn",
+        alternate = "Option<RPNFunctionScript>"
+    )]
     pub track_follower_function: Option<ParsedFunctionScript>,
 
     pub texture_override: TextureOverride,
@@ -1,17 +1,23 @@
-use crate::parse::function_scripts::ParsedFunctionScript;
+use crate::parse::function_scripts::{ParsedFunctionScript, RPNFunctionScript};
 use bve_derive::FromKVPSection;
 use glam::Vec3A;
 
 #[derive(Debug, Clone, PartialEq, FromKVPSection)]
 pub struct AnimatedSound {
-    filename: String,
-    position: Vec3A,
-    volume: f32,
-    volume_function: Option<ParsedFunctionScript>,
-    pitch: f32,
-    pitch_function: Option<ParsedFunctionScript>,
-    radius: f32,
-    track_follower_function: Option<ParsedFunctionScript>,
+    pub filename: String,
+    pub position: Vec3A,
+    pub volume: f32,
+    #[kvp(alternate_alias = "volumefunctionrpn", alternate = "Option<RPNFunctionScript>")]
+    pub volume_function: Option<ParsedFunctionScript>,
+    pub pitch: f32,
+    #[kvp(alternate_alias = "pitchfunctionrpn", alternate = "Option<RPNFunctionScript>")]
+    pub pitch_function: Option<ParsedFunctionScript>,
+    pub radius: f32,
+    #[kvp(
+        alternate_alias = "trackfollowerfunctionrpn",
+        alternate = "Option<RPNFunctionScript>"
+    )]
+    pub track_follower_function: Option<ParsedFunctionScript>,
 }
 
 impl Default for AnimatedSound {
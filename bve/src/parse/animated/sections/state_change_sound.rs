@@ -5,15 +5,15 @@ use std::io;
 
 #[derive(Debug, Clone, PartialEq, FromKVPSection)]
 pub struct AnimatedStateChangeSound {
-    filename: String,
+    pub filename: String,
     #[kvp(variadic)]
-    filenames: Vec<String>,
-    position: Vec3A,
-    volume: f32,
-    pitch: f32,
-    radius: f32,
-    play_on_show: PlayOn,
-    play_on_hide: PlayOn,
+    pub filenames: Vec<String>,
+    pub position: Vec3A,
+    pub volume: f32,
+    pub pitch: f32,
+    pub radius: f32,
+    pub play_on_show: PlayOn,
+    pub play_on_hide: PlayOn,
 }
 
 impl Default for AnimatedStateChangeSound {
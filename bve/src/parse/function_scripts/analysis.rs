@@ -0,0 +1,171 @@
+use crate::parse::function_scripts::{
+    parse_function_script, resolve_variable, tree::operand_count, CallTarget, FunctionScriptErrorKind, Instruction,
+};
+
+/// Finds every problem in `instructions` that would stop them from being evaluated, without running them.
+///
+/// Unlike [`evaluate_function_script`](super::evaluate_function_script), which stops at the first problem, analysis
+/// continues after each problem so all of them can be reported at once.
+#[must_use]
+pub fn analyze_function_script(instructions: &[Instruction]) -> Vec<FunctionScriptErrorKind> {
+    let mut problems = Vec::new();
+    let mut stack_size = 0_usize;
+    for instruction in instructions {
+        let resolution = match instruction {
+            Instruction::Variable { name } => resolve_variable(name).map(drop),
+            Instruction::FunctionCall { name, arg_count } => CallTarget::resolve(name, *arg_count).map(drop),
+            _ => Ok(()),
+        };
+        if let Err(problem) = resolution {
+            problems.push(problem);
+        }
+
+        let popped = operand_count(instruction);
+        if stack_size < popped {
+            problems.push(FunctionScriptErrorKind::StackUnderflow);
+            stack_size = 0;
+        } else {
+            stack_size -= popped;
+        }
+        stack_size += 1;
+    }
+    if stack_size != 1 {
+        problems.push(FunctionScriptErrorKind::InvalidResult { stack_size });
+    }
+    problems
+}
+
+/// Problem found when checking the source of a function script.
+#[derive(Debug, Clone, PartialEq)]
+pub enum FunctionScriptWarning {
+    /// Input after the end of the script that couldn't be parsed, and was ignored.
+    TrailingInput { input: String },
+    /// Problem found by [`analyze_function_script`].
+    Invalid { problem: FunctionScriptErrorKind },
+}
+
+/// Parses and analyzes the source of a function script, returning every problem found.
+///
+/// Source that can't be parsed at all gives no warnings, as it is already reported as an invalid value.
+#[must_use]
+pub fn check_function_script(source: &str) -> Vec<FunctionScriptWarning> {
+    let (remaining, script) = match parse_function_script(source) {
+        Ok(parsed) => parsed,
+        Err(..) => return Vec::new(),
+    };
+    let mut warnings: Vec<_> = trailing_input(remaining).into_iter().collect();
+    warnings.extend(
+        analyze_function_script(&script.instructions)
+            .into_iter()
+            .map(|problem| FunctionScriptWarning::Invalid { problem }),
+    );
+    warnings
+}
+
+/// Warning for the input left over after parsing a function script, if there is any besides whitespace.
+pub(super) fn trailing_input(remaining: &str) -> Option<FunctionScriptWarning> {
+    let remaining = remaining.trim();
+    if remaining.is_empty() {
+        None
+    } else {
+        Some(FunctionScriptWarning::TrailingInput {
+            input: remaining.to_owned(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::parse::{
+        animated::ParsedAnimatedObject,
+        function_scripts::{
+            analyze_function_script, check_function_script, Arity, FunctionScriptErrorKind, FunctionScriptWarning,
+            Instruction,
+        },
+        kvp::{KVPGenericWarning, KVPGenericWarningKind},
+        FileParser, Span,
+    };
+
+    fn invalid(problem: FunctionScriptErrorKind) -> FunctionScriptWarning {
+        FunctionScriptWarning::Invalid { problem }
+    }
+
+    #[bve_derive::bve_test]
+    #[test]
+    fn valid() {
+        assert_eq!(
+            check_function_script("if[speed > 10, pluginState[3], sin[time]]"),
+            vec![]
+        );
+        assert_eq!(check_function_script("  speed[1] * 2  "), vec![]);
+    }
+
+    #[bve_derive::bve_test]
+    #[test]
+    fn problems() {
+        assert_eq!(check_function_script("speed + 1 )"), vec![
+            FunctionScriptWarning::TrailingInput {
+                input: String::from(")")
+            }
+        ]);
+        assert_eq!(check_function_script("frobnicate[1] + spede + sin[1, 2]"), vec![
+            invalid(FunctionScriptErrorKind::UnknownFunction {
+                name: String::from("frobnicate")
+            }),
+            invalid(FunctionScriptErrorKind::UnknownVariable {
+                name: String::from("spede")
+            }),
+            invalid(FunctionScriptErrorKind::WrongArgumentCount {
+                name: String::from("sin"),
+                expected: Arity::Exactly(1),
+                found: 2
+            }),
+        ]);
+        assert_eq!(check_function_script("pluginState + time[2]"), vec![
+            invalid(FunctionScriptErrorKind::MissingIndex {
+                name: String::from("pluginState")
+            }),
+            invalid(FunctionScriptErrorKind::UnexpectedIndex {
+                name: String::from("time")
+            }),
+        ]);
+        assert_eq!(check_function_script("+"), vec![]);
+    }
+
+    #[bve_derive::bve_test]
+    #[test]
+    fn stack() {
+        assert_eq!(
+            analyze_function_script(&[Instruction::Number { value: 1.0 }, Instruction::Multiplication]),
+            vec![FunctionScriptErrorKind::StackUnderflow]
+        );
+        assert_eq!(
+            analyze_function_script(&[Instruction::Number { value: 1.0 }, Instruction::Number { value: 1.0 }]),
+            vec![FunctionScriptErrorKind::InvalidResult { stack_size: 2 }]
+        );
+    }
+
+    #[bve_derive::bve_test]
+    #[test]
+    fn kvp_warnings() {
+        let source = indoc::indoc!(
+            r#"
+            [Object]
+            States = a.b3d
+            StateFunction = if[speed > 10, 1, 0]
+            RotateXFunction = sin[time, 2]
+        "#
+        );
+        let result = ParsedAnimatedObject::parse_from(source);
+        assert_eq!(result.warnings, vec![KVPGenericWarning {
+            span: Span::from_line(4),
+            kind: KVPGenericWarningKind::FunctionScript {
+                warning: invalid(FunctionScriptErrorKind::WrongArgumentCount {
+                    name: String::from("sin"),
+                    expected: Arity::Exactly(1),
+                    found: 2
+                })
+            }
+        }]);
+    }
+}
@@ -0,0 +1,243 @@
+use crate::parse::function_scripts::{tree::Node, Instruction, ParsedFunctionScript};
+use std::fmt::{self, Write};
+
+/// Writes `instructions` back out as infix source, using only the parentheses needed to parse back into the same
+/// instructions.
+///
+/// Returns `None` if the instructions don't form a single expression, or contain a NaN, which has no source form.
+/// Negative numbers, which the parser never produces, are written with a leading `-` and parse back as a negated
+/// positive number. Infinities are written as `1e999`, which overflows back to infinity when parsed.
+#[must_use]
+pub fn decompile_function_script(instructions: &[Instruction]) -> Option<String> {
+    let tree = Node::from_instructions(instructions)?;
+    let mut output = String::new();
+    write_node(&mut output, &tree).ok()?;
+    Some(output)
+}
+
+impl fmt::Display for ParsedFunctionScript {
+    /// Infix source of the script. Scripts that can't be decompiled are written as a list of instructions.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match decompile_function_script(&self.instructions) {
+            Some(source) => f.write_str(&source),
+            None => write!(f, "{:?}", self.instructions.as_slice()),
+        }
+    }
+}
+
+/// How tightly each construct binds, from the loosest to the tightest. Follows the structure of the parser.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
+enum Precedence {
+    Or,
+    Xor,
+    And,
+    Not,
+    Comparison,
+    Sum,
+    Product,
+    Quotient,
+    Negation,
+    Atom,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Associativity {
+    Left,
+    Right,
+}
+
+fn binary_operator(instruction: &Instruction) -> Option<(&'static str, Precedence, Associativity)> {
+    use Associativity::*;
+    use Precedence::*;
+    Some(match instruction {
+        Instruction::LogicalOr => ("|", Or, Right),
+        Instruction::LogicalXor => ("^", Xor, Right),
+        Instruction::LogicalAnd => ("&", And, Right),
+        Instruction::Equal => ("==", Comparison, Left),
+        Instruction::NotEqual => ("!=", Comparison, Left),
+        Instruction::Less => ("<", Comparison, Left),
+        Instruction::Greater => (">", Comparison, Left),
+        Instruction::LessEqual => ("<=", Comparison, Left),
+        Instruction::GreaterEqual => (">=", Comparison, Left),
+        Instruction::Addition => ("+", Sum, Left),
+        Instruction::Subtraction => ("-", Sum, Left),
+        Instruction::Multiplication => ("*", Product, Right),
+        Instruction::Division => ("/", Quotient, Right),
+        _ => return None,
+    })
+}
+
+fn precedence(node: &Node) -> Precedence {
+    match &node.instruction {
+        Instruction::UnaryLogicalNot => Precedence::Not,
+        Instruction::UnaryNegative => Precedence::Negation,
+        Instruction::Number { value } if value.is_sign_negative() => Precedence::Negation,
+        instruction => binary_operator(instruction).map_or(Precedence::Atom, |(_, precedence, _)| precedence),
+    }
+}
+
+/// Writes `node`, wrapped in parentheses if it binds more loosely than `minimum`.
+fn write_operand(output: &mut String, node: &Node, minimum: Precedence) -> fmt::Result {
+    if precedence(node) < minimum {
+        output.push('(');
+        write_node(output, node)?;
+        output.push(')');
+        Ok(())
+    } else {
+        write_node(output, node)
+    }
+}
+
+/// The precedence one step tighter than `precedence`.
+fn tighter(precedence: Precedence) -> Precedence {
+    use Precedence::*;
+    match precedence {
+        Or => Xor,
+        Xor => And,
+        And => Not,
+        Not => Comparison,
+        Comparison => Sum,
+        Sum => Product,
+        Product => Quotient,
+        Quotient => Negation,
+        Negation | Atom => Atom,
+    }
+}
+
+fn write_node(output: &mut String, node: &Node) -> fmt::Result {
+    match &node.instruction {
+        Instruction::Number { value } if value.is_nan() => Err(fmt::Error),
+        Instruction::Number { value } if value.is_infinite() => {
+            output.write_str(if value.is_sign_negative() { "-1e999" } else { "1e999" })
+        }
+        Instruction::Number { value } => write!(output, "{}", value),
+        Instruction::Variable { name } => output.write_str(name),
+        Instruction::FunctionCall { name, .. } => {
+            write!(output, "{}[", name)?;
+            for (idx, arg) in node.args.iter().enumerate() {
+                if idx != 0 {
+                    output.push_str(", ");
+                }
+                write_node(output, arg)?;
+            }
+            output.push(']');
+            Ok(())
+        }
+        // The operand of `!` is a comparison, and the operand of `-` is a function call or term
+        Instruction::UnaryLogicalNot => {
+            output.push('!');
+            write_operand(output, &node.args[0], Precedence::Comparison)
+        }
+        Instruction::UnaryNegative => {
+            output.push('-');
+            write_operand(output, &node.args[0], Precedence::Atom)
+        }
+        instruction => {
+            let (symbol, precedence, associativity) =
+                binary_operator(instruction).expect("All other instructions are binary operators");
+            let (left, right) = match associativity {
+                Associativity::Left => (precedence, tighter(precedence)),
+                Associativity::Right => (tighter(precedence), precedence),
+            };
+            write_operand(output, &node.args[0], left)?;
+            write!(output, " {} ", symbol)?;
+            write_operand(output, &node.args[1], right)
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::parse::function_scripts::{decompile_function_script, parse_function_script, Instruction};
+
+    fn parse(source: &str) -> Vec<Instruction> {
+        let (remaining, script) = parse_function_script(source).expect("Could not parse");
+        assert_eq!(remaining, "", "{}", source);
+        script.instructions.into_vec()
+    }
+
+    fn decompile(source: &str) -> String {
+        decompile_function_script(&parse(source)).expect("Could not decompile")
+    }
+
+    #[bve_derive::bve_test]
+    #[test]
+    fn minimal_parentheses() {
+        assert_eq!(decompile("if[(speed > 10), 1, (0)]"), "if[speed > 10, 1, 0]");
+        assert_eq!(decompile("((1 + 2)) + 3"), "1 + 2 + 3");
+        assert_eq!(decompile("1 + (2 + 3)"), "1 + (2 + 3)");
+        assert_eq!(decompile("1 - (2 - 3)"), "1 - (2 - 3)");
+        assert_eq!(decompile("(1 * 2) * 3"), "(1 * 2) * 3");
+        assert_eq!(decompile("1 * (2 * 3)"), "1 * 2 * 3");
+        assert_eq!(decompile("(1 / 2) * 3"), "1 / 2 * 3");
+        assert_eq!(decompile("1 * (2 / 3)"), "1 * 2 / 3");
+        assert_eq!(decompile("(1 * 2) / 3"), "(1 * 2) / 3");
+        assert_eq!(decompile("(1 + 2) * 3"), "(1 + 2) * 3");
+        assert_eq!(decompile("-(speed)"), "-speed");
+        assert_eq!(decompile("-(speed * 2)"), "-(speed * 2)");
+        assert_eq!(decompile("-(-speed)"), "-(-speed)");
+        assert_eq!(decompile("!(a == b)"), "!a == b");
+        assert_eq!(decompile("(!a) == b"), "(!a) == b");
+        assert_eq!(decompile("!(a & b)"), "!(a & b)");
+        assert_eq!(decompile("(!a) & b"), "!a & b");
+        assert_eq!(decompile("(a | b) & c"), "(a | b) & c");
+        assert_eq!(decompile("a | (b & c)"), "a | b & c");
+        assert_eq!(decompile("pluginState[1 + 2]"), "pluginState[1 + 2]");
+    }
+
+    #[bve_derive::bve_test]
+    #[test]
+    fn malformed() {
+        assert_eq!(decompile_function_script(&[]), None);
+        assert_eq!(decompile_function_script(&[Instruction::Addition]), None);
+        assert_eq!(
+            decompile_function_script(&[Instruction::Number { value: -2.5 }, Instruction::UnaryNegative]),
+            Some(String::from("-(-2.5)"))
+        );
+        assert_eq!(
+            decompile_function_script(&[Instruction::Number {
+                value: f64::NEG_INFINITY
+            }]),
+            Some(String::from("-1e999"))
+        );
+        assert_eq!(
+            decompile_function_script(&[Instruction::Number { value: f64::NAN }, Instruction::UnaryNegative]),
+            None
+        );
+    }
+
+    #[bve_derive::bve_test]
+    #[test]
+    fn round_trip() {
+        let sources = [
+            "1",
+            "0.125",
+            "100000",
+            "speed",
+            "func[1]",
+            "func[1, 2, a, b + c]",
+            "if[leftDoorsTarget == 0, if[leftDoors >= 0.496, 0.9368 * leftDoors - 0.3068, exp[4.684 * leftDoors - \
+             4.05] - 0.02], if[leftDoors <= 0.5068, 0.9368 * leftDoors, -exp[-4.684 * leftDoors + 0.615] + 0.647]]",
+            "!-func[1] / 2 * 3 + 4 - 5 == 6 != 7 < 8 > 9 <= 10 >= 11 & 12 ^ 13 | 14",
+            "((a | b) ^ (c & d)) & !(e | f)",
+            "(a < b) + (c > d) * (e <= f) / (g >= h)",
+            "(1 - 2) - (3 - (4 - 5))",
+            "((1 / 2) / 3) / (4 / 5)",
+            "((1 * 2) * 3) * (4 * 5)",
+            "-(1 + 2) * -(3 / 4)",
+            "!(1 == 2) & !(3 != 4)",
+            "!((1 == 2) == 3)",
+            "(a == b) == (c == d)",
+            "-(-(-a))",
+            "min[max[1, 2], -a, if[b, c, d]] ^ (e ^ f) ^ g",
+            "1e999 * -1e999",
+        ];
+        for source in &sources {
+            let instructions = parse(source);
+            let decompiled = decompile_function_script(&instructions).expect("Could not decompile");
+            assert_eq!(parse(&decompiled), instructions, "{} -> {}", source, decompiled);
+            // Decompiling is stable
+            assert_eq!(decompile(&decompiled), decompiled);
+        }
+    }
+}
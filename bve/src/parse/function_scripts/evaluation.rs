@@ -0,0 +1,489 @@
+use crate::parse::function_scripts::{
+    library::{boolean, divide},
+    Arity, Function, Instruction, ParsedFunctionScript, Variable, VariableIndex,
+};
+use smallvec::SmallVec;
+use std::{error::Error, fmt};
+
+/// Provides the values of variables to a running function script.
+pub trait FunctionScriptContext {
+    /// Current value of `variable`.
+    ///
+    /// `index` is the value between the brackets when the variable was written as `name[index]`, truncated to an
+    /// integer. It is always `Some` for [`VariableIndex::Required`] variables and always `None` for
+    /// [`VariableIndex::None`] variables.
+    fn variable(&mut self, variable: Variable, index: Option<i64>) -> f64;
+
+    /// A random value in `[0, 1)`, used by `Random` and `RandomInt`.
+    fn random(&mut self) -> f64 {
+        rand::random()
+    }
+}
+
+/// Reason a function script could not be evaluated.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FunctionScriptError {
+    /// Index of the instruction that failed.
+    pub instruction: usize,
+    pub kind: FunctionScriptErrorKind,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum FunctionScriptErrorKind {
+    /// An instruction needed more values than were on the stack.
+    StackUnderflow,
+    /// The script didn't leave exactly one value on the stack.
+    InvalidResult {
+        stack_size: usize,
+    },
+    UnknownFunction {
+        name: String,
+    },
+    WrongArgumentCount {
+        name: String,
+        expected: Arity,
+        found: usize,
+    },
+    UnknownVariable {
+        name: String,
+    },
+    /// A variable that needs an index was used without one.
+    MissingIndex {
+        name: String,
+    },
+    /// A variable that can't be indexed was used with one.
+    UnexpectedIndex {
+        name: String,
+    },
+}
+
+impl fmt::Display for FunctionScriptError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Instruction {}: ", self.instruction)?;
+        match &self.kind {
+            FunctionScriptErrorKind::StackUnderflow => write!(f, "Stack underflow"),
+            FunctionScriptErrorKind::InvalidResult { stack_size } => {
+                write!(f, "Script left {} values on the stack instead of 1", stack_size)
+            }
+            FunctionScriptErrorKind::UnknownFunction { name } => write!(f, "Unknown function {}", name),
+            FunctionScriptErrorKind::WrongArgumentCount { name, expected, found } => write!(
+                f,
+                "Function {} takes {} arguments, but was given {}",
+                name, expected, found
+            ),
+            FunctionScriptErrorKind::UnknownVariable { name } => write!(f, "Unknown variable {}", name),
+            FunctionScriptErrorKind::MissingIndex { name } => write!(f, "Variable {} must have an index", name),
+            FunctionScriptErrorKind::UnexpectedIndex { name } => write!(f, "Variable {} can't have an index", name),
+        }
+    }
+}
+
+impl Error for FunctionScriptError {}
+
+/// What a [`Instruction::FunctionCall`] refers to. Indexed variables are written the same way as function calls.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum CallTarget {
+    Function(Function),
+    IndexedVariable(Variable),
+}
+
+impl CallTarget {
+    /// Resolves the `name[...]` of a call with `arg_count` arguments.
+    ///
+    /// # Errors
+    ///
+    /// Errors if the name is neither a function nor an indexable variable, or the amount of arguments is wrong.
+    pub fn resolve(name: &str, arg_count: usize) -> Result<Self, FunctionScriptErrorKind> {
+        if let Some(function) = Function::from_name(name) {
+            let expected = function.arity();
+            if !expected.accepts(arg_count) {
+                return Err(FunctionScriptErrorKind::WrongArgumentCount {
+                    name: name.to_owned(),
+                    expected,
+                    found: arg_count,
+                });
+            }
+            return Ok(Self::Function(function));
+        }
+        match Variable::from_name(name) {
+            Some(variable) if variable.index() == VariableIndex::None => {
+                Err(FunctionScriptErrorKind::UnexpectedIndex { name: name.to_owned() })
+            }
+            Some(_) if arg_count != 1 => Err(FunctionScriptErrorKind::WrongArgumentCount {
+                name: name.to_owned(),
+                expected: Arity::Exactly(1),
+                found: arg_count,
+            }),
+            Some(variable) => Ok(Self::IndexedVariable(variable)),
+            None => Err(FunctionScriptErrorKind::UnknownFunction { name: name.to_owned() }),
+        }
+    }
+}
+
+/// Resolves a plain variable name.
+///
+/// # Errors
+///
+/// Errors if the name isn't a variable, or the variable must be indexed.
+pub fn resolve_variable(name: &str) -> Result<Variable, FunctionScriptErrorKind> {
+    match Variable::from_name(name) {
+        Some(variable) if variable.index() == VariableIndex::Required => {
+            Err(FunctionScriptErrorKind::MissingIndex { name: name.to_owned() })
+        }
+        Some(variable) => Ok(variable),
+        None => Err(FunctionScriptErrorKind::UnknownVariable { name: name.to_owned() }),
+    }
+}
+
+/// Single step of a [`CompiledFunctionScript`], with any name already resolved.
+#[derive(Debug, Clone, PartialEq)]
+enum CompiledInstruction {
+    Number(f64),
+    Variable(Variable),
+    /// Takes its index off the stack.
+    IndexedVariable(Variable),
+    Function {
+        function: Function,
+        arg_count: usize,
+    },
+    /// Any instruction without a name.
+    Operator(Instruction),
+    /// Name that couldn't be resolved. Running it gives the problem.
+    Unresolved(FunctionScriptErrorKind),
+}
+
+impl CompiledInstruction {
+    fn new(instruction: &Instruction) -> Self {
+        let resolved = match instruction {
+            Instruction::Number { value } => return Self::Number(*value),
+            Instruction::Variable { name } => resolve_variable(name).map(Self::Variable),
+            Instruction::FunctionCall { name, arg_count } => {
+                CallTarget::resolve(name, *arg_count).map(|target| match target {
+                    CallTarget::Function(function) => Self::Function {
+                        function,
+                        arg_count: *arg_count,
+                    },
+                    CallTarget::IndexedVariable(variable) => Self::IndexedVariable(variable),
+                })
+            }
+            operator => return Self::Operator(operator.clone()),
+        };
+        resolved.unwrap_or_else(Self::Unresolved)
+    }
+}
+
+type CompiledInstructionSmallVec = SmallVec<[CompiledInstruction; 8]>;
+
+/// Function script with every name resolved ahead of time, so evaluating it never has to look a name up.
+///
+/// Names that can't be resolved still compile, and fail when evaluation reaches them, the same as they do when
+/// evaluating the [`ParsedFunctionScript`] directly.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CompiledFunctionScript {
+    script: ParsedFunctionScript,
+    instructions: CompiledInstructionSmallVec,
+}
+
+impl CompiledFunctionScript {
+    #[must_use]
+    pub fn new(script: ParsedFunctionScript) -> Self {
+        let instructions = script.instructions.iter().map(CompiledInstruction::new).collect();
+        Self { script, instructions }
+    }
+
+    /// The script this was compiled from.
+    #[must_use]
+    pub fn script(&self) -> &ParsedFunctionScript {
+        &self.script
+    }
+
+    /// Evaluates the script. Gives the same result as [`evaluate_function_script`] on the original instructions.
+    ///
+    /// # Errors
+    ///
+    /// Errors if the script can't be run.
+    pub fn evaluate(&self, context: &mut dyn FunctionScriptContext) -> Result<f64, FunctionScriptError> {
+        run(&self.instructions, context)
+    }
+}
+
+impl From<ParsedFunctionScript> for CompiledFunctionScript {
+    fn from(script: ParsedFunctionScript) -> Self {
+        Self::new(script)
+    }
+}
+
+impl fmt::Display for CompiledFunctionScript {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&self.script, f)
+    }
+}
+
+type Stack = SmallVec<[f64; 16]>;
+
+/// Runs `instructions` to completion, returning the single value they leave on the stack.
+///
+/// Names are resolved every call. Scripts that are evaluated more than once should be compiled into a
+/// [`CompiledFunctionScript`] instead.
+///
+/// # Errors
+///
+/// Errors if any instruction can't be run, or the script doesn't leave exactly one value. No partial result is given.
+pub fn evaluate_function_script(
+    instructions: &[Instruction],
+    context: &mut dyn FunctionScriptContext,
+) -> Result<f64, FunctionScriptError> {
+    let compiled: CompiledInstructionSmallVec = instructions.iter().map(CompiledInstruction::new).collect();
+    run(&compiled, context)
+}
+
+impl ParsedFunctionScript {
+    /// Evaluates the script. See [`evaluate_function_script`].
+    ///
+    /// # Errors
+    ///
+    /// Errors if the script can't be run.
+    pub fn evaluate(&self, context: &mut dyn FunctionScriptContext) -> Result<f64, FunctionScriptError> {
+        evaluate_function_script(&self.instructions, context)
+    }
+
+    /// Resolves every name in the script, so it can be evaluated repeatedly. See [`CompiledFunctionScript`].
+    #[must_use]
+    pub fn compiled(self) -> CompiledFunctionScript {
+        CompiledFunctionScript::new(self)
+    }
+}
+
+fn run(
+    instructions: &[CompiledInstruction],
+    context: &mut dyn FunctionScriptContext,
+) -> Result<f64, FunctionScriptError> {
+    let mut stack = Stack::new();
+    for (idx, instruction) in instructions.iter().enumerate() {
+        execute(instruction, &mut stack, context).map_err(|kind| FunctionScriptError { instruction: idx, kind })?;
+    }
+    match stack.as_slice() {
+        &[value] => Ok(value),
+        _ => Err(FunctionScriptError {
+            instruction: instructions.len(),
+            kind: FunctionScriptErrorKind::InvalidResult {
+                stack_size: stack.len(),
+            },
+        }),
+    }
+}
+
+fn pop(stack: &mut Stack) -> Result<f64, FunctionScriptErrorKind> {
+    stack.pop().ok_or(FunctionScriptErrorKind::StackUnderflow)
+}
+
+fn execute(
+    instruction: &CompiledInstruction,
+    stack: &mut Stack,
+    context: &mut dyn FunctionScriptContext,
+) -> Result<(), FunctionScriptErrorKind> {
+    let value = match instruction {
+        CompiledInstruction::Number(value) => *value,
+        CompiledInstruction::Variable(variable) => context.variable(*variable, None),
+        CompiledInstruction::IndexedVariable(variable) => {
+            let index = pop(stack)?;
+            context.variable(*variable, Some(index.trunc() as i64))
+        }
+        CompiledInstruction::Function { function, arg_count } => {
+            if stack.len() < *arg_count {
+                return Err(FunctionScriptErrorKind::StackUnderflow);
+            }
+            let args_start = stack.len() - arg_count;
+            let value = function.call(&stack[args_start..], &mut || context.random());
+            stack.truncate(args_start);
+            value
+        }
+        CompiledInstruction::Operator(Instruction::UnaryNegative) => -pop(stack)?,
+        CompiledInstruction::Operator(Instruction::UnaryLogicalNot) => boolean(pop(stack)? == 0.0),
+        CompiledInstruction::Operator(binary) => {
+            let rhs = pop(stack)?;
+            let lhs = pop(stack)?;
+            match binary {
+                Instruction::Addition => lhs + rhs,
+                Instruction::Subtraction => lhs - rhs,
+                Instruction::Multiplication => lhs * rhs,
+                Instruction::Division => divide(lhs, rhs),
+                Instruction::LogicalOr => boolean(lhs != 0.0 || rhs != 0.0),
+                Instruction::LogicalAnd => boolean(lhs != 0.0 && rhs != 0.0),
+                Instruction::LogicalXor => boolean((lhs != 0.0) ^ (rhs != 0.0)),
+                Instruction::Equal => boolean(lhs == rhs),
+                Instruction::NotEqual => boolean(lhs != rhs),
+                Instruction::Less => boolean(lhs < rhs),
+                Instruction::Greater => boolean(lhs > rhs),
+                Instruction::LessEqual => boolean(lhs <= rhs),
+                Instruction::GreaterEqual => boolean(lhs >= rhs),
+                _ => unreachable!(),
+            }
+        }
+        CompiledInstruction::Unresolved(problem) => return Err(problem.clone()),
+    };
+    stack.push(value);
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use crate::parse::function_scripts::{
+        evaluate_function_script, parse_function_script, Arity, FunctionScriptContext, FunctionScriptError,
+        FunctionScriptErrorKind, Instruction, Variable,
+    };
+
+    struct TestContext;
+
+    impl FunctionScriptContext for TestContext {
+        fn variable(&mut self, variable: Variable, index: Option<i64>) -> f64 {
+            match (variable, index) {
+                (Variable::Speed, None) => 20.0,
+                (Variable::Speed, Some(car)) => 10.0 + car as f64,
+                (Variable::PluginState, Some(index)) => index as f64 * 100.0,
+                (Variable::Time, None) => 3600.5,
+                _ => 0.0,
+            }
+        }
+
+        fn random(&mut self) -> f64 {
+            0.5
+        }
+    }
+
+    fn eval(source: &str) -> Result<f64, FunctionScriptError> {
+        let (remaining, script) = parse_function_script(source).expect("Could not parse");
+        assert_eq!(remaining, "");
+        script.evaluate(&mut TestContext)
+    }
+
+    fn eval_ok(source: &str) -> f64 {
+        eval(source).unwrap_or_else(|err| panic!("{}: {}", source, err))
+    }
+
+    #[bve_derive::bve_test]
+    #[test]
+    fn operators() {
+        assert_eq!(eval_ok("1 + 2 * 3"), 7.0);
+        assert_eq!(eval_ok("10 - 4 - 3"), 3.0);
+        assert_eq!(eval_ok("1 / 0"), 0.0);
+        assert_eq!(eval_ok("-(2 + 3)"), -5.0);
+        assert_eq!(eval_ok("!0"), 1.0);
+        assert_eq!(eval_ok("!3"), 0.0);
+        assert_eq!(eval_ok("2 > 1 & 3 < 2"), 0.0);
+        assert_eq!(eval_ok("2 > 1 | 3 < 2"), 1.0);
+        assert_eq!(eval_ok("1 ^ 1"), 0.0);
+        assert_eq!(eval_ok("1 == 1"), 1.0);
+        assert_eq!(eval_ok("1 != 1"), 0.0);
+        assert_eq!(eval_ok("2 <= 2"), 1.0);
+        assert_eq!(eval_ok("2 >= 3"), 0.0);
+    }
+
+    #[bve_derive::bve_test]
+    #[test]
+    fn functions() {
+        assert_eq!(eval_ok("if[1, 2, 3]"), 2.0);
+        assert_eq!(eval_ok("IF[0, 2, 3]"), 3.0);
+        assert_eq!(eval_ok("min[3, 1, 2]"), 1.0);
+        assert_eq!(eval_ok("max[3, 1, 2]"), 3.0);
+        assert_eq!(eval_ok("plus[1, 2, 3]"), 6.0);
+        assert_eq!(eval_ok("times[2, 3, 4]"), 24.0);
+        assert_eq!(eval_ok("quotient[7, 2]"), 3.0);
+        assert_eq!(eval_ok("quotient[7, 0]"), 0.0);
+        assert_eq!(eval_ok("mod[-1, 3]"), 2.0);
+        assert_eq!(eval_ok("reciprocal[4]"), 0.25);
+        assert_eq!(eval_ok("power[2, 10]"), 1024.0);
+        assert_eq!(eval_ok("abs[-2]"), 2.0);
+        assert_eq!(eval_ok("sign[-2]"), -1.0);
+        assert_eq!(eval_ok("sign[0]"), 0.0);
+        assert_eq!(eval_ok("floor[1.5]"), 1.0);
+        assert_eq!(eval_ok("ceiling[1.5]"), 2.0);
+        assert_eq!(eval_ok("round[2.5]"), 2.0);
+        assert_eq!(eval_ok("round[3.5]"), 4.0);
+        assert_eq!(eval_ok("round[2.6]"), 3.0);
+        assert_eq!(eval_ok("random[2, 4]"), 3.0);
+        assert_eq!(eval_ok("randomInt[1, 4]"), 3.0);
+        assert_eq!(eval_ok("sqrt[-1]"), 0.0);
+        assert_eq!(eval_ok("log[0]"), 0.0);
+        assert_eq!(eval_ok("sin[0] + cos[0] + tan[0] + arctan[0] + exp[0]"), 2.0);
+        assert_eq!(eval_ok("not[0] + and[1, 0] + or[1, 0] + xor[1, 1]"), 2.0);
+    }
+
+    #[bve_derive::bve_test]
+    #[test]
+    fn variables() {
+        assert_eq!(eval_ok("speed"), 20.0);
+        assert_eq!(eval_ok("Speed[2]"), 12.0);
+        assert_eq!(eval_ok("speed[1.9]"), 11.0);
+        assert_eq!(eval_ok("pluginState[1 + 2]"), 300.0);
+        assert_eq!(eval_ok("mod[time, 60]"), 0.5);
+        assert_eq!(eval_ok("locoBrakeNotches + TRAINDISTANCE + routeLimit + klaxon"), 0.0);
+    }
+
+    #[bve_derive::bve_test]
+    #[test]
+    fn compiled() {
+        for source in &[
+            "speed[2] * if[time > 1, 2, 3]",
+            "pluginState[1] + nothing",
+            "random[2, 4] + spede[1]",
+        ] {
+            let (_, script) = parse_function_script(source).expect("Could not parse");
+            let expected = script.evaluate(&mut TestContext);
+            let compiled = script.compiled();
+            assert_eq!(compiled.evaluate(&mut TestContext), expected, "{}", source);
+            assert_eq!(compiled.evaluate(&mut TestContext), expected, "{}", source);
+        }
+    }
+
+    #[bve_derive::bve_test]
+    #[test]
+    fn errors() {
+        let error = |source: &str| eval(source).expect_err(source).kind;
+        assert_eq!(error("nothing[1]"), FunctionScriptErrorKind::UnknownFunction {
+            name: String::from("nothing")
+        });
+        assert_eq!(error("nothing"), FunctionScriptErrorKind::UnknownVariable {
+            name: String::from("nothing")
+        });
+        assert_eq!(error("if[1, 2]"), FunctionScriptErrorKind::WrongArgumentCount {
+            name: String::from("if"),
+            expected: Arity::Exactly(3),
+            found: 2
+        });
+        assert_eq!(error("pluginState"), FunctionScriptErrorKind::MissingIndex {
+            name: String::from("pluginState")
+        });
+        assert_eq!(error("time[1]"), FunctionScriptErrorKind::UnexpectedIndex {
+            name: String::from("time")
+        });
+
+        // Hand written instructions can be malformed in ways the parser can't produce
+        let underflow = evaluate_function_script(
+            &[Instruction::Number { value: 1.0 }, Instruction::Addition],
+            &mut TestContext,
+        );
+        assert_eq!(
+            underflow,
+            Err(FunctionScriptError {
+                instruction: 1,
+                kind: FunctionScriptErrorKind::StackUnderflow
+            })
+        );
+        let leftover = evaluate_function_script(
+            &[Instruction::Number { value: 1.0 }, Instruction::Number { value: 2.0 }],
+            &mut TestContext,
+        );
+        assert_eq!(
+            leftover,
+            Err(FunctionScriptError {
+                instruction: 2,
+                kind: FunctionScriptErrorKind::InvalidResult { stack_size: 2 }
+            })
+        );
+        assert_eq!(
+            evaluate_function_script(&[], &mut TestContext).map_err(|e| e.kind),
+            Err(FunctionScriptErrorKind::InvalidResult { stack_size: 0 })
+        );
+    }
+}
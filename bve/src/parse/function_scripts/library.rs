@@ -0,0 +1,330 @@
+//! Functions and variables that function scripts can use, matching the set OpenBVE provides.
+//!
+//! Names are case insensitive, the same as in OpenBVE.
+
+use std::fmt;
+
+/// Amount of arguments a [`Function`] accepts.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Arity {
+    Exactly(usize),
+    AtLeast(usize),
+}
+
+impl Arity {
+    #[must_use]
+    pub fn accepts(self, arg_count: usize) -> bool {
+        match self {
+            Self::Exactly(count) => arg_count == count,
+            Self::AtLeast(count) => arg_count >= count,
+        }
+    }
+}
+
+impl fmt::Display for Arity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Exactly(count) => write!(f, "{}", count),
+            Self::AtLeast(count) => write!(f, "at least {}", count),
+        }
+    }
+}
+
+macro_rules! lookup_table {
+    ($(#[$attr:meta])* $vis:vis enum $enum_name:ident: $info:ty { $($variant:ident => ($name:literal, $value:expr),)* }) => {
+        $(#[$attr])*
+        #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+        $vis enum $enum_name {
+            $($variant,)*
+        }
+
+        impl $enum_name {
+            pub const ALL: &'static [Self] = &[$(Self::$variant,)*];
+
+            /// Looks up a name, ignoring case.
+            #[must_use]
+            pub fn from_name(name: &str) -> Option<Self> {
+                Self::ALL.iter().copied().find(|v| v.name().eq_ignore_ascii_case(name))
+            }
+
+            /// Name as written in the OpenBVE documentation.
+            #[must_use]
+            pub fn name(self) -> &'static str {
+                match self {
+                    $(Self::$variant => $name,)*
+                }
+            }
+
+            fn info(self) -> $info {
+                match self {
+                    $(Self::$variant => $value,)*
+                }
+            }
+        }
+
+        impl fmt::Display for $enum_name {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str(self.name())
+            }
+        }
+    };
+}
+
+lookup_table! {
+    /// Functions called with `name[arg, ...]` syntax.
+    pub enum Function: Arity {
+        If => ("if", Arity::Exactly(3)),
+        Plus => ("Plus", Arity::AtLeast(2)),
+        Subtract => ("Subtract", Arity::Exactly(2)),
+        Times => ("Times", Arity::AtLeast(2)),
+        Divide => ("Divide", Arity::Exactly(2)),
+        Minus => ("Minus", Arity::Exactly(1)),
+        Reciprocal => ("Reciprocal", Arity::Exactly(1)),
+        Power => ("Power", Arity::Exactly(2)),
+        Equal => ("Equal", Arity::Exactly(2)),
+        Unequal => ("Unequal", Arity::Exactly(2)),
+        Less => ("Less", Arity::Exactly(2)),
+        Greater => ("Greater", Arity::Exactly(2)),
+        LessEqual => ("LessEqual", Arity::Exactly(2)),
+        GreaterEqual => ("GreaterEqual", Arity::Exactly(2)),
+        Not => ("Not", Arity::Exactly(1)),
+        And => ("And", Arity::Exactly(2)),
+        Or => ("Or", Arity::Exactly(2)),
+        Xor => ("Xor", Arity::Exactly(2)),
+        Quotient => ("Quotient", Arity::Exactly(2)),
+        Mod => ("Mod", Arity::Exactly(2)),
+        Min => ("Min", Arity::AtLeast(1)),
+        Max => ("Max", Arity::AtLeast(1)),
+        Abs => ("Abs", Arity::Exactly(1)),
+        Sign => ("Sign", Arity::Exactly(1)),
+        Floor => ("Floor", Arity::Exactly(1)),
+        Ceiling => ("Ceiling", Arity::Exactly(1)),
+        Round => ("Round", Arity::Exactly(1)),
+        Random => ("Random", Arity::Exactly(2)),
+        RandomInt => ("RandomInt", Arity::Exactly(2)),
+        Exp => ("Exp", Arity::Exactly(1)),
+        Log => ("Log", Arity::Exactly(1)),
+        Sqrt => ("Sqrt", Arity::Exactly(1)),
+        Sin => ("Sin", Arity::Exactly(1)),
+        Cos => ("Cos", Arity::Exactly(1)),
+        Tan => ("Tan", Arity::Exactly(1)),
+        ArcTan => ("ArcTan", Arity::Exactly(1)),
+    }
+}
+
+impl Function {
+    #[must_use]
+    pub fn arity(self) -> Arity {
+        self.info()
+    }
+
+    /// Functions that may give different results with the same arguments.
+    #[must_use]
+    pub fn is_random(self) -> bool {
+        matches!(self, Self::Random | Self::RandomInt)
+    }
+
+    /// Calls the function. `args` must be accepted by the function's [`arity`](Self::arity). `random` gives values in
+    /// `[0, 1)`.
+    ///
+    /// Following OpenBVE, operations that would give an infinite or undefined result, like dividing by zero, give zero.
+    #[must_use]
+    pub fn call(self, args: &[f64], random: &mut dyn FnMut() -> f64) -> f64 {
+        debug_assert!(self.arity().accepts(args.len()));
+        let a = args.get(0).copied().unwrap_or_default();
+        let b = args.get(1).copied().unwrap_or_default();
+        match self {
+            Self::If => {
+                if a != 0.0 {
+                    b
+                } else {
+                    args[2]
+                }
+            }
+            Self::Plus => args.iter().sum(),
+            Self::Subtract => a - b,
+            Self::Times => args.iter().product(),
+            Self::Divide => divide(a, b),
+            Self::Minus => -a,
+            Self::Reciprocal => divide(1.0, a),
+            Self::Power => finite_or_zero(a.powf(b)),
+            Self::Equal => boolean(a == b),
+            Self::Unequal => boolean(a != b),
+            Self::Less => boolean(a < b),
+            Self::Greater => boolean(a > b),
+            Self::LessEqual => boolean(a <= b),
+            Self::GreaterEqual => boolean(a >= b),
+            Self::Not => boolean(a == 0.0),
+            Self::And => boolean(a != 0.0 && b != 0.0),
+            Self::Or => boolean(a != 0.0 || b != 0.0),
+            Self::Xor => boolean((a != 0.0) ^ (b != 0.0)),
+            Self::Quotient => divide(a, b).floor(),
+            Self::Mod => {
+                if b == 0.0 {
+                    0.0
+                } else {
+                    a - b * (a / b).floor()
+                }
+            }
+            Self::Min => args.iter().copied().fold(f64::INFINITY, f64::min),
+            Self::Max => args.iter().copied().fold(f64::NEG_INFINITY, f64::max),
+            Self::Abs => a.abs(),
+            Self::Sign => {
+                if a == 0.0 {
+                    0.0
+                } else {
+                    a.signum()
+                }
+            }
+            Self::Floor => a.floor(),
+            Self::Ceiling => a.ceil(),
+            Self::Round => round_half_even(a),
+            Self::Random => a + random() * (b - a),
+            Self::RandomInt => {
+                let (low, high) = (a.round(), b.round());
+                (low + (random() * (high - low + 1.0)).floor()).min(high)
+            }
+            Self::Exp => finite_or_zero(a.exp()),
+            Self::Log => {
+                if a > 0.0 {
+                    a.ln()
+                } else {
+                    0.0
+                }
+            }
+            Self::Sqrt => {
+                if a >= 0.0 {
+                    a.sqrt()
+                } else {
+                    0.0
+                }
+            }
+            Self::Sin => a.sin(),
+            Self::Cos => a.cos(),
+            Self::Tan => finite_or_zero(a.tan()),
+            Self::ArcTan => a.atan(),
+        }
+    }
+}
+
+pub(super) fn boolean(value: bool) -> f64 {
+    if value { 1.0 } else { 0.0 }
+}
+
+pub(super) fn divide(numerator: f64, denominator: f64) -> f64 {
+    if denominator == 0.0 {
+        0.0
+    } else {
+        numerator / denominator
+    }
+}
+
+fn finite_or_zero(value: f64) -> f64 {
+    if value.is_finite() { value } else { 0.0 }
+}
+
+/// Rounds halfway cases to the nearest even number, the same as .NET's `Math.Round`.
+fn round_half_even(value: f64) -> f64 {
+    if (value - value.trunc()).abs() == 0.5 {
+        2.0 * (value / 2.0).round()
+    } else {
+        value.round()
+    }
+}
+
+/// If a [`Variable`] can be given an index with `name[index]` syntax.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum VariableIndex {
+    /// Never indexed.
+    None,
+    /// The index is the car to read the value of. Without one, the value is for the car the object is attached to.
+    OptionalCar,
+    /// Must always be indexed.
+    Required,
+}
+
+lookup_table! {
+    /// Values provided by a [`FunctionScriptContext`](super::FunctionScriptContext).
+    pub enum Variable: VariableIndex {
+        Time => ("time", VariableIndex::None),
+        Hour => ("hour", VariableIndex::None),
+        Minute => ("minute", VariableIndex::None),
+        Second => ("second", VariableIndex::None),
+        CameraDistance => ("cameraDistance", VariableIndex::None),
+        CameraXDistance => ("cameraXDistance", VariableIndex::None),
+        CameraYDistance => ("cameraYDistance", VariableIndex::None),
+        CameraZDistance => ("cameraZDistance", VariableIndex::None),
+        CameraMode => ("cameraMode", VariableIndex::None),
+        Cars => ("cars", VariableIndex::None),
+        CarNumber => ("carNumber", VariableIndex::None),
+        PlayerTrain => ("playerTrain", VariableIndex::None),
+        Speed => ("speed", VariableIndex::OptionalCar),
+        Speedometer => ("speedometer", VariableIndex::OptionalCar),
+        Acceleration => ("acceleration", VariableIndex::OptionalCar),
+        AccelerationMotor => ("accelerationMotor", VariableIndex::OptionalCar),
+        Distance => ("distance", VariableIndex::OptionalCar),
+        TrackDistance => ("trackDistance", VariableIndex::OptionalCar),
+        TrainDistance => ("trainDistance", VariableIndex::None),
+        TrainTrackDistance => ("trainTrackDistance", VariableIndex::None),
+        CurveRadius => ("curveRadius", VariableIndex::OptionalCar),
+        FrontAxleCurveRadius => ("frontAxleCurveRadius", VariableIndex::OptionalCar),
+        RearAxleCurveRadius => ("rearAxleCurveRadius", VariableIndex::OptionalCar),
+        CurveCant => ("curveCant", VariableIndex::OptionalCar),
+        Pitch => ("pitch", VariableIndex::OptionalCar),
+        Odometer => ("odometer", VariableIndex::OptionalCar),
+        WheelSlip => ("wheelSlip", VariableIndex::OptionalCar),
+        MainReservoir => ("mainReservoir", VariableIndex::OptionalCar),
+        EqualizingReservoir => ("equalizingReservoir", VariableIndex::OptionalCar),
+        BrakePipe => ("brakePipe", VariableIndex::OptionalCar),
+        BrakeCylinder => ("brakeCylinder", VariableIndex::OptionalCar),
+        StraightAirPipe => ("straightAirPipe", VariableIndex::OptionalCar),
+        Doors => ("doors", VariableIndex::OptionalCar),
+        LeftDoors => ("leftDoors", VariableIndex::OptionalCar),
+        RightDoors => ("rightDoors", VariableIndex::OptionalCar),
+        LeftDoorsTarget => ("leftDoorsTarget", VariableIndex::OptionalCar),
+        RightDoorsTarget => ("rightDoorsTarget", VariableIndex::OptionalCar),
+        LeftDoorButton => ("leftDoorButton", VariableIndex::None),
+        RightDoorButton => ("rightDoorButton", VariableIndex::None),
+        ReverserNotch => ("reverserNotch", VariableIndex::None),
+        PowerNotch => ("powerNotch", VariableIndex::None),
+        PowerNotches => ("powerNotches", VariableIndex::None),
+        BrakeNotch => ("brakeNotch", VariableIndex::None),
+        BrakeNotches => ("brakeNotches", VariableIndex::None),
+        BrakeNotchLinear => ("brakeNotchLinear", VariableIndex::None),
+        BrakeNotchesLinear => ("brakeNotchesLinear", VariableIndex::None),
+        LocoBrakeNotch => ("locoBrakeNotch", VariableIndex::None),
+        LocoBrakeNotches => ("locoBrakeNotches", VariableIndex::None),
+        EmergencyBrake => ("emergencyBrake", VariableIndex::None),
+        HasAirBrake => ("hasAirBrake", VariableIndex::None),
+        HoldBrake => ("holdBrake", VariableIndex::None),
+        HasHoldBrake => ("hasHoldBrake", VariableIndex::None),
+        ConstSpeed => ("constSpeed", VariableIndex::None),
+        HasConstSpeed => ("hasConstSpeed", VariableIndex::None),
+        HasPlugin => ("hasPlugin", VariableIndex::None),
+        PluginState => ("pluginState", VariableIndex::Required),
+        PassAlarm => ("passAlarm", VariableIndex::None),
+        PilotLamp => ("pilotLamp", VariableIndex::None),
+        StationAdjustAlarm => ("stationAdjustAlarm", VariableIndex::None),
+        Klaxon => ("klaxon", VariableIndex::None),
+        PrimaryKlaxon => ("primaryKlaxon", VariableIndex::None),
+        SecondaryKlaxon => ("secondaryKlaxon", VariableIndex::None),
+        MusicKlaxon => ("musicKlaxon", VariableIndex::None),
+        Destination => ("destination", VariableIndex::None),
+        NextStation => ("nextStation", VariableIndex::None),
+        NextStationStop => ("nextStationStop", VariableIndex::None),
+        TerminalStation => ("terminalStation", VariableIndex::None),
+        DistanceNextStation => ("distanceNextStation", VariableIndex::None),
+        StopsNextStation => ("stopsNextStation", VariableIndex::None),
+        DistanceStation => ("distanceStation", VariableIndex::Required),
+        StopsStation => ("stopsStation", VariableIndex::Required),
+        Section => ("section", VariableIndex::None),
+        RouteLimit => ("routeLimit", VariableIndex::None),
+    }
+}
+
+impl Variable {
+    #[must_use]
+    pub fn index(self) -> VariableIndex {
+        self.info()
+    }
+}
@@ -1,9 +1,25 @@
+mod analysis;
+mod decompile;
+mod evaluation;
 mod ir;
+mod library;
+mod optimization;
 mod parser;
+mod rpn;
+mod tree;
 
-use crate::parse::{kvp::FromKVPValue, util, PrettyPrintResult};
+use crate::parse::{
+    kvp::{FromKVPValue, KVPGenericWarningKind},
+    util, PrettyPrintResult,
+};
+pub use analysis::*;
+pub use decompile::*;
+pub use evaluation::*;
 pub use ir::*;
+pub use library::*;
+pub use optimization::*;
 pub use parser::*;
+pub use rpn::*;
 use std::io;
 
 #[derive(Debug, Default, Clone, PartialEq)]
@@ -21,10 +37,31 @@ impl FromKVPValue for ParsedFunctionScript {
     fn from_kvp_value(value: &str) -> Option<Self> {
         parse_function_script(value).map(|(_, o)| o).ok()
     }
+
+    fn kvp_value_warnings(&self, value: &str) -> Vec<KVPGenericWarningKind> {
+        // The script doesn't keep what was left after it, so that is all the source is parsed again for
+        let trailing = parse_function_script(value)
+            .ok()
+            .and_then(|(remaining, _)| analysis::trailing_input(remaining));
+        trailing
+            .into_iter()
+            .chain(
+                analyze_function_script(&self.instructions)
+                    .into_iter()
+                    .map(|problem| FunctionScriptWarning::Invalid { problem }),
+            )
+            .map(|warning| KVPGenericWarningKind::FunctionScript { warning })
+            .collect()
+    }
 }
 
 impl PrettyPrintResult for ParsedFunctionScript {
     fn fmt(&self, indent: usize, out: &mut dyn io::Write) -> io::Result<()> {
+        if let Some(source) = decompile_function_script(&self.instructions) {
+            return writeln!(out, "{}", source);
+        }
+
+        // Only scripts that weren't parsed from source can fail to decompile
         writeln!(out)?;
         for (idx, instruction) in self.instructions.iter().enumerate() {
             util::indent(indent, out)?;
@@ -0,0 +1,279 @@
+use crate::parse::function_scripts::{
+    analyze_function_script, evaluate_function_script, tree::Node, CallTarget, Function, FunctionScriptContext,
+    Instruction, InstructionSmallVec, ParsedFunctionScript, Variable,
+};
+
+/// Simplifies `instructions` into ones that give the same result with less work.
+///
+/// - Subexpressions that only use numbers are replaced by their value, unless they call a random function.
+/// - `x + 0`, `0 + x`, `x - 0`, `x * 1`, `1 * x`, `x / 1`, and `--x` are replaced by `x`.
+/// - `if[]` calls with a constant condition are replaced by the branch that would be taken.
+///
+/// Scripts that [`analyze_function_script`] finds problems in are returned unchanged, so their errors still show up
+/// when they are evaluated.
+#[must_use]
+pub fn optimize_function_script(instructions: &[Instruction]) -> InstructionSmallVec {
+    if !analyze_function_script(instructions).is_empty() {
+        return instructions.iter().cloned().collect();
+    }
+
+    let tree = Node::from_instructions(instructions).expect("Analysis checked the stack");
+    let mut output = InstructionSmallVec::new();
+    simplify_tree(tree).emit(&mut output);
+    output
+}
+
+impl ParsedFunctionScript {
+    /// Optimized copy of the script. See [`optimize_function_script`].
+    #[must_use]
+    pub fn optimized(&self) -> Self {
+        optimize_function_script(&self.instructions).into()
+    }
+
+    /// The value of the script if it always gives the same value, so it doesn't need to be evaluated every frame.
+    ///
+    /// Only scripts that are a single number are detected, so the script should be [`optimized`](Self::optimized)
+    /// first.
+    #[must_use]
+    pub fn constant_value(&self) -> Option<f64> {
+        match self.instructions.as_slice() {
+            [Instruction::Number { value }] => Some(*value),
+            _ => None,
+        }
+    }
+}
+
+/// Context for folding constants. Constant expressions don't use variables or randomness, so this is never called.
+struct NoVariables;
+
+impl FunctionScriptContext for NoVariables {
+    fn variable(&mut self, _variable: Variable, _index: Option<i64>) -> f64 {
+        unreachable!("Constant expressions don't use variables")
+    }
+
+    fn random(&mut self) -> f64 {
+        unreachable!("Constant expressions don't use randomness")
+    }
+}
+
+fn simplify_tree(mut node: Node) -> Node {
+    node.args = node.args.into_iter().map(simplify_tree).collect();
+    simplify(node)
+}
+
+/// Simplifies a node whose arguments have already been simplified.
+fn simplify(mut node: Node) -> Node {
+    let foldable = match &node.instruction {
+        Instruction::Number { .. } | Instruction::Variable { .. } => false,
+        Instruction::FunctionCall { name, arg_count } => match CallTarget::resolve(name, *arg_count) {
+            Ok(CallTarget::Function(function)) => !function.is_random(),
+            _ => false,
+        },
+        _ => true,
+    };
+    if foldable && node.args.iter().all(|arg| arg.as_number().is_some()) {
+        let mut instructions = InstructionSmallVec::new();
+        node.emit(&mut instructions);
+        let value = evaluate_function_script(&instructions, &mut NoVariables).expect("Analysis checked the script");
+        return Node::number(value);
+    }
+
+    if node.instruction == Instruction::UnaryNegative && node.args[0].instruction == Instruction::UnaryNegative {
+        let inner = node.args.remove(0);
+        return inner.args.into_iter().next().expect("Negation has an argument");
+    }
+
+    let constant_arg = |idx: usize| node.args.get(idx).and_then(Node::as_number);
+    let keep = match &node.instruction {
+        Instruction::Addition if constant_arg(0) == Some(0.0) => Some(1),
+        Instruction::Addition | Instruction::Subtraction if constant_arg(1) == Some(0.0) => Some(0),
+        Instruction::Multiplication if constant_arg(0) == Some(1.0) => Some(1),
+        Instruction::Multiplication | Instruction::Division if constant_arg(1) == Some(1.0) => Some(0),
+        Instruction::FunctionCall { name, arg_count } => match (CallTarget::resolve(name, *arg_count), constant_arg(0))
+        {
+            (Ok(CallTarget::Function(Function::If)), Some(condition)) => Some(if condition != 0.0 { 1 } else { 2 }),
+            _ => None,
+        },
+        _ => None,
+    };
+    match keep {
+        Some(idx) => node.args.swap_remove(idx),
+        None => node,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::parse::function_scripts::{
+        optimize_function_script, parse_function_script, FunctionScriptContext, Instruction, ParsedFunctionScript,
+        Variable,
+    };
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    fn parse(source: &str) -> ParsedFunctionScript {
+        let (remaining, script) = parse_function_script(source).expect("Could not parse");
+        assert_eq!(remaining, "", "{}", source);
+        script
+    }
+
+    fn optimize(source: &str) -> Vec<Instruction> {
+        optimize_function_script(&parse(source).instructions).into_vec()
+    }
+
+    fn num(value: f64) -> Instruction {
+        Instruction::Number { value }
+    }
+
+    fn var(name: &str) -> Instruction {
+        Instruction::Variable {
+            name: String::from(name),
+        }
+    }
+
+    #[bve_derive::bve_test]
+    #[test]
+    fn folding() {
+        assert_eq!(optimize("1 + 2 * 3"), vec![num(7.0)]);
+        assert_eq!(optimize("speed * (60 / 3.6)"), vec![
+            var("speed"),
+            num(60.0 / 3.6),
+            Instruction::Multiplication
+        ]);
+        assert_eq!(optimize("sin[0] + max[1, 2, 3] + (1 / 0)"), vec![num(3.0)]);
+        assert_eq!(optimize("-(-(speed))"), vec![var("speed")]);
+        // Random functions are never folded
+        assert_eq!(optimize("random[0, 1]"), parse("random[0, 1]").instructions.into_vec());
+    }
+
+    #[bve_derive::bve_test]
+    #[test]
+    fn identities() {
+        for source in &[
+            "speed + 0",
+            "0 + speed",
+            "speed - 0",
+            "speed * 1",
+            "1 * speed",
+            "speed / 1",
+            "(speed + 0) * (2 - 1)",
+        ] {
+            assert_eq!(optimize(source), vec![var("speed")], "{}", source);
+        }
+        // Not identities
+        assert_eq!(optimize("0 - speed").len(), 3);
+        assert_eq!(optimize("1 / speed").len(), 3);
+    }
+
+    #[bve_derive::bve_test]
+    #[test]
+    fn if_short_circuit() {
+        assert_eq!(optimize("if[1 < 2, speed, time]"), vec![var("speed")]);
+        assert_eq!(optimize("if[0, speed, time * 2]"), vec![
+            var("time"),
+            num(2.0),
+            Instruction::Multiplication
+        ]);
+        assert_eq!(optimize("if[speed, 1, 2]").len(), 4);
+    }
+
+    #[bve_derive::bve_test]
+    #[test]
+    fn constant_scripts() {
+        assert_eq!(parse("if[2 > 1, 5, speed]").optimized().constant_value(), Some(5.0));
+        assert_eq!(parse("speed").optimized().constant_value(), None);
+        // Broken scripts are left alone
+        assert_eq!(
+            optimize("1 + 2 + nothing"),
+            parse("1 + 2 + nothing").instructions.into_vec()
+        );
+    }
+
+    /// Each variable has a fixed value, so scripts get the same values no matter which variables they read.
+    struct TestContext {
+        values: [f64; 4],
+    }
+
+    impl TestContext {
+        fn new(rng: &mut StdRng) -> Self {
+            let mut values = [0.0; 4];
+            for value in &mut values {
+                *value = match rng.gen_range(0, 4) {
+                    0 => 0.0,
+                    1 => 1.0,
+                    _ => rng.gen_range(-100.0, 100.0),
+                };
+            }
+            Self { values }
+        }
+    }
+
+    impl FunctionScriptContext for TestContext {
+        fn variable(&mut self, variable: Variable, index: Option<i64>) -> f64 {
+            match (variable, index) {
+                (Variable::Speed, None) => self.values[0],
+                (Variable::Time, None) => self.values[1],
+                (Variable::PluginState, _) => self.values[2],
+                _ => self.values[3],
+            }
+        }
+
+        fn random(&mut self) -> f64 {
+            0.5
+        }
+    }
+
+    const OPERATORS: &[&str] = &["+", "-", "*", "/", "&", "|", "^", "==", "!=", "<", ">", "<=", ">="];
+    const LEAVES: &[&str] = &[
+        "0",
+        "1",
+        "2",
+        "0.5",
+        "10",
+        "speed",
+        "time",
+        "pluginState[1]",
+        "speed[2]",
+    ];
+
+    fn random_expression(rng: &mut StdRng, depth: u32) -> String {
+        if depth == 0 || rng.gen_range(0, 4) == 0 {
+            return String::from(LEAVES[rng.gen_range(0, LEAVES.len())]);
+        }
+        let choice = rng.gen_range(0, 6);
+        let operator = OPERATORS[rng.gen_range(0, OPERATORS.len())];
+        let a = random_expression(rng, depth - 1);
+        let b = random_expression(rng, depth - 1);
+        match choice {
+            0 => format!("-({})", a),
+            1 => format!("!({})", a),
+            2 => format!("if[{}, {}, {}]", a, b, random_expression(rng, depth - 1)),
+            3 => format!("min[{}, {}]", a, b),
+            4 => format!("mod[{}, {}]", a, b),
+            _ => format!("({}) {} ({})", a, operator, b),
+        }
+    }
+
+    #[bve_derive::bve_test]
+    #[test]
+    fn differential() {
+        let mut rng = StdRng::seed_from_u64(0x00b5_e5c1);
+        for _ in 0..500 {
+            let source = random_expression(&mut rng, 5);
+            let original = parse(&source);
+            let optimized = original.optimized();
+            assert!(
+                optimized.instructions.len() <= original.instructions.len(),
+                "{}",
+                source
+            );
+
+            for _ in 0..4 {
+                let mut context = TestContext::new(&mut rng);
+                let expected = original.evaluate(&mut context).expect("Could not evaluate original");
+                let actual = optimized.evaluate(&mut context).expect("Could not evaluate optimized");
+                let same = expected == actual || (expected.is_nan() && actual.is_nan());
+                assert!(same, "{} with {:?}: {} != {}", source, context.values, expected, actual);
+            }
+        }
+    }
+}
@@ -0,0 +1,234 @@
+use crate::parse::{
+    function_scripts::{
+        analyze_function_script, Arity, Function, FunctionScriptWarning, Instruction, InstructionSmallVec,
+        ParsedFunctionScript, Variable, VariableIndex,
+    },
+    kvp::{FromKVPValue, KVPGenericWarningKind},
+};
+
+/// Parses a function script written in reverse polish notation, as used by the `*FunctionRPN` keys of animated
+/// files, into the same instructions [`parse_function_script`](super::parse_function_script) gives for the infix
+/// version.
+///
+/// Tokens are separated by whitespace, and each is one of:
+///
+/// - A number, which may be negative.
+/// - An operator: `+ - * / == != < > <= >= & | ^` take two values, `!` takes one.
+/// - The name of a [`Function`], taking as many values as it accepts. Functions that accept a variable amount take the
+///   fewest they accept, but at least two.
+/// - The name of a [`Variable`]. Variables that must be indexed take their index off the stack. Variables that can
+///   optionally be indexed by car take the car off the stack when written with `index` added to the end of the name.
+/// - Any other name, which is kept as a variable for [`analyze_function_script`] to report.
+///
+/// Returns `None` if any token is none of these.
+#[must_use]
+pub fn parse_rpn_function_script(input: &str) -> Option<ParsedFunctionScript> {
+    input
+        .split_whitespace()
+        .map(parse_token)
+        .collect::<Option<InstructionSmallVec>>()
+        .map(ParsedFunctionScript::from)
+}
+
+fn parse_token(token: &str) -> Option<Instruction> {
+    Some(match token {
+        "+" => Instruction::Addition,
+        "-" => Instruction::Subtraction,
+        "*" => Instruction::Multiplication,
+        "/" => Instruction::Division,
+        "|" => Instruction::LogicalOr,
+        "&" => Instruction::LogicalAnd,
+        "^" => Instruction::LogicalXor,
+        "!" => Instruction::UnaryLogicalNot,
+        "==" => Instruction::Equal,
+        "!=" => Instruction::NotEqual,
+        "<" => Instruction::Less,
+        ">" => Instruction::Greater,
+        "<=" => Instruction::LessEqual,
+        ">=" => Instruction::GreaterEqual,
+        _ if token.starts_with(|c: char| c.is_ascii_alphabetic()) => return name_token(token),
+        _ => {
+            let value: f64 = token.parse().ok()?;
+            if !value.is_finite() {
+                return None;
+            }
+            Instruction::Number { value }
+        }
+    })
+}
+
+fn name_token(token: &str) -> Option<Instruction> {
+    // Same characters as names in infix scripts
+    if !token.chars().all(|c| c.is_ascii_alphanumeric() || c == '.') {
+        return None;
+    }
+
+    if let Some(function) = Function::from_name(token) {
+        let arg_count = match function.arity() {
+            Arity::Exactly(count) => count,
+            Arity::AtLeast(count) => count.max(2),
+        };
+        return Some(Instruction::FunctionCall {
+            name: token.to_owned(),
+            arg_count,
+        });
+    }
+
+    let indexed = |name: &str| Instruction::FunctionCall {
+        name: name.to_owned(),
+        arg_count: 1,
+    };
+    match Variable::from_name(token).map(Variable::index) {
+        Some(VariableIndex::Required) => Some(indexed(token)),
+        Some(_) => Some(Instruction::Variable { name: token.to_owned() }),
+        None => {
+            // All characters are ascii, so any byte offset is a char boundary
+            let suffix_start = token.len().saturating_sub("index".len());
+            let (base, suffix) = token.split_at(suffix_start);
+            let car_indexed = Variable::from_name(base).map(Variable::index) == Some(VariableIndex::OptionalCar);
+            if suffix.eq_ignore_ascii_case("index") && car_indexed {
+                Some(indexed(base))
+            } else {
+                Some(Instruction::Variable { name: token.to_owned() })
+            }
+        }
+    }
+}
+
+/// Function script written in reverse polish notation. Converts into the [`ParsedFunctionScript`] it is equivalent to.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct RPNFunctionScript(pub ParsedFunctionScript);
+
+impl From<RPNFunctionScript> for ParsedFunctionScript {
+    fn from(script: RPNFunctionScript) -> Self {
+        script.0
+    }
+}
+
+impl FromKVPValue for RPNFunctionScript {
+    fn from_kvp_value(value: &str) -> Option<Self> {
+        parse_rpn_function_script(value).map(Self)
+    }
+
+    fn kvp_value_warnings(&self, _value: &str) -> Vec<KVPGenericWarningKind> {
+        analyze_function_script(&self.0.instructions)
+            .into_iter()
+            .map(|problem| KVPGenericWarningKind::FunctionScript {
+                warning: FunctionScriptWarning::Invalid { problem },
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::parse::{
+        animated::ParsedAnimatedObject,
+        function_scripts::{
+            parse_function_script, parse_rpn_function_script, FunctionScriptErrorKind, FunctionScriptWarning,
+            Instruction,
+        },
+        kvp::{KVPGenericWarning, KVPGenericWarningKind},
+        FileParser, Span,
+    };
+
+    fn infix(source: &str) -> Vec<Instruction> {
+        let (remaining, script) = parse_function_script(source).expect("Could not parse");
+        assert_eq!(remaining, "", "{}", source);
+        script.instructions.into_vec()
+    }
+
+    fn rpn(source: &str) -> Vec<Instruction> {
+        parse_rpn_function_script(source)
+            .expect("Could not parse")
+            .instructions
+            .into_vec()
+    }
+
+    #[bve_derive::bve_test]
+    #[test]
+    fn same_as_infix() {
+        let pairs = [
+            ("1", "1"),
+            ("speed 3.6 *", "speed * 3.6"),
+            ("1 2 3 - -", "1 - (2 - 3)"),
+            ("a b == ! c &", "!(a == b) & c"),
+            (
+                "a b != c d <= e f >= ^ g h < i j > & | |",
+                "a != b | c <= d ^ e >= f | g < h & i > j",
+            ),
+            ("speed 10 > 1 0 if", "if[speed > 10, 1, 0]"),
+            ("time sin abs 2 1 max min", "min[abs[sin[time]], max[2, 1]]"),
+            ("1 2 plus 3 4 times / minus", "minus[plus[1, 2] / times[3, 4]]"),
+            (
+                "3 pluginState 1 speedIndex + speed +",
+                "pluginState[3] + speed[1] + speed",
+            ),
+            (
+                "leftDoorsTarget 0 == leftDoors 0.5 <= &",
+                "leftDoorsTarget == 0 & leftDoors <= 0.5",
+            ),
+        ];
+        for (rpn_source, infix_source) in &pairs {
+            assert_eq!(rpn(rpn_source), infix(infix_source), "{}", rpn_source);
+        }
+        assert_eq!(rpn("  -2.5\t speed\n* "), vec![
+            Instruction::Number { value: -2.5 },
+            Instruction::Variable {
+                name: String::from("speed")
+            },
+            Instruction::Multiplication
+        ]);
+    }
+
+    #[bve_derive::bve_test]
+    #[test]
+    fn invalid() {
+        assert_eq!(parse_rpn_function_script("1 2 %"), None);
+        assert_eq!(parse_rpn_function_script("sin[time]"), None);
+        assert_eq!(parse_rpn_function_script("1e999"), None);
+        // Unknown names and bad stack use parse, and are left to analysis
+        assert_eq!(rpn("spede"), vec![Instruction::Variable {
+            name: String::from("spede")
+        }]);
+        assert_eq!(rpn("+"), vec![Instruction::Addition]);
+    }
+
+    #[bve_derive::bve_test]
+    #[test]
+    fn kvp_aliases() {
+        let source = indoc::indoc!(
+            r#"
+            [Object]
+            States = a.b3d, b.b3d
+            StateFunctionRPN = speed 10 > 1 0 if
+            RotateXFunctionRPN = time sin
+            TranslateYFunctionRPN = 1 +
+        "#
+        );
+        let result = ParsedAnimatedObject::parse_from(source);
+        let object = &result.objects[0];
+        assert_eq!(
+            object
+                .state_function
+                .as_ref()
+                .map(|script| script.instructions.to_vec()),
+            Some(infix("if[speed > 10, 1, 0]"))
+        );
+        assert_eq!(
+            object
+                .rotate_x_function
+                .as_ref()
+                .map(|script| script.instructions.to_vec()),
+            Some(infix("sin[time]"))
+        );
+        assert_eq!(result.warnings, vec![KVPGenericWarning {
+            span: Span::from_line(5),
+            kind: KVPGenericWarningKind::FunctionScript {
+                warning: FunctionScriptWarning::Invalid {
+                    problem: FunctionScriptErrorKind::StackUnderflow
+                }
+            }
+        }]);
+    }
+}
@@ -0,0 +1,59 @@
+use crate::parse::function_scripts::{Instruction, InstructionSmallVec};
+
+/// Amount of values `instruction` takes off the stack. Every instruction puts one value back.
+pub(super) fn operand_count(instruction: &Instruction) -> usize {
+    match instruction {
+        Instruction::Number { .. } | Instruction::Variable { .. } => 0,
+        Instruction::UnaryNegative | Instruction::UnaryLogicalNot => 1,
+        Instruction::FunctionCall { arg_count, .. } => *arg_count,
+        _ => 2,
+    }
+}
+
+/// An instruction along with the subtrees giving each of its operands.
+pub(super) struct Node {
+    pub instruction: Instruction,
+    pub args: Vec<Node>,
+}
+
+impl Node {
+    /// Builds the tree of a script. Returns `None` if the script underflows the stack or doesn't give exactly one
+    /// value.
+    pub fn from_instructions(instructions: &[Instruction]) -> Option<Self> {
+        let mut stack: Vec<Self> = Vec::new();
+        for instruction in instructions {
+            let args_start = stack.len().checked_sub(operand_count(instruction))?;
+            let args = stack.split_off(args_start);
+            stack.push(Self {
+                instruction: instruction.clone(),
+                args,
+            });
+        }
+        match stack.len() {
+            1 => stack.pop(),
+            _ => None,
+        }
+    }
+
+    pub fn number(value: f64) -> Self {
+        Self {
+            instruction: Instruction::Number { value },
+            args: Vec::new(),
+        }
+    }
+
+    pub fn as_number(&self) -> Option<f64> {
+        match self.instruction {
+            Instruction::Number { value } => Some(value),
+            _ => None,
+        }
+    }
+
+    /// Writes the tree back out as instructions.
+    pub fn emit(self, output: &mut InstructionSmallVec) {
+        for arg in self.args {
+            arg.emit(output);
+        }
+        output.push(self.instruction);
+    }
+}
@@ -3,7 +3,7 @@
 #![allow(clippy::shadow_unrelated)] // These are tests
 
 use crate::parse::{
-    kvp::{parse_kvp_file, traits::FromKVPFile, KVPGenericWarning, KVPGenericWarningKind, ANIMATED_LIKE},
+    kvp::{parse_kvp_file, traits::FromKVPFile, FromKVPValue, KVPGenericWarning, KVPGenericWarningKind, ANIMATED_LIKE},
     Span,
 };
 use bve_derive::{FromKVPFile, FromKVPSection};
@@ -290,6 +290,53 @@ fn alias_kvp() {
     assert_eq!(warnings, vec![]);
 }
 
+#[test]
+fn alternate_alias_kvp() {
+    #[derive(Debug, Default, Clone, PartialEq, FromKVPFile)]
+    struct File {
+        #[kvp(bare)]
+        first: Section,
+    }
+
+    #[derive(Debug, Default, Clone, PartialEq, FromKVPSection)]
+    struct Section {
+        #[kvp(alternate_alias = "some-percent; other-percent", alternate = "Option<Percent>")]
+        some: Option<f32>,
+    }
+
+    #[derive(Debug, Clone, PartialEq)]
+    struct Percent(f32);
+
+    impl FromKVPValue for Percent {
+        fn from_kvp_value(value: &str) -> Option<Self> {
+            if !value.ends_with('%') {
+                return None;
+            }
+            f32::from_kvp_value(value.trim_end_matches('%')).map(Self)
+        }
+    }
+
+    impl From<Percent> for f32 {
+        fn from(percent: Percent) -> Self {
+            percent.0 / 100.0
+        }
+    }
+
+    let file_lit = indoc!(
+        r#"
+        some = 0.2
+        other-percent = 50%
+    "#
+    );
+
+    let kvp = parse_kvp_file(file_lit, ANIMATED_LIKE);
+    let (parsed, warnings) = File::from_kvp_file(&kvp);
+    let mut answer = File::default();
+    answer.first.some = Some(0.5);
+    assert_eq!(parsed, answer);
+    assert_eq!(warnings, vec![]);
+}
+
 #[test]
 fn section_alias() {
     #[derive(Debug, Default, Clone, PartialEq, FromKVPFile)]
@@ -2,6 +2,7 @@ use crate::{
     l10n::ForceEnglish,
     localize,
     parse::{
+        function_scripts::{FunctionScriptErrorKind, FunctionScriptWarning},
         kvp::{KVPFile, KVPSection},
         util::{parse_loose_number, parse_loose_numeric_bool},
         Span, UserError, UserErrorCategory,
@@ -43,16 +44,64 @@ impl UserError for KVPGenericWarning {
             KVPGenericWarningKind::InvalidValue { value } => {
                 localize!(@en, "kvp-invalid-value", "value" -> value.as_str())
             }
+            KVPGenericWarningKind::FunctionScript { warning } => function_script_description(warning, en),
+        }
+    }
+}
+
+fn function_script_description(warning: &FunctionScriptWarning, en: ForceEnglish) -> String {
+    let problem = match warning {
+        FunctionScriptWarning::TrailingInput { input } => {
+            return localize!(@en, "kvp-function-script-trailing-input", "input" -> input.as_str());
+        }
+        FunctionScriptWarning::Invalid { problem } => problem,
+    };
+    match problem {
+        FunctionScriptErrorKind::StackUnderflow => localize!(@en, "kvp-function-script-stack-underflow"),
+        FunctionScriptErrorKind::InvalidResult { stack_size } => {
+            localize!(@en, "kvp-function-script-invalid-result", "count" -> *stack_size)
+        }
+        FunctionScriptErrorKind::UnknownFunction { name } => {
+            localize!(@en, "kvp-function-script-unknown-function", "name" -> name.as_str())
+        }
+        FunctionScriptErrorKind::WrongArgumentCount { name, expected, found } => localize!(
+            @en,
+            "kvp-function-script-argument-count",
+            "name" -> name.as_str(),
+            "expected" -> expected.to_string(),
+            "found" -> *found
+        ),
+        FunctionScriptErrorKind::UnknownVariable { name } => {
+            localize!(@en, "kvp-function-script-unknown-variable", "name" -> name.as_str())
+        }
+        FunctionScriptErrorKind::MissingIndex { name } => {
+            localize!(@en, "kvp-function-script-missing-index", "name" -> name.as_str())
+        }
+        FunctionScriptErrorKind::UnexpectedIndex { name } => {
+            localize!(@en, "kvp-function-script-unexpected-index", "name" -> name.as_str())
         }
     }
 }
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum KVPGenericWarningKind {
-    UnknownSection { name: String },
-    UnknownField { name: String },
-    TooManyFields { idx: u64, max: u64 },
-    InvalidValue { value: String },
+    UnknownSection {
+        name: String,
+    },
+    UnknownField {
+        name: String,
+    },
+    TooManyFields {
+        idx: u64,
+        max: u64,
+    },
+    InvalidValue {
+        value: String,
+    },
+    /// Function script that parsed, but has problems.
+    FunctionScript {
+        warning: FunctionScriptWarning,
+    },
 }
 
 pub trait FromKVPFile: Default {
@@ -84,6 +133,29 @@ pub trait FromKVPValue {
     fn from_kvp_value(value: &str) -> Option<Self>
     where
         Self: Sized;
+
+    /// Problems with a `value` that was successfully converted into `self`. Each is reported as a warning on the
+    /// field the value came from.
+    #[must_use]
+    fn kvp_value_warnings(&self, _value: &str) -> Vec<KVPGenericWarningKind> {
+        Vec::new()
+    }
+}
+
+/// Conversion from a value parsed as another type, used by fields with `#[kvp(alternate_alias = "...", alternate =
+/// "...")]` to accept keys whose values are written differently.
+pub trait FromAlternateKVPValue<T> {
+    #[must_use]
+    fn from_alternate_kvp_value(value: T) -> Self;
+}
+
+impl<T, U> FromAlternateKVPValue<Option<T>> for Option<U>
+where
+    U: From<T>,
+{
+    fn from_alternate_kvp_value(value: Option<T>) -> Self {
+        value.map(U::from)
+    }
 }
 
 macro_rules! impl_from_kvp_value_primitive {
@@ -112,6 +184,11 @@ where
     fn from_kvp_value(value: &str) -> Option<Self> {
         Some(T::from_kvp_value(value))
     }
+
+    fn kvp_value_warnings(&self, value: &str) -> Vec<KVPGenericWarningKind> {
+        self.as_ref()
+            .map_or_else(Vec::new, |inner| inner.kvp_value_warnings(value))
+    }
 }
 
 impl FromKVPValue for String {
@@ -18,6 +18,18 @@ pub struct MeshWarning {
 pub enum MeshWarningKind {
     /// Instruction no longer does anything anymore
     UselessInstruction { name: String },
+    /// Data object in a `.x` file of a template that isn't supported
+    UnsupportedXTemplate { name: String },
+    /// Face uses the same vertex more than once
+    DuplicateFaceIndex { idx: usize },
+    /// Triangle has no area, so can't be seen and has no normal
+    DegenerateTriangle,
+    /// Transformation made vertex positions infinite or NaN
+    NonFinitePosition,
+    /// Vertex normal couldn't be calculated, as its faces have no area or cancel each other out
+    ZeroLengthNormal,
+    /// File isn't valid in the detected text encoding, so some characters were replaced
+    MalformedEncoding { encoding: String },
 }
 
 impl UserError for MeshWarning {
@@ -34,6 +46,18 @@ impl UserError for MeshWarning {
             MeshWarningKind::UselessInstruction { name } => {
                 localize!(@en, "mesh-warning-useless-instruction", "name" -> name.as_str())
             }
+            MeshWarningKind::UnsupportedXTemplate { name } => {
+                localize!(@en, "mesh-warning-unsupported-x-template", "name" -> name.as_str())
+            }
+            MeshWarningKind::DuplicateFaceIndex { idx } => {
+                localize!(@en, "mesh-warning-duplicate-face-index", "idx" -> *idx)
+            }
+            MeshWarningKind::DegenerateTriangle => localize!(@en, "mesh-warning-degenerate-triangle"),
+            MeshWarningKind::NonFinitePosition => localize!(@en, "mesh-warning-non-finite-position"),
+            MeshWarningKind::ZeroLengthNormal => localize!(@en, "mesh-warning-zero-length-normal"),
+            MeshWarningKind::MalformedEncoding { encoding } => {
+                localize!(@en, "mesh-warning-malformed-encoding", "encoding" -> encoding.as_str())
+            }
         }
     }
 }
@@ -71,6 +95,16 @@ pub enum MeshErrorKind {
     },
     /// Unknown csv error
     UnknownCSV,
+    /// Material library referenced by an obj could not be read or parsed
+    UnreadableMaterialLibrary { file: String, error: String },
+    /// `.x` file doesn't start with a text format header
+    InvalidXHeader,
+    /// Token in a `.x` file that doesn't fit where it is
+    UnexpectedXToken { token: String },
+    /// Data object in a `.x` file ended before all of its data was read
+    UnexpectedXEnd { template: String },
+    /// Material referenced in a `.x` file does not exist
+    UnknownXMaterial { name: String },
 }
 
 impl UserError for MeshError {
@@ -93,6 +127,19 @@ impl UserError for MeshError {
                 if en == ForceEnglish::English { msg_english } else { msg }.clone()
             }
             MeshErrorKind::UnknownCSV => localize!(@en, "mesh-unknown-csv"),
+            MeshErrorKind::UnreadableMaterialLibrary { file, error } => {
+                localize!(@en, "mesh-error-unreadable-material-library", "file" -> file.as_str(), "error" -> error.as_str())
+            }
+            MeshErrorKind::InvalidXHeader => localize!(@en, "mesh-error-invalid-x-header"),
+            MeshErrorKind::UnexpectedXToken { token } => {
+                localize!(@en, "mesh-error-unexpected-x-token", "token" -> token.as_str())
+            }
+            MeshErrorKind::UnexpectedXEnd { template } => {
+                localize!(@en, "mesh-error-unexpected-x-end", "template" -> template.as_str())
+            }
+            MeshErrorKind::UnknownXMaterial { name } => {
+                localize!(@en, "mesh-error-unknown-x-material", "name" -> name.as_str())
+            }
         }
     }
 }
@@ -9,15 +9,20 @@ use std::iter::FromIterator;
 
 /// Adds a comma after the first space on each line. Forces newline on last line. Lowercases string.
 pub(in crate::parse::mesh::instructions) fn b3d_to_csv_syntax(input: &str) -> String {
+    b3d_add_commas(&input.to_lowercase())
+}
+
+/// Adds a comma after the first space on each line. Forces newline on last line.
+fn b3d_add_commas(input: &str) -> String {
     trace!("Processing .b3d into .csv");
 
     let mut p = String::with_capacity((input.len() as f32 * 1.1) as usize);
     for line in input.lines() {
-        let mut lowered = line.to_lowercase();
-        if let Some(idx) = lowered.find(' ') {
-            lowered.replace_range(idx..idx, ",")
+        let mut line = line.to_owned();
+        if let Some(idx) = line.find(' ') {
+            line.replace_range(idx..idx, ",")
         }
-        p.push_str(&lowered);
+        p.push_str(&line);
         p.push('\n');
     }
     if p.is_empty() {
@@ -26,6 +31,29 @@ pub(in crate::parse::mesh::instructions) fn b3d_to_csv_syntax(input: &str) -> St
     p
 }
 
+/// Same processing as [`create_instructions`] does, without lowercasing, so instructions that care about case can
+/// use the original arguments.
+fn case_preserving_csv_syntax(input: &str, file_type: FileType) -> String {
+    if file_type == FileType::B3D {
+        b3d_add_commas(input)
+    } else {
+        let mut p = input.to_owned();
+        if !p.ends_with('\n') {
+            p.push('\n');
+        }
+        p
+    }
+}
+
+fn first_argument(arguments: &str) -> &str {
+    arguments.split(',').next().unwrap_or_default().trim()
+}
+
+/// Everything after the instruction name on `line`, trimmed.
+fn arguments_text(line: &str) -> &str {
+    line.splitn(2, ',').nth(1).unwrap_or_default().trim()
+}
+
 enum DeserializeInstructionError {
     MeshError(MeshError),
     MeshWarning(MeshWarning),
@@ -49,9 +77,11 @@ impl From<csv::Error> for DeserializeInstructionError {
     }
 }
 
+/// `original` is the unparsed text of the arguments in their original case.
 fn deserialize_instruction(
     inst_type: InstructionType,
     record: &StringRecord,
+    original: &str,
     span: Span,
 ) -> Result<Instruction, DeserializeInstructionError> {
     let data = match inst_type {
@@ -170,6 +200,36 @@ fn deserialize_instruction(
             let parsed: SetTextureCoordinates = record.deserialize(None)?;
             InstructionData::SetTextureCoordinates(parsed)
         }
+        InstructionType::SetWrapMode => {
+            let parsed: SetWrapMode = record.deserialize(None)?;
+            InstructionData::SetWrapMode(parsed)
+        }
+        InstructionType::SetText => InstructionData::SetText(SetText {
+            text: original.to_owned(),
+        }),
+        InstructionType::SetTextColor => {
+            let parsed: SetTextColor = record.deserialize(None)?;
+            InstructionData::SetTextColor(parsed)
+        }
+        InstructionType::SetBackgroundColor => {
+            let parsed: SetBackgroundColor = record.deserialize(None)?;
+            InstructionData::SetBackgroundColor(parsed)
+        }
+        InstructionType::SetTextPadding => {
+            let parsed: SetTextPadding = record.deserialize(None)?;
+            InstructionData::SetTextPadding(parsed)
+        }
+        InstructionType::SetFont => InstructionData::SetFont(SetFont {
+            font: first_argument(original).to_owned(),
+        }),
+        InstructionType::LoadLightMap => InstructionData::LoadLightMap(LoadLightMap {
+            file: first_argument(original).to_owned(),
+        }),
+        InstructionType::SetCrossfading => {
+            let parsed: SetCrossfading = record.deserialize(None)?;
+            InstructionData::SetCrossfading(parsed)
+        }
+        InstructionType::EnableHacks => InstructionData::EnableHacks(EnableHacks),
     };
     Ok(Instruction { data, span })
 }
@@ -198,6 +258,10 @@ pub fn create_instructions(input: &str, file_type: FileType) -> InstructionList
 
     let stripped = strip_comments(&processed, ';');
 
+    // Same lines as `stripped`, in their original case, for instructions that take free text
+    let original = strip_comments(&case_preserving_csv_syntax(input, file_type), ';');
+    let original_lines: Vec<&str> = original.lines().collect();
+
     let csv_reader = ReaderBuilder::new()
         .has_headers(false)
         .flexible(true)
@@ -232,8 +296,12 @@ pub fn create_instructions(input: &str, file_type: FileType) -> InstructionList
 
                 // Remove the already parsed instruction name
                 let arguments = StringRecord::from_iter(record.iter().skip(1));
+                let original_arguments = span
+                    .line
+                    .and_then(|line| original_lines.get((line as usize).checked_sub(1)?))
+                    .map_or("", |line| arguments_text(line));
 
-                let inst = deserialize_instruction(instruction, &arguments, span);
+                let inst = deserialize_instruction(instruction, &arguments, original_arguments, span);
 
                 match inst {
                     Ok(i) => instructions.instructions.push(i),
@@ -962,4 +1030,167 @@ mod test {
             })
         );
     }
+
+    #[bve_derive::bve_test]
+    #[test]
+    fn wrap_mode() {
+        instruction_assert_default!(
+            "WrapMode",
+            "SetWrapMode",
+            "ClampRepeat",
+            InstructionData::SetWrapMode(SetWrapMode {
+                wrap_mode: WrapMode::ClampRepeat,
+            }),
+            "",
+            InstructionData::SetWrapMode(SetWrapMode {
+                wrap_mode: WrapMode::RepeatRepeat,
+            })
+        );
+    }
+
+    #[bve_derive::bve_test]
+    #[test]
+    fn text() {
+        instruction_assert_default!(
+            "Text",
+            "SetText",
+            "Platform 1,  Up Trains",
+            InstructionData::SetText(SetText {
+                text: "Platform 1,  Up Trains".into(),
+            }),
+            "",
+            InstructionData::SetText(SetText { text: String::new() })
+        );
+    }
+
+    #[bve_derive::bve_test]
+    #[test]
+    fn text_keeps_case() {
+        for &(input, file_type) in &[
+            (
+                "CreateMeshBuilder\nSetText Hello World ; Comment\nSetFont Arial Bold",
+                FileType::B3D,
+            ),
+            (
+                "CreateMeshBuilder\nSetText, Hello World ; Comment\nSetFont, Arial Bold",
+                FileType::CSV,
+            ),
+        ] {
+            let result = create_instructions(input, file_type);
+            assert!(result.errors.is_empty(), "{:#?}", result);
+            let data: Vec<_> = result.instructions.into_iter().map(|i| i.data).collect();
+            assert_eq!(data, vec![
+                InstructionData::CreateMeshBuilder(CreateMeshBuilder),
+                InstructionData::SetText(SetText {
+                    text: "Hello World".into(),
+                }),
+                InstructionData::SetFont(SetFont {
+                    font: "Arial Bold".into(),
+                }),
+            ]);
+        }
+    }
+
+    #[bve_derive::bve_test]
+    #[test]
+    fn text_color() {
+        instruction_assert_default!(
+            "TextColor",
+            "SetTextColor",
+            "1, 2, 3",
+            InstructionData::SetTextColor(SetTextColor {
+                color: ColorU8RGB::new(1, 2, 3),
+            }),
+            ",,",
+            InstructionData::SetTextColor(SetTextColor {
+                color: ColorU8RGB::zero(),
+            })
+        );
+    }
+
+    #[bve_derive::bve_test]
+    #[test]
+    fn background_color() {
+        instruction_assert_default!(
+            "BackgroundColor",
+            "SetBackgroundColor",
+            "1, 2, 3",
+            InstructionData::SetBackgroundColor(SetBackgroundColor {
+                color: ColorU8RGB::new(1, 2, 3),
+            }),
+            ",,",
+            InstructionData::SetBackgroundColor(SetBackgroundColor {
+                color: ColorU8RGB::splat(255),
+            })
+        );
+    }
+
+    #[bve_derive::bve_test]
+    #[test]
+    fn text_padding() {
+        instruction_assert_default!(
+            "TextPadding",
+            "SetTextPadding",
+            "2, 3.5",
+            InstructionData::SetTextPadding(SetTextPadding {
+                padding: Vec2::new(2.0, 3.5),
+            }),
+            ",",
+            InstructionData::SetTextPadding(SetTextPadding { padding: Vec2::zero() })
+        );
+    }
+
+    #[bve_derive::bve_test]
+    #[test]
+    fn font() {
+        instruction_assert_default!(
+            "Font",
+            "SetFont",
+            "Times New Roman",
+            InstructionData::SetFont(SetFont {
+                font: "Times New Roman".into(),
+            }),
+            "",
+            InstructionData::SetFont(SetFont { font: String::new() })
+        );
+    }
+
+    #[bve_derive::bve_test]
+    #[test]
+    fn light_map() {
+        instruction_assert_default!(
+            "LightMap",
+            "LoadLightMap",
+            "Path/Light.PNG",
+            InstructionData::LoadLightMap(LoadLightMap {
+                file: "Path/Light.PNG".into(),
+            }),
+            "",
+            InstructionData::LoadLightMap(LoadLightMap { file: String::new() })
+        );
+    }
+
+    #[bve_derive::bve_test]
+    #[test]
+    fn crossfading() {
+        instruction_assert_default!(
+            "Crossfading",
+            "SetCrossfading",
+            "True",
+            InstructionData::SetCrossfading(SetCrossfading { enabled: true }),
+            "",
+            InstructionData::SetCrossfading(SetCrossfading { enabled: false })
+        );
+    }
+
+    #[bve_derive::bve_test]
+    #[test]
+    fn enable_hacks() {
+        instruction_assert!(
+            "EnableHacks",
+            "EnableHacks",
+            "",
+            InstructionData::EnableHacks(EnableHacks)
+        );
+    }
 }
@@ -9,6 +9,8 @@
 //!   instructions.
 //! - [`crate::load::mesh::generate_meshes`] executes the instructions to create a mesh.
 //!
+//! [`write_instructions`] goes the other way, turning instructions back into the source of a mesh file.
+//!
 //! The rest of the module is various data structures to support that.
 //!
 //! Makes heavy use of [`bve-derive::serde_proxy`](../../../../bve_derive/attr.serde_proxy.html) and
@@ -16,7 +18,7 @@
 
 use crate::{
     parse::{
-        mesh::{BlendMode, GlowAttenuationMode, MeshError, MeshWarning},
+        mesh::{BlendMode, GlowAttenuationMode, MeshError, MeshWarning, WrapMode},
         util, PrettyPrintResult, Span,
     },
     BVec3A, ColorU8RGB, ColorU8RGBA,
@@ -26,9 +28,11 @@ use glam::{Vec2, Vec3A};
 pub use post_processing::*;
 use serde::Deserialize;
 use std::io;
+pub use writing::*;
 
 mod creation;
 mod post_processing;
+mod writing;
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct InstructionList {
@@ -91,6 +95,23 @@ pub enum InstructionType {
     SetDecalTransparentColor,
     #[serde(alias = "coordinates")]
     SetTextureCoordinates,
+    #[serde(alias = "wrapmode")]
+    SetWrapMode,
+    #[serde(alias = "text")]
+    SetText,
+    #[serde(alias = "textcolor")]
+    SetTextColor,
+    #[serde(alias = "backgroundcolor")]
+    SetBackgroundColor,
+    #[serde(alias = "textpadding")]
+    SetTextPadding,
+    #[serde(alias = "font")]
+    SetFont,
+    #[serde(alias = "lightmap")]
+    LoadLightMap,
+    #[serde(alias = "crossfading")]
+    SetCrossfading,
+    EnableHacks,
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -111,6 +132,15 @@ pub enum InstructionData {
     LoadTexture(LoadTexture),
     SetDecalTransparentColor(SetDecalTransparentColor),
     SetTextureCoordinates(SetTextureCoordinates),
+    SetWrapMode(SetWrapMode),
+    SetText(SetText),
+    SetTextColor(SetTextColor),
+    SetBackgroundColor(SetBackgroundColor),
+    SetTextPadding(SetTextPadding),
+    SetFont(SetFont),
+    LoadLightMap(LoadLightMap),
+    SetCrossfading(SetCrossfading),
+    EnableHacks(EnableHacks),
 }
 
 impl PrettyPrintResult for Instruction {
@@ -132,6 +162,15 @@ impl PrettyPrintResult for Instruction {
             InstructionData::LoadTexture(inner) => inner.fmt(indent, out),
             InstructionData::SetDecalTransparentColor(inner) => inner.fmt(indent, out),
             InstructionData::SetTextureCoordinates(inner) => inner.fmt(indent, out),
+            InstructionData::SetWrapMode(inner) => inner.fmt(indent, out),
+            InstructionData::SetText(inner) => inner.fmt(indent, out),
+            InstructionData::SetTextColor(inner) => inner.fmt(indent, out),
+            InstructionData::SetBackgroundColor(inner) => inner.fmt(indent, out),
+            InstructionData::SetTextPadding(inner) => inner.fmt(indent, out),
+            InstructionData::SetFont(inner) => inner.fmt(indent, out),
+            InstructionData::LoadLightMap(inner) => inner.fmt(indent, out),
+            InstructionData::SetCrossfading(inner) => inner.fmt(indent, out),
+            InstructionData::EnableHacks(inner) => inner.fmt(indent, out),
         }
     }
 }
@@ -278,6 +317,68 @@ pub struct SetTextureCoordinates {
     pub coords: Vec2,
 }
 
+#[bve_derive::serde_proxy]
+pub struct SetWrapMode {
+    #[default("SetWrapMode::default_wrap_mode")]
+    pub wrap_mode: WrapMode,
+}
+
+impl SetWrapMode {
+    const fn default_wrap_mode() -> Option<WrapMode> {
+        Some(WrapMode::RepeatRepeat)
+    }
+}
+
+/// Text is kept in its original case. Commas in the text are kept.
+#[bve_derive::serde_proxy]
+pub struct SetText {
+    #[default("util::some_string")]
+    pub text: String,
+}
+
+#[bve_derive::serde_proxy]
+pub struct SetTextColor {
+    #[default("util::some_zero_u8")]
+    pub color: ColorU8RGB,
+}
+
+#[bve_derive::serde_proxy]
+pub struct SetBackgroundColor {
+    #[default("util::some_u8_max")]
+    pub color: ColorU8RGB,
+}
+
+#[bve_derive::serde_proxy]
+pub struct SetTextPadding {
+    /// unit: pixels
+    #[default("util::some_zero_f32")]
+    pub padding: Vec2,
+}
+
+/// Font name is kept in its original case.
+#[bve_derive::serde_proxy]
+pub struct SetFont {
+    #[default("util::some_string")]
+    pub font: String,
+}
+
+/// Filename is kept in its original case.
+#[bve_derive::serde_proxy]
+pub struct LoadLightMap {
+    #[default("util::some_string")]
+    pub file: String,
+}
+
+#[bve_derive::serde_proxy]
+pub struct SetCrossfading {
+    #[default("util::some_false")]
+    pub enabled: bool,
+}
+
+/// Enables compatibility hacks for the rest of the file.
+#[bve_derive::serde_proxy]
+pub struct EnableHacks;
+
 #[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
 pub enum Sides {
     Unset,
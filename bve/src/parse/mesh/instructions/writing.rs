@@ -0,0 +1,298 @@
+use crate::{
+    parse::mesh::{instructions::*, BlendMode, FileType, GlowAttenuationMode, WrapMode},
+    ColorU8RGB, ColorU8RGBA,
+};
+use glam::{Vec2, Vec3A};
+use std::fmt::Write;
+
+/// Writes `instructions` out as the source of a `file_type` file, one instruction per line.
+///
+/// Every argument is written explicitly, so running the output back through [`create_instructions`] gives the same
+/// instructions. [`AddVertex`] texture coordinates, which only exist after [`post_process`], are written as a
+/// following `SetTextureCoordinates` so post processed instructions can be written as well.
+#[must_use]
+pub fn write_instructions(instructions: &[Instruction], file_type: FileType) -> String {
+    let mut writer = InstructionWriter {
+        output: String::new(),
+        file_type,
+        vertex_count: 0,
+    };
+    for instruction in instructions {
+        writer.write(&instruction.data);
+    }
+    writer.output
+}
+
+struct InstructionWriter {
+    output: String,
+    file_type: FileType,
+    /// Vertices created since the last `CreateMeshBuilder`, needed to know the index of each vertex.
+    vertex_count: usize,
+}
+
+impl InstructionWriter {
+    fn write(&mut self, data: &InstructionData) {
+        match data {
+            InstructionData::CreateMeshBuilder(_) => {
+                self.vertex_count = 0;
+                self.line("CreateMeshBuilder", "[MeshBuilder]", &[]);
+            }
+            InstructionData::AddVertex(vertex) => {
+                self.line("AddVertex", "Vertex", &[vec3(vertex.position), vec3(vertex.normal)]);
+                if vertex.texture_coord != Vec2::zero() {
+                    let index = self.vertex_count.to_string();
+                    self.line("SetTextureCoordinates", "Coordinates", &[
+                        index,
+                        vec2(vertex.texture_coord),
+                    ]);
+                }
+                self.vertex_count += 1;
+            }
+            InstructionData::AddFace(face) => {
+                let indexes = face.indexes.iter().map(usize::to_string).collect::<Vec<_>>().join(", ");
+                match face.sides {
+                    Sides::Two => self.line("AddFace2", "Face2", &[indexes]),
+                    Sides::Unset | Sides::One => self.line("AddFace", "Face", &[indexes]),
+                }
+            }
+            InstructionData::Cube(cube) => {
+                self.vertex_count += 8;
+                self.line("Cube", "Cube", &[vec3(cube.half_dim)]);
+            }
+            InstructionData::Cylinder(cylinder) => {
+                self.vertex_count += 2 * cylinder.sides as usize;
+                self.line("Cylinder", "Cylinder", &[
+                    cylinder.sides.to_string(),
+                    cylinder.upper_radius.to_string(),
+                    cylinder.lower_radius.to_string(),
+                    cylinder.height.to_string(),
+                ]);
+            }
+            InstructionData::Translate(translate) => {
+                self.transform("Translate", translate.application, &[vec3(translate.value)]);
+            }
+            InstructionData::Scale(scale) => {
+                self.transform("Scale", scale.application, &[vec3(scale.value)]);
+            }
+            InstructionData::Rotate(rotate) => {
+                self.transform("Rotate", rotate.application, &[
+                    vec3(rotate.axis),
+                    rotate.angle.to_string(),
+                ]);
+            }
+            InstructionData::Shear(shear) => {
+                self.transform("Shear", shear.application, &[
+                    vec3(shear.direction),
+                    vec3(shear.shear),
+                    shear.ratio.to_string(),
+                ]);
+            }
+            InstructionData::Mirror(mirror) => {
+                let directions = [mirror.directions.x, mirror.directions.y, mirror.directions.z];
+                let directions = directions
+                    .iter()
+                    .map(|&d| if d { "1" } else { "0" })
+                    .collect::<Vec<_>>();
+                self.transform("Mirror", mirror.application, &[directions.join(", ")]);
+            }
+            InstructionData::SetColor(color) => {
+                self.line("SetColor", "Color", &[rgba(color.color)]);
+            }
+            InstructionData::SetEmissiveColor(color) => {
+                self.line("SetEmissiveColor", "EmissiveColor", &[rgb(color.color)]);
+            }
+            InstructionData::SetBlendMode(blend) => {
+                let blend_mode = match blend.blend_mode {
+                    BlendMode::Normal => "Normal",
+                    BlendMode::Additive => "Additive",
+                };
+                let attenuation = match blend.glow_attenuation_mode {
+                    GlowAttenuationMode::DivideExponent2 => "DivideExponent2",
+                    GlowAttenuationMode::DivideExponent4 => "DivideExponent4",
+                };
+                self.line("SetBlendMode", "BlendMode", &[
+                    blend_mode.to_owned(),
+                    blend.glow_half_distance.to_string(),
+                    attenuation.to_owned(),
+                ]);
+            }
+            InstructionData::LoadTexture(texture) => {
+                self.line("LoadTexture", "Load", &[
+                    texture.daytime.clone(),
+                    texture.nighttime.clone(),
+                ]);
+            }
+            InstructionData::SetDecalTransparentColor(color) => {
+                self.line("SetDecalTransparentColor", "Transparent", &[rgb(color.color)]);
+            }
+            InstructionData::SetTextureCoordinates(coords) => {
+                self.line("SetTextureCoordinates", "Coordinates", &[
+                    coords.index.to_string(),
+                    vec2(coords.coords),
+                ]);
+            }
+            InstructionData::SetWrapMode(wrap) => {
+                let wrap_mode = match wrap.wrap_mode {
+                    WrapMode::ClampClamp => "ClampClamp",
+                    WrapMode::ClampRepeat => "ClampRepeat",
+                    WrapMode::RepeatClamp => "RepeatClamp",
+                    WrapMode::RepeatRepeat => "RepeatRepeat",
+                };
+                self.line("SetWrapMode", "WrapMode", &[wrap_mode.to_owned()]);
+            }
+            InstructionData::SetText(text) => self.line("SetText", "Text", &[text.text.clone()]),
+            InstructionData::SetTextColor(color) => self.line("SetTextColor", "TextColor", &[rgb(color.color)]),
+            InstructionData::SetBackgroundColor(color) => {
+                self.line("SetBackgroundColor", "BackgroundColor", &[rgb(color.color)]);
+            }
+            InstructionData::SetTextPadding(padding) => {
+                self.line("SetTextPadding", "TextPadding", &[vec2(padding.padding)]);
+            }
+            InstructionData::SetFont(font) => self.line("SetFont", "Font", &[font.font.clone()]),
+            InstructionData::LoadLightMap(light_map) => {
+                self.line("LoadLightMap", "LightMap", &[light_map.file.clone()]);
+            }
+            InstructionData::SetCrossfading(crossfading) => {
+                let enabled = if crossfading.enabled { "1" } else { "0" };
+                self.line("SetCrossfading", "Crossfading", &[enabled.to_owned()]);
+            }
+            InstructionData::EnableHacks(_) => self.line("EnableHacks", "EnableHacks", &[]),
+        }
+    }
+
+    /// Transformations are the same instruction with an `All` suffix when they apply to all meshes.
+    fn transform(&mut self, name: &str, application: ApplyTo, arguments: &[String]) {
+        let name = match application {
+            ApplyTo::AllMeshes => format!("{}All", name),
+            ApplyTo::Unset | ApplyTo::SingleMesh => name.to_owned(),
+        };
+        self.line(&name, &name, arguments);
+    }
+
+    fn line(&mut self, csv_name: &str, b3d_name: &str, arguments: &[String]) {
+        let arguments = arguments.join(", ");
+        let (name, separator) = match self.file_type {
+            FileType::CSV => (csv_name, ", "),
+            FileType::B3D => (b3d_name, " "),
+        };
+        let result = if arguments.is_empty() {
+            writeln!(self.output, "{}", name)
+        } else {
+            writeln!(self.output, "{}{}{}", name, separator, arguments)
+        };
+        result.expect("Writing to a string cannot fail");
+    }
+}
+
+fn vec2(value: Vec2) -> String {
+    format!("{}, {}", value.x(), value.y())
+}
+
+fn vec3(value: Vec3A) -> String {
+    format!("{}, {}, {}", value.x(), value.y(), value.z())
+}
+
+fn rgb(color: ColorU8RGB) -> String {
+    format!("{}, {}, {}", color.x, color.y, color.z)
+}
+
+fn rgba(color: ColorU8RGBA) -> String {
+    format!("{}, {}, {}, {}", color.x, color.y, color.z, color.w)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::parse::mesh::{instructions::*, FileType};
+    use glam::Vec2;
+
+    /// Instructions without their spans, as those are different after writing.
+    ///
+    /// Leading `CreateMeshBuilder`s are skipped, as post processing adds one to the start every time it runs.
+    fn data(list: &InstructionList) -> Vec<InstructionData> {
+        assert_eq!(list.errors, vec![]);
+        assert_eq!(list.warnings, vec![]);
+        list.instructions
+            .iter()
+            .map(|i| i.data.clone())
+            .skip_while(|d| *d == InstructionData::CreateMeshBuilder(CreateMeshBuilder))
+            .collect()
+    }
+
+    const SOURCE: &str = indoc::indoc!(
+        r#"
+        CreateMeshBuilder
+        AddVertex, 1, 2.5, -3, 0, 1, 0
+        AddVertex, 0.1, 0, 0
+        AddVertex, 0, 0, 1e-7
+        AddFace, 0, 1, 2
+        AddFace2, 2, 1, 0
+        SetTextureCoordinates, 1, 0.5, 1
+        Cube, 1, 2, 3
+        Cylinder, 6, 1, 0.5, 2
+        Translate, 1, 2, 3
+        TranslateAll, 1, 2, 3
+        ScaleAll, 2, 2, 2
+        Rotate, 0, 1, 0, 90
+        Shear, 1, 0, 0, 0, 1, 0, 0.5
+        MirrorAll, 1, 0, 1
+        SetColor, 10, 20, 30, 40
+        SetEmissiveColor, 1, 2, 3
+        SetBlendMode, Additive, 5, DivideExponent2
+        LoadTexture, day.png, night.png
+        SetDecalTransparentColor, 0, 0, 255
+        SetWrapMode, ClampRepeat
+        SetText, Hello, World
+        SetTextColor, 255, 0, 0
+        SetBackgroundColor, 0, 255, 0
+        SetTextPadding, 4, 8
+        SetFont, Arial
+        LoadLightMap, Light.png
+        SetCrossfading, 1
+        EnableHacks
+    "#
+    );
+
+    #[bve_derive::bve_test]
+    #[test]
+    fn round_trip() {
+        let original = create_instructions(SOURCE, FileType::CSV);
+        for &file_type in &[FileType::CSV, FileType::B3D] {
+            let written = write_instructions(&original.instructions, file_type);
+            let reparsed = create_instructions(&written, file_type);
+            assert_eq!(data(&reparsed), data(&original), "{:?}:\n{}", file_type, written);
+        }
+    }
+
+    #[bve_derive::bve_test]
+    #[test]
+    fn round_trip_post_processed() {
+        let original = post_process(create_instructions(SOURCE, FileType::CSV));
+        for &file_type in &[FileType::CSV, FileType::B3D] {
+            let written = write_instructions(&original.instructions, file_type);
+            let reparsed = post_process(create_instructions(&written, file_type));
+            assert_eq!(data(&reparsed), data(&original), "{:?}:\n{}", file_type, written);
+        }
+        let coords = original.instructions.iter().find_map(|i| match &i.data {
+            InstructionData::AddVertex(v) if v.texture_coord != Vec2::zero() => Some(v.texture_coord),
+            _ => None,
+        });
+        assert_eq!(coords, Some(Vec2::new(0.5, 1.0)));
+    }
+
+    #[bve_derive::bve_test]
+    #[test]
+    fn syntax() {
+        let original = create_instructions(
+            "CreateMeshBuilder\nAddFace2, 0, 1, 2\nRotateAll, 1, 0, 0, 45\nSetText, Hello, World\nSetFont, Arial",
+            FileType::CSV,
+        );
+        assert_eq!(
+            write_instructions(&original.instructions, FileType::CSV),
+            "CreateMeshBuilder\nAddFace2, 0, 1, 2\nRotateAll, 1, 0, 0, 45\nSetText, Hello, World\nSetFont, Arial\n"
+        );
+        assert_eq!(
+            write_instructions(&original.instructions, FileType::B3D),
+            "[MeshBuilder]\nFace2 0, 1, 2\nRotateAll 1, 0, 0, 45\nText Hello, World\nFont Arial\n"
+        );
+    }
+}
@@ -13,6 +13,25 @@ pub mod instructions;
 
 pub struct ParsedStaticObject(Vec<instructions::Instruction>);
 
+impl ParsedStaticObject {
+    #[must_use]
+    pub fn instructions(&self) -> &[instructions::Instruction] {
+        &self.0
+    }
+
+    /// Writes the object back out as a `.csv` file.
+    #[must_use]
+    pub fn to_csv(&self) -> String {
+        instructions::write_instructions(&self.0, FileType::CSV)
+    }
+
+    /// Writes the object back out as a `.b3d` file.
+    #[must_use]
+    pub fn to_b3d(&self) -> String {
+        instructions::write_instructions(&self.0, FileType::B3D)
+    }
+}
+
 pub struct ParsedStaticObjectB3D;
 pub struct ParsedStaticObjectCSV;
 
@@ -118,3 +137,25 @@ impl PrettyPrintResult for GlowAttenuationMode {
         }
     }
 }
+
+/// How texture coordinates outside of 0..1 are treated, horizontally then vertically.
+#[repr(C)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum WrapMode {
+    ClampClamp,
+    ClampRepeat,
+    RepeatClamp,
+    RepeatRepeat,
+}
+
+impl PrettyPrintResult for WrapMode {
+    fn fmt(&self, _indent: usize, out: &mut dyn io::Write) -> io::Result<()> {
+        match self {
+            Self::ClampClamp => writeln!(out, "Clamp Clamp"),
+            Self::ClampRepeat => writeln!(out, "Clamp Repeat"),
+            Self::RepeatClamp => writeln!(out, "Repeat Clamp"),
+            Self::RepeatRepeat => writeln!(out, "Repeat Repeat"),
+        }
+    }
+}
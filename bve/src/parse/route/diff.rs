@@ -0,0 +1,345 @@
+//! Semantic differences between two versions of a route.
+//!
+//! Routes are compared after parsing, so formatting, comments, capitalization and `With` usage never show up as
+//! changes. Directives are aligned by track position, then commands at the same position are matched by type.
+
+use crate::parse::route::{
+    ir::{ParsedCommand, ParsedDirective},
+    ParsedRoute, TrackPositionSmallVec,
+};
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::{cmp::Ordering, fmt, mem::discriminant};
+
+/// All changes needed to get from one route to another, ordered by track position.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RouteDiff {
+    pub changes: Vec<RouteChange>,
+}
+
+impl RouteDiff {
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+}
+
+/// A single changed command.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RouteChange {
+    /// In the units of the route file, as written. `Options.UnitOfLength` is not applied.
+    pub position: TrackPositionSmallVec,
+    pub change: ChangeKind,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum ChangeKind {
+    Added {
+        command: ParsedCommand,
+    },
+    Removed {
+        command: ParsedCommand,
+    },
+    Modified {
+        old: ParsedCommand,
+        new: ParsedCommand,
+        fields: Vec<FieldChange>,
+    },
+}
+
+/// A single field that differs between two commands of the same type.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FieldChange {
+    pub field: String,
+    pub old: Value,
+    pub new: Value,
+}
+
+/// Computes the changes between `old` and `new`.
+#[must_use]
+pub fn diff_routes(old: &ParsedRoute, new: &ParsedRoute) -> RouteDiff {
+    let old_groups = group_by_position(old.directives());
+    let new_groups = group_by_position(new.directives());
+
+    let mut changes = Vec::new();
+    let mut old_iter = old_groups.into_iter().peekable();
+    let mut new_iter = new_groups.into_iter().peekable();
+    loop {
+        let ordering = match (old_iter.peek(), new_iter.peek()) {
+            (Some((old_position, _)), Some((new_position, _))) => compare_positions(old_position, new_position),
+            (Some(_), None) => Ordering::Less,
+            (None, Some(_)) => Ordering::Greater,
+            (None, None) => break,
+        };
+        match ordering {
+            Ordering::Less => {
+                let (position, commands) = old_iter.next().unwrap_or_else(|| unreachable!());
+                changes.extend(commands.into_iter().map(|command| RouteChange {
+                    position: position.clone(),
+                    change: ChangeKind::Removed {
+                        command: command.clone(),
+                    },
+                }));
+            }
+            Ordering::Greater => {
+                let (position, commands) = new_iter.next().unwrap_or_else(|| unreachable!());
+                changes.extend(commands.into_iter().map(|command| RouteChange {
+                    position: position.clone(),
+                    change: ChangeKind::Added {
+                        command: command.clone(),
+                    },
+                }));
+            }
+            Ordering::Equal => {
+                let (position, old_commands) = old_iter.next().unwrap_or_else(|| unreachable!());
+                let (_, new_commands) = new_iter.next().unwrap_or_else(|| unreachable!());
+                changes.extend(
+                    diff_commands(old_commands, new_commands)
+                        .into_iter()
+                        .map(|change| RouteChange {
+                            position: position.clone(),
+                            change,
+                        }),
+                );
+            }
+        }
+    }
+
+    RouteDiff { changes }
+}
+
+fn compare_positions(left: &[f32], right: &[f32]) -> Ordering {
+    left.iter()
+        .zip(right.iter())
+        .map(|(l, r)| l.partial_cmp(r).unwrap_or(Ordering::Equal))
+        .find(|&ordering| ordering != Ordering::Equal)
+        .unwrap_or_else(|| left.len().cmp(&right.len()))
+}
+
+/// Groups all commands by position, sorted by position. Routes may revisit earlier positions, so all commands at the
+/// same position end up in the same group, in file order.
+fn group_by_position(directives: &[ParsedDirective]) -> Vec<(TrackPositionSmallVec, Vec<&ParsedCommand>)> {
+    let mut sorted: Vec<&ParsedDirective> = directives.iter().collect();
+    sorted.sort_by(|l, r| compare_positions(&l.position, &r.position));
+
+    sorted
+        .into_iter()
+        .group_by(|directive| directive.position.clone())
+        .into_iter()
+        .map(|(position, group)| (position, group.map(|directive| &directive.command).collect()))
+        .collect()
+}
+
+/// Exact matches are unchanged. Left over commands of the same type are paired up in order as modifications. Anything
+/// still left over was added or removed.
+fn diff_commands(old: Vec<&ParsedCommand>, new: Vec<&ParsedCommand>) -> Vec<ChangeKind> {
+    let mut new_remaining: Vec<Option<&ParsedCommand>> = new.into_iter().map(Some).collect();
+    let mut old_unmatched = Vec::new();
+    for old_command in old {
+        match new_remaining.iter_mut().find(|slot| **slot == Some(old_command)) {
+            Some(slot) => *slot = None,
+            None => old_unmatched.push(old_command),
+        }
+    }
+    let mut new_unmatched: Vec<&ParsedCommand> = new_remaining.into_iter().flatten().collect();
+
+    let mut changes = Vec::new();
+    for old_command in old_unmatched {
+        let paired = new_unmatched
+            .iter()
+            .position(|new_command| discriminant(*new_command) == discriminant(old_command));
+        changes.push(match paired {
+            Some(idx) => {
+                let new_command = new_unmatched.remove(idx);
+                ChangeKind::Modified {
+                    old: old_command.clone(),
+                    new: new_command.clone(),
+                    fields: diff_fields(old_command, new_command),
+                }
+            }
+            None => ChangeKind::Removed {
+                command: old_command.clone(),
+            },
+        });
+    }
+    changes.extend(new_unmatched.into_iter().map(|command| ChangeKind::Added {
+        command: command.clone(),
+    }));
+    changes
+}
+
+/// Splits a command into its type name and its fields, as they appear when serialized.
+fn command_fields(command: &ParsedCommand) -> (String, Value) {
+    match serde_json::to_value(command).expect("Commands are always serializable") {
+        Value::Object(map) => map.into_iter().next().unwrap_or_else(|| unreachable!()),
+        other => unreachable!("Commands serialize as externally tagged, got {}", other),
+    }
+}
+
+fn diff_fields(old: &ParsedCommand, new: &ParsedCommand) -> Vec<FieldChange> {
+    let (_, old_fields) = command_fields(old);
+    let (_, new_fields) = command_fields(new);
+    match (old_fields, new_fields) {
+        (Value::Object(old_map), Value::Object(new_map)) => old_map
+            .into_iter()
+            .filter_map(|(field, old_value)| {
+                let new_value = new_map.get(&field).cloned().unwrap_or(Value::Null);
+                if old_value == new_value {
+                    None
+                } else {
+                    Some(FieldChange {
+                        field,
+                        old: old_value,
+                        new: new_value,
+                    })
+                }
+            })
+            .collect(),
+        (old_value, new_value) if old_value != new_value => vec![FieldChange {
+            field: String::new(),
+            old: old_value,
+            new: new_value,
+        }],
+        _ => Vec::new(),
+    }
+}
+
+impl fmt::Display for RouteDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for change in &self.changes {
+            writeln!(f, "{}", change)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for RouteChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let position = self.position.iter().join(";");
+        match &self.change {
+            ChangeKind::Added { command } => {
+                let (name, fields) = command_fields(command);
+                write!(f, "+ {} at {}: {}", name, position, fields)
+            }
+            ChangeKind::Removed { command } => {
+                let (name, fields) = command_fields(command);
+                write!(f, "- {} at {}: {}", name, position, fields)
+            }
+            ChangeKind::Modified { old, fields, .. } => {
+                let (name, _) = command_fields(old);
+                write!(f, "~ {} at {} changed ", name, position)?;
+                for (idx, field) in fields.iter().enumerate() {
+                    if idx != 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{} from {} to {}", field.field, field.old, field.new)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::parse::FileAwareFileParser;
+    use async_std::{path::Path, task::block_on};
+    use indoc::indoc;
+
+    fn parse(input: &str) -> ParsedRoute {
+        block_on(ParsedRoute::file_aware_parse_from(
+            std::iter::empty::<&Path>(),
+            "route.csv",
+            input,
+        ))
+        .output
+    }
+
+    #[bve_derive::bve_test]
+    #[test]
+    fn formatting_ignored() {
+        let old = parse(indoc!(
+            r#"
+            With Track
+            1200, .Signal 3
+            1250, .Curve 300; 0
+            "#
+        ));
+        let new = parse(indoc!(
+            r#"
+            ; A comment
+            1200.0 , Track.SIGNAL(3)
+            1250 , track.curve 300;0
+            "#
+        ));
+        assert!(diff_routes(&old, &new).is_empty());
+    }
+
+    #[bve_derive::bve_test]
+    #[test]
+    fn modified_command() {
+        let old = parse("With Track\n1200, .Signal 3");
+        let new = parse("With Track\n1200, .Signal 4");
+        let diff = diff_routes(&old, &new);
+        assert_eq!(diff.changes.len(), 1);
+        match &diff.changes[0].change {
+            ChangeKind::Modified { fields, .. } => {
+                assert_eq!(fields, &[FieldChange {
+                    field: String::from("typ"),
+                    old: Value::from(3),
+                    new: Value::from(4),
+                }]);
+            }
+            other => panic!("Expected modification, got {:?}", other),
+        }
+        assert_eq!(
+            diff.changes[0].to_string(),
+            "~ TrackSignal at 1200 changed typ from 3 to 4"
+        );
+    }
+
+    #[bve_derive::bve_test]
+    #[test]
+    fn added_and_removed() {
+        let old = parse(indoc!(
+            r#"
+            With Track
+            100, .Curve 300; 0
+            200, .Height 1
+            "#
+        ));
+        let new = parse(indoc!(
+            r#"
+            With Track
+            100, .Curve 300; 0
+            .Pitch 5
+            300, .Height 1
+            "#
+        ));
+        let diff = diff_routes(&old, &new);
+        let kinds: Vec<_> = diff
+            .changes
+            .iter()
+            .map(|change| match &change.change {
+                ChangeKind::Added { .. } => ("added", change.position[0]),
+                ChangeKind::Removed { .. } => ("removed", change.position[0]),
+                ChangeKind::Modified { .. } => ("modified", change.position[0]),
+            })
+            .collect();
+        assert_eq!(kinds, vec![("added", 100.0), ("removed", 200.0), ("added", 300.0)]);
+    }
+
+    #[bve_derive::bve_test]
+    #[test]
+    fn json_output() {
+        let old = parse("With Track\n1200, .Signal 3");
+        let new = parse("With Track\n1200, .Signal 4");
+        let diff = diff_routes(&old, &new);
+        let json = serde_json::to_string(&diff).expect("Unable to serialize diff");
+        let deserialized: RouteDiff = serde_json::from_str(&json).expect("Unable to deserialize diff");
+        assert_eq!(diff, deserialized);
+    }
+}
@@ -6,7 +6,7 @@ use crate::{
 use rand::distributions::WeightedError;
 use smallvec::SmallVec;
 use smartstring::{LazyCompact, SmartString};
-use std::io;
+use std::{io, sync::Arc};
 
 #[derive(Debug)]
 pub enum RouteError {
@@ -39,7 +39,7 @@ impl From<PreprocessingError> for RouteError {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum PreprocessingError {
     /// Directive syntax is incorrect
     MalformedDirective { directive: SmartString<LazyCompact> },
@@ -52,7 +52,7 @@ pub enum PreprocessingError {
     /// Include file can't be read
     IncludeFileUnreadable {
         file: SmartString<LazyCompact>,
-        error: io::Error,
+        error: Arc<io::Error>,
     },
     /// Invalid random include.
     RandomIncludeError {
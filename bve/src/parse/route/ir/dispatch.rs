@@ -2,7 +2,7 @@ use super::*;
 use crate::parse::route::{
     errors::{CommandCreationError, RouteError},
     parser::Directive,
-    TrackPositionSmallVec,
+    RouteParseOptions, TrackPositionSmallVec,
 };
 use std::cell::RefCell;
 
@@ -22,6 +22,12 @@ where
 {
     current_namespace: Option<SmartString<LazyCompact>>,
     current_position: TrackPositionSmallVec,
+    strict: bool,
+    /// Errors from preprocessing, which strict mode doesn't stop on.
+    preprocessing_errors: usize,
+    /// Turned on by the options or by `Options.EnableBveTsHacks`. Unknown commands are silently ignored, like BVE-TS
+    /// does.
+    bve_ts_hacks: bool,
     errors: &'a RefCell<Vec<RouteError>>,
     instruction_stream: T,
 }
@@ -30,14 +36,21 @@ impl<'a, T> CommandParserIterator<'a, T>
 where
     T: Iterator<Item = Directive> + 'a,
 {
-    pub fn new(instruction_stream: T, errors: &'a RefCell<Vec<RouteError>>) -> Self {
+    pub fn new(instruction_stream: T, options: &RouteParseOptions, errors: &'a RefCell<Vec<RouteError>>) -> Self {
         Self {
             current_namespace: None,
             current_position: smallvec::smallvec![0.0],
+            strict: options.strict,
+            preprocessing_errors: errors.borrow().len(),
+            bve_ts_hacks: options.bve_ts_hacks,
             errors,
             instruction_stream,
         }
     }
+
+    fn halted(&self) -> bool {
+        self.strict && self.errors.borrow().len() > self.preprocessing_errors
+    }
 }
 
 impl<'a, T> Iterator for CommandParserIterator<'a, T>
@@ -48,6 +61,9 @@ where
 
     fn next(&mut self) -> Option<Self::Item> {
         while let Some(command) = self.instruction_stream.next() {
+            if self.halted() {
+                return None;
+            }
             match command {
                 Directive::TrackPosition(v) => {
                     self.current_position = v;
@@ -234,11 +250,18 @@ where
 
                     match parsed_command {
                         Ok(command) => {
+                            if let ParsedCommand::OptionsEnableBveTsHacks(OptionsEnableBveTsHacks {
+                                mode: OptionsEnableBveTsHacksMode::On,
+                            }) = command
+                            {
+                                self.bve_ts_hacks = true;
+                            }
                             return Some(ParsedDirective {
                                 command,
                                 position: self.current_position.clone(),
                             });
                         }
+                        Err(CommandCreationError::UnknownCommand { .. }) if self.bve_ts_hacks => {}
                         Err(err) => self.errors.borrow_mut().push(err.into()),
                     }
                 }
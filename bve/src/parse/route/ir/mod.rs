@@ -5,6 +5,7 @@ use crate::{
 };
 use bve_derive::FromRouteCommand;
 pub use dispatch::*;
+use serde::{Deserialize, Serialize};
 use smallvec::SmallVec;
 use smartstring::{LazyCompact, SmartString};
 pub use specials::*;
@@ -14,13 +15,13 @@ use std::{num::NonZeroU64, str::FromStr};
 mod specials;
 mod dispatch;
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct ParsedDirective {
     pub command: ParsedCommand,
     pub position: TrackPositionSmallVec,
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum ParsedCommand {
     OptionsUnitOfLength(OptionsUnitOfLength),
     OptionsUnitOfSpeed(OptionsUnitOfSpeed),
@@ -192,19 +193,19 @@ impl<'a> FromVariadicRouteArgument<'a> for ColorU8RGBA {
     }
 }
 
-#[derive(Debug, Clone, PartialEq, FromRouteCommand)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, FromRouteCommand)]
 pub struct OptionsUnitOfLength {
     #[command(variadic, default = "SmallVec::from_slice(&[1.0])")]
     pub factors: SmallVec<[f32; 2]>,
 }
 
-#[derive(Debug, Clone, PartialEq, FromRouteCommand)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, FromRouteCommand)]
 pub struct OptionsUnitOfSpeed {
     #[command(default = "1.0")]
     pub factor: f32,
 }
 
-#[derive(Debug, Clone, PartialEq, FromRouteCommand)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, FromRouteCommand)]
 pub struct OptionsBlockLength {
     /// unit: UnitOfLength
     #[command(default = "25.0")]
@@ -212,58 +213,58 @@ pub struct OptionsBlockLength {
 }
 
 flag_enum!(OptionsObjectVisibilityMode, u8, Legacy = 0, TrackBased = 1);
-#[derive(Debug, Clone, PartialEq, FromRouteCommand)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, FromRouteCommand)]
 pub struct OptionsObjectVisibility {
     #[command(default = "OptionsObjectVisibilityMode::Legacy")]
     pub mode: OptionsObjectVisibilityMode,
 }
 
 flag_enum!(OptionsSectionBehaviorMode, u8, Default = 0, Simplified = 1);
-#[derive(Debug, Clone, PartialEq, FromRouteCommand)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, FromRouteCommand)]
 pub struct OptionsSectionBehavior {
     #[command(default = "OptionsSectionBehaviorMode::Default")]
     pub mode: OptionsSectionBehaviorMode,
 }
 
 flag_enum!(OptionsCantBehaviorMode, u8, Unsigned = 0, Signed = 1);
-#[derive(Debug, Clone, PartialEq, FromRouteCommand)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, FromRouteCommand)]
 pub struct OptionsCantBehavior {
     #[command(default = "OptionsCantBehaviorMode::Unsigned")]
     pub mode: OptionsCantBehaviorMode,
 }
 
 flag_enum!(OptionsFogBehaviorMode, u8, BlockBased = 0, Interpolated = 1);
-#[derive(Debug, Clone, PartialEq, FromRouteCommand)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, FromRouteCommand)]
 pub struct OptionsFogBehavior {
     #[command(default = "OptionsFogBehaviorMode::BlockBased")]
     pub mode: OptionsFogBehaviorMode,
 }
 
 flag_enum!(OptionsCompatibleTransparencyMode, u8, Off = 0, On = 1);
-#[derive(Debug, Clone, PartialEq, FromRouteCommand)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, FromRouteCommand)]
 pub struct OptionsCompatibleTransparency {
     #[command(default = "OptionsCompatibleTransparencyMode::Off")]
     pub mode: OptionsCompatibleTransparencyMode,
 }
 
 flag_enum!(OptionsEnableBveTsHacksMode, u8, Off = 0, On = 1);
-#[derive(Debug, Clone, PartialEq, FromRouteCommand)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, FromRouteCommand)]
 pub struct OptionsEnableBveTsHacks {
     #[command(default = "OptionsEnableBveTsHacksMode::Off")]
     pub mode: OptionsEnableBveTsHacksMode,
 }
 
-#[derive(Debug, Clone, PartialEq, FromRouteCommand)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, FromRouteCommand)]
 pub struct RouteComment {
     pub comment: SmartString<LazyCompact>,
 }
 
-#[derive(Debug, Clone, PartialEq, FromRouteCommand)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, FromRouteCommand)]
 pub struct RouteImage {
     pub file: SmartString<LazyCompact>,
 }
 
-#[derive(Debug, Clone, PartialEq, FromRouteCommand)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, FromRouteCommand)]
 pub struct RouteTimetable {
     pub text: SmartString<LazyCompact>,
 }
@@ -275,20 +276,20 @@ flag_enum!(
     ActiveEmergency = 0,
     InactiveEmergency = 1
 );
-#[derive(Debug, Clone, PartialEq, FromRouteCommand)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, FromRouteCommand)]
 pub struct RouteChange {
     #[command(default = "RouteChangeSafetyMode::ActiveEmergency")]
     pub text: RouteChangeSafetyMode,
 }
 
-#[derive(Debug, Clone, PartialEq, FromRouteCommand)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, FromRouteCommand)]
 pub struct RouteGauge {
     /// unit: mm
     #[command(default = "1435.0")]
     pub gauge: f32,
 }
 
-#[derive(Debug, Clone, PartialEq, FromRouteCommand)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, FromRouteCommand)]
 pub struct RouteSignal {
     #[command(index)]
     pub aspect_index: u8,
@@ -296,42 +297,42 @@ pub struct RouteSignal {
     pub speed: f32,
 }
 
-#[derive(Debug, Clone, PartialEq, FromRouteCommand)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, FromRouteCommand)]
 pub struct RouteRunInterval {
     /// unit: s
     #[command(variadic)]
     pub intervals: SmallVec<[f32; 4]>,
 }
 
-#[derive(Debug, Clone, PartialEq, FromRouteCommand)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, FromRouteCommand)]
 pub struct RouteAccelerationDueToGravity {
     /// unit: m/s^2
     #[command(default = "9.80665")]
     pub gravity: f32,
 }
 
-#[derive(Debug, Clone, PartialEq, FromRouteCommand)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, FromRouteCommand)]
 pub struct RouteElevation {
     /// unit: UnitOfLength
     #[command(default = "0.0")]
     pub height: f32,
 }
 
-#[derive(Debug, Clone, PartialEq, FromRouteCommand)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, FromRouteCommand)]
 pub struct RouteTemperature {
     /// unit: celsius
     #[command(default = "20.0")]
     pub temperature: f32,
 }
 
-#[derive(Debug, Clone, PartialEq, FromRouteCommand)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, FromRouteCommand)]
 pub struct RoutePressure {
     /// unit: kPa
     #[command(default = "101.325")]
     pub pressure: f32,
 }
 
-#[derive(Debug, Clone, PartialEq, FromRouteCommand)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, FromRouteCommand)]
 pub struct RouteDisplaySpeed {
     #[command(default = "SmartString::default()")]
     pub unit: SmartString<LazyCompact>,
@@ -339,34 +340,34 @@ pub struct RouteDisplaySpeed {
     pub factor: f32,
 }
 
-#[derive(Debug, Clone, PartialEq, FromRouteCommand)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, FromRouteCommand)]
 pub struct RouteLoadingScreen {
     pub image: SmartString<LazyCompact>,
 }
 
-#[derive(Debug, Clone, PartialEq, FromRouteCommand)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, FromRouteCommand)]
 pub struct RouteStartTime {
     pub time: Time,
 }
 
-#[derive(Debug, Clone, PartialEq, FromRouteCommand)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, FromRouteCommand)]
 pub struct RouteDynamicLight {
     pub path: SmartString<LazyCompact>,
 }
 
-#[derive(Debug, Clone, PartialEq, FromRouteCommand)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, FromRouteCommand)]
 pub struct RouteAmbientLight {
     #[command(variadic, default = "ColorU8RGB::new(160, 160, 160)")]
     pub color: ColorU8RGB,
 }
 
-#[derive(Debug, Clone, PartialEq, FromRouteCommand)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, FromRouteCommand)]
 pub struct RouteDirectionalLight {
     #[command(variadic, default = "ColorU8RGB::new(160, 160, 160)")]
     pub color: ColorU8RGB,
 }
 
-#[derive(Debug, Clone, PartialEq, FromRouteCommand)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, FromRouteCommand)]
 pub struct RouteLightDirection {
     #[command(default = "60.0")]
     pub theta: f32,
@@ -382,37 +383,37 @@ flag_enum!(
     FlybyCamera = 2,
     FlybyZoomingCamera = 3
 );
-#[derive(Debug, Clone, PartialEq, FromRouteCommand)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, FromRouteCommand)]
 pub struct RouteInitialViewpoint {
     #[command(default = "RouteInitialViewpointMode::Cab")]
     pub view: RouteInitialViewpointMode,
 }
 
-#[derive(Debug, Clone, PartialEq, FromRouteCommand)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, FromRouteCommand)]
 pub struct RouteDeveloperId {
     pub id: SmartString<LazyCompact>,
 }
 
-#[derive(Debug, Clone, PartialEq, FromRouteCommand)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, FromRouteCommand)]
 pub struct TrainFolder {
     pub folder: SmartString<LazyCompact>,
 }
 
-#[derive(Debug, Clone, PartialEq, FromRouteCommand)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, FromRouteCommand)]
 pub struct TrainRail {
     #[command(index)]
     pub rail_type_index: u64,
     pub run_sound_index: u64,
 }
 
-#[derive(Debug, Clone, PartialEq, FromRouteCommand)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, FromRouteCommand)]
 pub struct TrainFlange {
     #[command(index)]
     pub rail_type_index: u64,
     pub flange_sound_index: u64,
 }
 
-#[derive(Debug, Clone, PartialEq, FromRouteCommand)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, FromRouteCommand)]
 pub struct TrainTimetable {
     #[command(index)]
     pub timetable_index: u64,
@@ -421,14 +422,14 @@ pub struct TrainTimetable {
     pub filename: SmartString<LazyCompact>,
 }
 
-#[derive(Debug, Clone, PartialEq, FromRouteCommand)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, FromRouteCommand)]
 pub struct TrainVelocity {
     #[command(default = "0.0")]
     /// unit: UnitOfSpeed
     pub max_ai_speed: f32,
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum StructureCommandKind {
     Ground,
     Rail,
@@ -449,7 +450,7 @@ pub enum StructureCommandKind {
     FreeObj,
     Beacon,
 }
-#[derive(Debug, Clone, PartialEq, FromRouteCommand)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, FromRouteCommand)]
 pub struct StructureCommand {
     #[command(ignore)]
     pub command: Option<StructureCommandKind>,
@@ -458,7 +459,7 @@ pub struct StructureCommand {
     pub filename: SmartString<LazyCompact>,
 }
 
-#[derive(Debug, Clone, PartialEq, FromRouteCommand)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, FromRouteCommand)]
 pub struct StructurePole {
     #[command(index)]
     pub number_of_additional_rails: u64,
@@ -467,14 +468,14 @@ pub struct StructurePole {
     pub file_name: SmartString<LazyCompact>,
 }
 
-#[derive(Debug, Clone, PartialEq, FromRouteCommand)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, FromRouteCommand)]
 pub struct TextureBackgroundLoad {
     #[command(index)]
     pub background_texture_index: u64,
     pub file_name: SmartString<LazyCompact>,
 }
 
-#[derive(Debug, Clone, PartialEq, FromRouteCommand)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, FromRouteCommand)]
 pub struct TextureBackgroundX {
     #[command(index)]
     pub background_texture_index: u64,
@@ -483,7 +484,7 @@ pub struct TextureBackgroundX {
 }
 
 flag_enum!(TextureBackgroundAspectMode, u8, Fixed = 0, Aspect = 1);
-#[derive(Debug, Clone, PartialEq, FromRouteCommand)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, FromRouteCommand)]
 pub struct TextureBackgroundAspect {
     #[command(index)]
     pub background_texture_index: u64,
@@ -491,7 +492,7 @@ pub struct TextureBackgroundAspect {
     pub mode: TextureBackgroundAspectMode,
 }
 
-#[derive(Debug, Clone, PartialEq, FromRouteCommand)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, FromRouteCommand)]
 pub struct CycleGround {
     #[command(index)]
     pub ground_structure_index: u64,
@@ -499,7 +500,7 @@ pub struct CycleGround {
     pub ground_structures: SmallVec<[u64; 8]>,
 }
 
-#[derive(Debug, Clone, PartialEq, FromRouteCommand)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, FromRouteCommand)]
 pub struct CycleRail {
     #[command(index)]
     pub rail_structure_index: u64,
@@ -507,14 +508,14 @@ pub struct CycleRail {
     pub rail_structures: SmallVec<[u64; 8]>,
 }
 
-#[derive(Debug, Clone, PartialEq, FromRouteCommand)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, FromRouteCommand)]
 pub struct SignalSingle {
     #[command(index)]
     pub signal_index: u64,
     pub signal_file: SmartString<LazyCompact>,
 }
 
-#[derive(Debug, Clone, PartialEq, FromRouteCommand)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, FromRouteCommand)]
 pub struct SignalSplit {
     #[command(index)]
     pub signal_index: u64,
@@ -523,7 +524,7 @@ pub struct SignalSplit {
     pub glow_file: SmartString<LazyCompact>,
 }
 
-#[derive(Debug, Clone, PartialEq, FromRouteCommand)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, FromRouteCommand)]
 pub struct TrackRailStart {
     pub rail_index: NonZeroU64,
     /// unit: UnitOfDistance
@@ -536,7 +537,7 @@ pub struct TrackRailStart {
     pub rail_type: Option<u64>,
 }
 
-#[derive(Debug, Clone, PartialEq, FromRouteCommand)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, FromRouteCommand)]
 pub struct TrackRail {
     pub rail_index: NonZeroU64,
     /// unit: UnitOfDistance
@@ -549,7 +550,7 @@ pub struct TrackRail {
     pub rail_type: Option<u64>,
 }
 
-#[derive(Debug, Clone, PartialEq, FromRouteCommand)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, FromRouteCommand)]
 pub struct TrackRailType {
     #[command(default = "0")]
     pub rail_index: u64,
@@ -557,7 +558,7 @@ pub struct TrackRailType {
     pub rail_type: u64,
 }
 
-#[derive(Debug, Clone, PartialEq, FromRouteCommand)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, FromRouteCommand)]
 pub struct TrackRailEnd {
     pub rail_index: NonZeroU64,
     /// unit: UnitOfDistance
@@ -568,25 +569,25 @@ pub struct TrackRailEnd {
     pub y_offset: f32,
 }
 
-#[derive(Debug, Clone, PartialEq, FromRouteCommand)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, FromRouteCommand)]
 pub struct TrackAccuracy {
     #[command(default = "2.0")]
     pub accuracy: f32,
 }
 
-#[derive(Debug, Clone, PartialEq, FromRouteCommand)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, FromRouteCommand)]
 pub struct TrackAdhesion {
     #[command(default = "100.0")]
     pub accuracy: f32,
 }
 
-#[derive(Debug, Clone, PartialEq, FromRouteCommand)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, FromRouteCommand)]
 pub struct TrackPitch {
     #[command(default = "0.0")]
     pub accuracy: f32,
 }
 
-#[derive(Debug, Clone, PartialEq, FromRouteCommand)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, FromRouteCommand)]
 pub struct TrackCurve {
     /// unit: UnitOfDistance
     #[command(default = "0.0")]
@@ -596,20 +597,20 @@ pub struct TrackCurve {
     pub cant: f32,
 }
 
-#[derive(Debug, Clone, PartialEq, FromRouteCommand)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, FromRouteCommand)]
 pub struct TrackTurn {
     #[command(default = "0.0")]
     pub turn: f32,
 }
 
-#[derive(Debug, Clone, PartialEq, FromRouteCommand)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, FromRouteCommand)]
 pub struct TrackHeight {
     /// unit: UnitOfDistance
     #[command(default = "0.0")]
     pub height: f32,
 }
 
-#[derive(Debug, Clone, PartialEq, FromRouteCommand)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, FromRouteCommand)]
 pub struct TrackFreeObj {
     #[command(default = "0")]
     pub rail_index: u64,
@@ -634,7 +635,7 @@ pub struct TrackFreeObj {
 
 flag_enum!(StructureDirection, i8, Left = -1, Both = 0, Right = 1);
 
-#[derive(Debug, Clone, PartialEq, FromRouteCommand)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, FromRouteCommand)]
 pub struct TrackWall {
     #[command(default = "0")]
     pub rail_index: u64,
@@ -644,13 +645,13 @@ pub struct TrackWall {
     pub structure_index: u64,
 }
 
-#[derive(Debug, Clone, PartialEq, FromRouteCommand)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, FromRouteCommand)]
 pub struct TrackWallEnd {
     #[command(default = "0")]
     pub rail_index: u64,
 }
 
-#[derive(Debug, Clone, PartialEq, FromRouteCommand)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, FromRouteCommand)]
 pub struct TrackDike {
     #[command(default = "0")]
     pub rail_index: u64,
@@ -660,13 +661,13 @@ pub struct TrackDike {
     pub structure_index: u64,
 }
 
-#[derive(Debug, Clone, PartialEq, FromRouteCommand)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, FromRouteCommand)]
 pub struct TrackDikeEnd {
     #[command(default = "0")]
     pub rail_index: u64,
 }
 
-#[derive(Debug, Clone, PartialEq, FromRouteCommand)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, FromRouteCommand)]
 pub struct TrackPole {
     #[command(default = "0")]
     pub rail_index: u64,
@@ -680,13 +681,13 @@ pub struct TrackPole {
     pub structure_index: u64,
 }
 
-#[derive(Debug, Clone, PartialEq, FromRouteCommand)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, FromRouteCommand)]
 pub struct TrackPoleEnd {
     #[command(default = "0")]
     pub rail_index: u64,
 }
 
-#[derive(Debug, Clone, PartialEq, FromRouteCommand)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, FromRouteCommand)]
 pub struct TrackCrack {
     #[command(default = "0")]
     pub rail_index1: u64,
@@ -696,7 +697,7 @@ pub struct TrackCrack {
     pub structure_index: u64,
 }
 
-#[derive(Debug, Clone, PartialEq, FromRouteCommand)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, FromRouteCommand)]
 pub struct TrackGround {
     #[command(default = "0")]
     pub cycle_index: u64,
@@ -704,7 +705,7 @@ pub struct TrackGround {
 
 flag_enum!(PassAlarmMode, u8, Silent = 0, Enabled = 1);
 flag_enum!(ForcedRedSingleMode, u8, Unaffected = 0, Enabled = 1);
-#[derive(Debug, Clone, PartialEq, FromRouteCommand)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, FromRouteCommand)]
 pub struct TrackSta {
     #[command(default = "SmartString::new()")]
     pub name: SmartString<LazyCompact>,
@@ -732,7 +733,7 @@ pub struct TrackSta {
     pub timetable_index: Option<u64>,
 }
 
-#[derive(Debug, Clone, PartialEq, FromRouteCommand)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, FromRouteCommand)]
 pub struct TrackStation {
     #[command(default = "SmartString::new()")]
     pub name: SmartString<LazyCompact>,
@@ -766,7 +767,7 @@ impl From<TrackStation> for TrackSta {
 }
 
 flag_enum!(SignPostDirection, i8, Left = -1, None = 0, Right = 1);
-#[derive(Debug, Clone, PartialEq, FromRouteCommand)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, FromRouteCommand)]
 pub struct TrackStop {
     #[command(default = "SignPostDirection::None")]
     pub direction: SignPostDirection,
@@ -780,7 +781,7 @@ pub struct TrackStop {
     pub cars: u64,
 }
 
-#[derive(Debug, Clone, PartialEq, FromRouteCommand)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, FromRouteCommand)]
 pub struct TrackForm {
     pub rail_index1: u64,
     pub rail_index2: FormRailIndex2Data,
@@ -792,7 +793,7 @@ pub struct TrackForm {
 
 flag_enum!(TrackLimitPostDirection, i8, Left = -1, None = 0, Right = 1);
 flag_enum!(TrackLimitCourceDirection, i8, Left = -1, None = 0, Right = 1);
-#[derive(Debug, Clone, PartialEq, FromRouteCommand)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, FromRouteCommand)]
 pub struct TrackLimit {
     /// unit: UnitOfSpeed
     #[command(default = "0.0")]
@@ -803,13 +804,13 @@ pub struct TrackLimit {
     pub cource: TrackLimitCourceDirection,
 }
 
-#[derive(Debug, Clone, PartialEq, FromRouteCommand)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, FromRouteCommand)]
 pub struct TrackSection {
     #[command(variadic)]
     pub sections: SmallVec<[u64; 4]>,
 }
 
-#[derive(Debug, Clone, PartialEq, FromRouteCommand)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, FromRouteCommand)]
 pub struct TrackSigF {
     pub signal_index: u64,
     pub section: u64,
@@ -830,7 +831,7 @@ pub struct TrackSigF {
     pub roll: f32,
 }
 
-#[derive(Debug, Clone, PartialEq, FromRouteCommand)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, FromRouteCommand)]
 pub struct TrackSignal {
     #[command(default = "-2")]
     pub typ: i64,
@@ -853,7 +854,7 @@ pub struct TrackSignal {
     pub roll: f32,
 }
 
-#[derive(Debug, Clone, PartialEq, FromRouteCommand)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, FromRouteCommand)]
 pub struct TrackRelay {
     /// unit: UnitOfLength
     #[command(default = "0.0")]
@@ -872,7 +873,7 @@ pub struct TrackRelay {
     pub roll: f32,
 }
 
-#[derive(Debug, Clone, PartialEq, FromRouteCommand)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, FromRouteCommand)]
 pub struct TrackBeacon {
     pub typ: u64,
     pub structure_index: i64,
@@ -907,7 +908,7 @@ flag_enum!(
     AtsPImmediateStop = 4
 );
 flag_enum!(TrackTransponderSwitchSystem, i8, DoNothing = -1, Switch = 0);
-#[derive(Debug, Clone, PartialEq, FromRouteCommand)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, FromRouteCommand)]
 pub struct TrackTransponder {
     #[command(default = "TrackTransponderType::SType")]
     pub typ: TrackTransponderType,
@@ -933,14 +934,14 @@ pub struct TrackTransponder {
 }
 
 flag_enum!(TrackPatternType, u8, Temporary = 0, Permanent = 1);
-#[derive(Debug, Clone, PartialEq, FromRouteCommand)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, FromRouteCommand)]
 pub struct TrackPattern {
     pub typ: TrackPatternType,
     /// unit: UnitOfSpeed
     pub speed: f32,
 }
 
-#[derive(Debug, Clone, PartialEq, FromRouteCommand)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, FromRouteCommand)]
 pub struct TrackPLimit {
     /// unit: UnitOfSpeed
     pub speed: f32,
@@ -955,12 +956,12 @@ impl From<TrackPLimit> for TrackPattern {
     }
 }
 
-#[derive(Debug, Clone, PartialEq, FromRouteCommand)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, FromRouteCommand)]
 pub struct TrackBack {
     pub background_texture_index: u64,
 }
 
-#[derive(Debug, Clone, PartialEq, FromRouteCommand)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, FromRouteCommand)]
 pub struct TrackFog {
     /// unit: UnitOfLength
     #[command(default = "0.0")]
@@ -976,25 +977,25 @@ pub struct TrackFog {
     pub blue: u8,
 }
 
-#[derive(Debug, Clone, PartialEq, FromRouteCommand)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, FromRouteCommand)]
 pub struct TrackBrightness {
     #[command(default = "255")]
     pub brightness: u8,
 }
 
-#[derive(Debug, Clone, PartialEq, FromRouteCommand)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, FromRouteCommand)]
 pub struct TrackMarker {
     pub filename: SmartString<LazyCompact>,
     #[command(default = "0.0")]
     pub display_distance: f32,
 }
 
-#[derive(Debug, Clone, PartialEq, FromRouteCommand)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, FromRouteCommand)]
 pub struct TrackMarkerXml {
     pub filename: SmartString<LazyCompact>,
 }
 
-#[derive(Debug, Clone, PartialEq, FromRouteCommand)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, FromRouteCommand)]
 pub struct TrackTextMarker {
     pub text: SmartString<LazyCompact>,
     #[command(default = "0.0")]
@@ -1002,7 +1003,7 @@ pub struct TrackTextMarker {
     pub color: TextMarkerColor,
 }
 
-#[derive(Debug, Clone, PartialEq, FromRouteCommand)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, FromRouteCommand)]
 pub struct TrackPointOfInterest {
     pub rail_index: u64,
     /// unit: UnitOfLength
@@ -1023,12 +1024,12 @@ pub struct TrackPointOfInterest {
     pub text: SmartString<LazyCompact>,
 }
 
-#[derive(Debug, Clone, PartialEq, FromRouteCommand)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, FromRouteCommand)]
 pub struct TrackPreTrain {
     pub time: Time,
 }
 
-#[derive(Debug, Clone, PartialEq, FromRouteCommand)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, FromRouteCommand)]
 pub struct TrackAnnounce {
     pub filename: SmartString<LazyCompact>,
     /// unit: UnitOfSpeed
@@ -1036,7 +1037,7 @@ pub struct TrackAnnounce {
     pub speed: f32,
 }
 
-#[derive(Debug, Clone, PartialEq, FromRouteCommand)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, FromRouteCommand)]
 pub struct TrackDoppler {
     pub filename: SmartString<LazyCompact>,
     /// unit: UnitOfLength
@@ -1047,12 +1048,12 @@ pub struct TrackDoppler {
     pub y_offset: f32,
 }
 
-#[derive(Debug, Clone, PartialEq, FromRouteCommand)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, FromRouteCommand)]
 pub struct TrackBuffer;
 
 flag_enum!(TrackDestinationType, i8, AiOnly = -1, All = 0, PlayerOnly = 1);
 flag_enum!(TrackDestinationTriggerOnce, u8, All = 0, Once = 1);
-#[derive(Debug, Clone, PartialEq, FromRouteCommand)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, FromRouteCommand)]
 pub struct TrackDestination {
     pub typ: TrackDestinationType,
     pub beacon_structure_index: i64,
@@ -1,10 +1,11 @@
 use crate::Time;
+use serde::{Deserialize, Serialize};
 use smartstring::{LazyCompact, SmartString};
 use std::str::FromStr;
 
 macro_rules! flag_enum {
     ($name:ident, $ty:ty, $($variant:ident = $num:expr),*) => {
-        #[derive(Debug, Clone, PartialEq)]
+        #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
         pub enum $name {
             $($variant = $num,)*
         }
@@ -21,7 +22,7 @@ macro_rules! flag_enum {
     };
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum TimetableSuffix {
     Day,
     Night,
@@ -38,7 +39,7 @@ impl FromStr for TimetableSuffix {
     }
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum ArrivalTimeState {
     Player(Option<Time>),
     AiStop,
@@ -65,7 +66,7 @@ impl FromStr for ArrivalTimeState {
     }
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum DepartureTimeState {
     Regular(Option<Time>),
     Terminal(Option<Time>),
@@ -127,7 +128,7 @@ impl FromStr for DepartureTimeState {
     }
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum StationDoorMode {
     Left = -1,
     None = 0,
@@ -149,7 +150,7 @@ impl FromStr for StationDoorMode {
     }
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum SystemAtsMode {
     ATS = 0,
     ATC = 1,
@@ -167,7 +168,7 @@ impl FromStr for SystemAtsMode {
     }
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum FormRailIndex2Data {
     Current(u64),
     Left,
@@ -186,7 +187,7 @@ impl FromStr for FormRailIndex2Data {
     }
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum TextMarkerColor {
     Black,
     Gray,
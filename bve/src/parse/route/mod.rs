@@ -4,10 +4,13 @@ use crate::{
 };
 use async_std::path::Path;
 use async_trait::async_trait;
+use encoding_rs::Encoding;
 use rand::SeedableRng;
+use serde::{Deserialize, Serialize};
 use smallvec::SmallVec;
-use std::{cell::RefCell, io};
+use std::{cell::RefCell, io, sync::Arc};
 
+pub mod diff;
 pub mod errors;
 pub mod ir;
 pub mod parser;
@@ -15,33 +18,63 @@ pub mod preprocessor;
 
 pub type TrackPositionSmallVec = SmallVec<[f32; 4]>;
 
-#[derive(Debug)]
-pub struct ParsedRoute(Vec<ir::ParsedDirective>);
+/// Controls how a route is parsed. The defaults match how OpenBVE loads a route.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RouteParseOptions {
+    /// Seed for `$Rnd` and random `$Include` choices.
+    pub rng_seed: u64,
+    /// Load `$Include`d files. If false, includes are removed without being loaded.
+    pub follow_includes: bool,
+    /// Encoding of included files. If `None`, it is detected for each file.
+    pub encoding: Option<&'static Encoding>,
+    /// Stop producing directives after the first parse error. Preprocessing errors, such as a missing `$Include`, are
+    /// still reported but don't stop parsing.
+    pub strict: bool,
+    /// Enable BVE-TS compatibility hacks even if the route doesn't use `Options.EnableBveTsHacks`.
+    pub bve_ts_hacks: bool,
+}
 
-impl PrettyPrintResult for ParsedRoute {
-    fn fmt(&self, _indent: usize, out: &mut dyn io::Write) -> io::Result<()> {
-        write!(out, "{:#?}", self)
+impl Default for RouteParseOptions {
+    fn default() -> Self {
+        Self {
+            rng_seed: 42,
+            follow_includes: true,
+            encoding: None,
+            strict: false,
+            bve_ts_hacks: false,
+        }
     }
 }
 
-#[async_trait(?Send)]
-impl FileAwareFileParser for ParsedRoute {
-    type Output = Self;
-    type Warnings = ();
-    type Errors = errors::RouteError;
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ParsedRoute(Vec<ir::ParsedDirective>);
 
-    async fn file_aware_parse_from<'a, IntoIter, AsRefPath>(
+impl ParsedRoute {
+    /// All directives in the route, in the order they appear in the file.
+    #[must_use]
+    pub fn directives(&self) -> &[ir::ParsedDirective] {
+        &self.0
+    }
+
+    /// Same as [`FileAwareFileParser::file_aware_parse_from`], but with explicit options. Also reports how
+    /// `$Include`s were resolved.
+    pub async fn parse_with_options<'a, IntoIter, AsRefPath>(
         resolve_bases: IntoIter,
         current_path: &str,
         input: &str,
-    ) -> ParserResult<Self::Output, Self::Warnings, Self::Errors>
+        options: &RouteParseOptions,
+    ) -> (
+        ParserResult<Self, (), errors::RouteError>,
+        preprocessor::IncludeStatistics,
+    )
     where
         IntoIter: IntoIterator<Item = &'a AsRefPath> + Clone + 'a,
         AsRefPath: AsRef<Path> + ?Sized + 'a,
     {
-        let mut rng = rand::rngs::StdRng::seed_from_u64(42);
+        let mut rng = rand::rngs::StdRng::seed_from_u64(options.rng_seed);
         let resolve_bases_ref = &resolve_bases;
-        let file_func = |input: preprocessor::FileInput| async move {
+        let encoding = options.encoding;
+        let resolve_func = |input: preprocessor::FileInput| async move {
             let current_dir = Path::new(&input.base_path).parent().expect("Path has no parent");
             try {
                 let requested_path = &*input.requested_path;
@@ -58,26 +91,198 @@ impl FileAwareFileParser for ParsedRoute {
                 .ok_or_else(|| errors::PreprocessingError::IncludeFileNotFound {
                     file: requested_path.into(),
                 })?;
-                let contents = async_std::fs::read_to_string(&file).await.map_err(|error| {
-                    errors::PreprocessingError::IncludeFileUnreadable {
-                        file: requested_path.into(),
-                        error,
-                    }
-                })?;
-                preprocessor::FileOutput {
-                    path: file.to_string_lossy().to_string(),
-                    contents,
-                }
+                file.to_string_lossy().to_string()
+            }
+        };
+        let file_func = |path: String| async move {
+            try {
+                let contents = filesystem::read_convert_utf8_with(&path, encoding)
+                    .await
+                    .map_err(|error| errors::PreprocessingError::IncludeFileUnreadable {
+                        file: path[..].into(),
+                        error: Arc::new(error),
+                    })?;
+                preprocessor::FileOutput { path, contents }
             }
         };
-        let (preprocessed, errors) = preprocessor::preprocess_route(current_path, input, &mut rng, file_func).await;
+        let (preprocessed, errors, statistics) =
+            preprocessor::preprocess_route(current_path, input, &mut rng, options, resolve_func, file_func).await;
         let error_refcell = RefCell::new(errors);
-        let parsed = parser::parse_route(&preprocessed, &error_refcell);
-        let commands = ir::CommandParserIterator::new(parsed, &error_refcell);
-        ParserResult {
-            output: ParsedRoute(commands.collect()),
+        let parsed = parser::parse_route(&preprocessed, options, &error_refcell);
+        let commands = ir::CommandParserIterator::new(parsed, options, &error_refcell);
+        let result = ParserResult {
+            output: Self(commands.collect()),
             warnings: Vec::new(),
             errors: error_refcell.into_inner(),
-        }
+        };
+        (result, statistics)
+    }
+}
+
+impl PrettyPrintResult for ParsedRoute {
+    fn fmt(&self, _indent: usize, out: &mut dyn io::Write) -> io::Result<()> {
+        write!(out, "{:#?}", self)
+    }
+}
+
+#[async_trait(?Send)]
+impl FileAwareFileParser for ParsedRoute {
+    type Output = Self;
+    type Warnings = ();
+    type Errors = errors::RouteError;
+
+    async fn file_aware_parse_from<'a, IntoIter, AsRefPath>(
+        resolve_bases: IntoIter,
+        current_path: &str,
+        input: &str,
+    ) -> ParserResult<Self::Output, Self::Warnings, Self::Errors>
+    where
+        IntoIter: IntoIterator<Item = &'a AsRefPath> + Clone + 'a,
+        AsRefPath: AsRef<Path> + ?Sized + 'a,
+    {
+        Self::parse_with_options(resolve_bases, current_path, input, &RouteParseOptions::default())
+            .await
+            .0
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use async_std::task::block_on;
+    use indoc::indoc;
+
+    fn parse(input: &str) -> ParsedRoute {
+        block_on(ParsedRoute::file_aware_parse_from(
+            std::iter::empty::<&Path>(),
+            "route.csv",
+            input,
+        ))
+        .output
+    }
+
+    fn parse_with(input: &str, options: &RouteParseOptions) -> ParserResult<ParsedRoute, (), errors::RouteError> {
+        block_on(ParsedRoute::parse_with_options(
+            std::iter::empty::<&Path>(),
+            "route.csv",
+            input,
+            options,
+        ))
+        .0
+    }
+
+    #[bve_derive::bve_test]
+    #[test]
+    fn json_snapshot() {
+        let route = parse("With Track\n100, .Curve 300; 10");
+        assert_eq!(
+            serde_json::to_string(&route).expect("Unable to serialize route"),
+            r#"[{"command":{"TrackCurve":{"curve":300.0,"cant":10.0}},"position":[100.0]}]"#
+        );
+    }
+
+    #[bve_derive::bve_test]
+    #[test]
+    fn json_round_trip() {
+        let route = parse(indoc!(
+            r#"
+            With Route
+            .Comment Hello World
+            .StartTime 10.30
+            .AmbientLight 100; 120; 140
+            With Track
+            0, .RailStart 1; 3.5; 0; 2
+            .Sta Station A; 10.3000; 10.3100; 0; B
+            .Form 1; L
+            50;2, .PreTrain 10.30
+            "#
+        ));
+        assert_eq!(route.directives().len(), 7);
+
+        let json = serde_json::to_string(&route).expect("Unable to serialize route");
+        let deserialized: ParsedRoute = serde_json::from_str(&json).expect("Unable to deserialize route");
+        assert_eq!(route, deserialized);
+    }
+
+    #[bve_derive::bve_test]
+    #[test]
+    fn strict() {
+        let input = indoc!(
+            r#"
+            With Track
+            100, .Curve 300; 0
+            .Unknown
+            200, .Curve 400; 0
+            "#
+        );
+
+        let lenient = parse_with(input, &RouteParseOptions::default());
+        assert_eq!(lenient.output.directives().len(), 2);
+        assert_eq!(lenient.errors.len(), 1);
+
+        let strict = parse_with(input, &RouteParseOptions {
+            strict: true,
+            ..RouteParseOptions::default()
+        });
+        assert_eq!(strict.output.directives().len(), 1);
+        assert_eq!(strict.errors.len(), 1);
+    }
+
+    #[bve_derive::bve_test]
+    #[test]
+    fn strict_continues_after_preprocessing_errors() {
+        let input = indoc!(
+            r#"
+            $Include(missing.include)
+            With Track
+            100, .Curve 300; 0
+            200, .Curve 400; 0
+            "#
+        );
+
+        let strict = parse_with(input, &RouteParseOptions {
+            strict: true,
+            ..RouteParseOptions::default()
+        });
+        assert_eq!(strict.output.directives().len(), 2);
+        assert_eq!(strict.errors.len(), 1);
+    }
+
+    #[bve_derive::bve_test]
+    #[test]
+    fn bve_ts_hacks() {
+        let unknown = "With Track\n.Unknown\n100, .Curve 300; 0";
+
+        let result = parse_with(unknown, &RouteParseOptions::default());
+        assert_eq!(result.errors.len(), 1);
+
+        let result = parse_with(unknown, &RouteParseOptions {
+            bve_ts_hacks: true,
+            ..RouteParseOptions::default()
+        });
+        assert!(result.errors.is_empty(), "{:?}", result.errors);
+        assert_eq!(result.output.directives().len(), 1);
+
+        let result = parse_with(
+            &format!("Options.EnableBveTsHacks 1\n{}", unknown),
+            &RouteParseOptions::default(),
+        );
+        assert!(result.errors.is_empty(), "{:?}", result.errors);
+        assert_eq!(result.output.directives().len(), 2);
+    }
+
+    #[bve_derive::bve_test]
+    #[test]
+    fn rng_seed() {
+        let input = "With Track\n100, .Height $Rnd(1; 1000000)";
+        let first = parse_with(input, &RouteParseOptions::default()).output;
+        let same = parse_with(input, &RouteParseOptions::default()).output;
+        let other = parse_with(input, &RouteParseOptions {
+            rng_seed: 7,
+            ..RouteParseOptions::default()
+        })
+        .output;
+        assert_eq!(first, same);
+        assert_ne!(first, other);
     }
 }
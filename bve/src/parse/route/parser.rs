@@ -1,6 +1,6 @@
 use crate::parse::route::{
     errors::{PreprocessingError, RouteError},
-    TrackPositionSmallVec,
+    RouteParseOptions, TrackPositionSmallVec,
 };
 use bve_common::nom::{separated_list_small, w, MapOutput};
 use itertools::Itertools;
@@ -74,8 +74,12 @@ pub type ArgumentSmallVec = SmallVec<[SmartString<LazyCompact>; 8]>;
 
 pub fn parse_route<'a>(
     preprocessed: &'a str,
+    options: &RouteParseOptions,
     errors: &'a RefCell<Vec<RouteError>>,
 ) -> impl Iterator<Item = Directive> + 'a {
+    let strict = options.strict;
+    // Errors already here came from preprocessing and don't stop parsing
+    let preprocessing_errors = errors.borrow().len();
     split_into_commands(preprocessed)
         .filter_map(move |v| match apply_chr(v) {
             Ok(r) => Some(r),
@@ -92,6 +96,8 @@ pub fn parse_route<'a>(
                 None
             }
         })
+        // In strict mode, nothing after the first parse error is used
+        .take_while(move |_| !strict || errors.borrow().len() == preprocessing_errors)
 }
 
 fn split_into_commands(input: &str) -> impl Iterator<Item = &str> {
@@ -570,4 +576,25 @@ mod test {
             }))
         );
     }
+
+    #[test]
+    fn strict_stops_at_error() {
+        let errors = RefCell::new(Vec::new());
+        let options = RouteParseOptions {
+            strict: true,
+            ..RouteParseOptions::default()
+        };
+        let directives: Vec<_> = parse_route("With Track\n100\n.?\n200", &options, &errors).collect();
+        assert_eq!(directives, vec![
+            Directive::With(ss!("Track")),
+            Directive::TrackPosition(smallvec::smallvec![100.0])
+        ]);
+        assert_eq!(errors.borrow().len(), 1);
+
+        let errors = RefCell::new(Vec::new());
+        let directives: Vec<_> =
+            parse_route("With Track\n100\n.?\n200", &RouteParseOptions::default(), &errors).collect();
+        assert_eq!(directives.len(), 3);
+        assert_eq!(errors.borrow().len(), 1);
+    }
 }
@@ -1,5 +1,10 @@
-use crate::parse::route::errors::{PreprocessingError, RouteError};
+use crate::parse::route::{
+    errors::{PreprocessingError, RouteError},
+    RouteParseOptions,
+};
 use bve_common::nom::w;
+use futures::future::join_all;
+use itertools::Itertools;
 use nom::{
     branch::alt,
     bytes::complete::{is_a, is_not, tag, tag_no_case},
@@ -12,7 +17,7 @@ use once_cell::sync::Lazy;
 use rand::{distributions::WeightedIndex, prelude::*};
 use regex::Regex;
 use smallvec::SmallVec;
-use std::{collections::HashMap, future::Future, pin::Pin};
+use std::{collections::HashMap, future::Future, pin::Pin, rc::Rc};
 
 static INCLUDE_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new(r#"(?i)\$include\s*\([^\n]*"#).expect("invalid regex"));
 static RND_REGEX: Lazy<Regex> =
@@ -26,6 +31,7 @@ static IF_PARSE_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new(r#"(?i)\$if\s*\(\s*
 
 type SubMap = HashMap<u64, String>;
 
+/// Include to resolve into the path of a file.
 pub struct FileInput {
     pub base_path: String,
     pub requested_path: String,
@@ -36,79 +42,214 @@ pub struct FileOutput {
     pub contents: String,
 }
 
+/// How includes were resolved while preprocessing a single route.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub struct IncludeStatistics {
+    /// Number of `$Include` directives that chose a file.
+    pub includes: usize,
+    /// Number of times the file function was called. Each resolved path is only loaded once.
+    pub files_loaded: usize,
+    /// Number of distinct files that were successfully loaded.
+    pub distinct_files: usize,
+    /// Number of `$Include` directives whose file could not be resolved or loaded.
+    pub failures: usize,
+}
+
+impl IncludeStatistics {
+    /// Number of `$Include` directives served by a file that was already loaded.
+    #[must_use]
+    pub const fn cache_hits(&self) -> usize {
+        self.includes - self.failures - self.distinct_files
+    }
+}
+
+/// Files loaded during a single preprocessing run.
+///
+/// Files are keyed on their resolved path, so the same file requested with a different spelling or case is only
+/// loaded once. Resolving is cached on the directory of the including file and the requested path, as those fully
+/// determine which file is resolved.
+#[derive(Default)]
+struct IncludeCache {
+    resolved: HashMap<(String, String), Result<String, PreprocessingError>>,
+    files: HashMap<String, Result<Rc<FileOutput>, PreprocessingError>>,
+    statistics: IncludeStatistics,
+}
+
+impl IncludeCache {
+    fn insert_file(&mut self, path: String, result: Result<FileOutput, PreprocessingError>) {
+        self.statistics.files_loaded += 1;
+        if result.is_ok() {
+            self.statistics.distinct_files += 1;
+        }
+        self.files.insert(path, result.map(Rc::new));
+    }
+
+    /// Resolved paths that haven't been loaded yet.
+    fn unloaded(&self) -> Vec<String> {
+        self.resolved
+            .values()
+            .filter_map(|path| path.as_ref().ok())
+            .filter(|&path| !self.files.contains_key(path))
+            .unique()
+            .cloned()
+            .collect()
+    }
+}
+
 // (pass -> pass2) means pass2 is applied to the result of pass
 // (pass2 <- pass) means pass2 is applied to the skipped input between the last tag and the current
 // pass(pass2) means pass2 is applied to pass's arguments
 //
 // Preprocessing happens as:
 // (include(rnd -> chr) -> include) -> (sub <- if(sub -> rnd)) -> rnd -> chr
-pub async fn preprocess_route<R, FileFn, FileFut>(
+//
+// `resolve_fn` turns an include into the path of a file, then `file_fn` loads the file at that path.
+pub async fn preprocess_route<R, ResolveFn, ResolveFut, FileFn, FileFut>(
     file_path: &str,
     content: &str,
     rng: &mut R,
+    options: &RouteParseOptions,
+    resolve_fn: ResolveFn,
     file_fn: FileFn,
-) -> (String, Vec<RouteError>)
+) -> (String, Vec<RouteError>, IncludeStatistics)
 where
     R: Rng + ?Sized,
-    FileFn: FnMut(FileInput) -> FileFut + Copy,
+    ResolveFn: FnMut(FileInput) -> ResolveFut + Copy,
+    ResolveFut: Future<Output = Result<String, PreprocessingError>>,
+    FileFn: FnMut(String) -> FileFut + Copy,
     FileFut: Future<Output = Result<FileOutput, PreprocessingError>>,
 {
     let mut errors = Vec::new();
-    let content = run_includes(file_path, content, &mut errors, rng, file_fn).await;
+    let mut cache = IncludeCache::default();
+    let content = if options.follow_includes {
+        run_includes(file_path, content, &mut errors, rng, &mut cache, resolve_fn, file_fn).await
+    } else {
+        INCLUDE_REGEX.replace_all(content, "").into_owned()
+    };
     let content = run_if(&content, &mut errors, rng, &mut SubMap::new());
     let content = run_rnd(&content, &mut errors, rng);
     let content = run_chr(&content, &mut errors);
-    (content, errors)
+    (content, errors, cache.statistics)
+}
+
+fn include_directory(file_path: &str) -> String {
+    std::path::Path::new(file_path)
+        .parent()
+        .map(|p| p.to_string_lossy().to_string())
+        .unwrap_or_default()
 }
 
-fn run_includes<'a, R, FileFn, FileFut>(
+fn run_includes<'a, R, ResolveFn, ResolveFut, FileFn, FileFut>(
     file_path: &'a str,
     content: &'a str,
     errors: &'a mut Vec<RouteError>,
     rng: &'a mut R,
-    mut file_fn: FileFn,
+    cache: &'a mut IncludeCache,
+    resolve_fn: ResolveFn,
+    file_fn: FileFn,
 ) -> Pin<Box<dyn Future<Output = String> + 'a>>
 where
     R: Rng + ?Sized,
-    FileFn: FnMut(FileInput) -> FileFut + Copy + 'a,
+    ResolveFn: FnMut(FileInput) -> ResolveFut + Copy + 'a,
+    ResolveFut: Future<Output = Result<String, PreprocessingError>>,
+    FileFn: FnMut(String) -> FileFut + Copy + 'a,
     FileFut: Future<Output = Result<FileOutput, PreprocessingError>>,
 {
     Box::pin(async move {
+        let directory = include_directory(file_path);
+
+        // Includes with only one possible file don't touch the rng, so they can all be loaded up front
+        let known: Vec<String> = INCLUDE_REGEX
+            .find_iter(content)
+            .map(|mat| mat.as_str())
+            .filter(|&include| !RND_REGEX.is_match(include) && !CHR_REGEX.is_match(include))
+            .filter_map(|include| match parse_include(include) {
+                Ok(parsed) if parsed.len() == 1 => Some(parsed[0].file.to_owned()),
+                _ => None,
+            })
+            .filter(|requested| !cache.resolved.contains_key(&(directory.clone(), requested.clone())))
+            .unique()
+            .collect();
+        let resolved = join_all(known.into_iter().map(|requested| {
+            let mut resolve_fn = resolve_fn;
+            let path = resolve_fn(FileInput {
+                base_path: file_path.to_owned(),
+                requested_path: requested.clone(),
+            });
+            async move { (requested, path.await) }
+        }))
+        .await;
+        for (requested, path) in resolved {
+            cache.resolved.insert((directory.clone(), requested), path);
+        }
+        let loaded = join_all(cache.unloaded().into_iter().map(|path| {
+            let mut file_fn = file_fn;
+            let file = file_fn(path.clone());
+            async move { (path, file.await) }
+        }))
+        .await;
+        for (path, result) in loaded {
+            cache.insert_file(path, result);
+        }
+
+        // Choices are made in source order, depth first, so `$Rnd` consumes the rng the same way however files load.
         // Content will likely get much bigger
         let mut output = String::with_capacity(content.len() * 2);
         let mut last_match = 0_usize;
         for mat in INCLUDE_REGEX.find_iter(content) {
             output.push_str(&content[last_match..mat.start()]);
+            last_match = mat.end();
+
             let include = &content[mat.range()];
             let include = run_rnd(include, errors, rng);
             let include = run_chr(&include, errors);
-            let chosen_opt: Result<(Include<'_>, FileOutput), PreprocessingError> = try {
+            let chosen: Result<Include<'_>, PreprocessingError> = try {
                 let parsed = parse_include(&include)?;
-                let chosen = choose_include(&parsed, rng)?;
-                (
-                    chosen,
-                    file_fn(FileInput {
-                        base_path: file_path.to_owned(),
-                        requested_path: chosen.file.to_owned(),
-                    })
-                    .await?,
-                )
+                choose_include(&parsed, rng)?
             };
-            let (chosen, content) = match chosen_opt {
-                Ok(c) => c,
+            let chosen = match chosen {
+                Ok(chosen) => chosen,
                 Err(error) => {
                     errors.push(error.into());
-                    last_match = mat.end();
                     continue;
                 }
             };
 
-            let recursive_processed = run_includes(&content.path, &content.contents, errors, rng, file_fn).await;
+            cache.statistics.includes += 1;
+            let key = (directory.clone(), chosen.file.to_owned());
+            if !cache.resolved.contains_key(&key) {
+                let mut resolve_fn = resolve_fn;
+                let path = resolve_fn(FileInput {
+                    base_path: file_path.to_owned(),
+                    requested_path: chosen.file.to_owned(),
+                })
+                .await;
+                cache.resolved.insert(key.clone(), path);
+            }
+            let file: Result<Rc<FileOutput>, PreprocessingError> = try {
+                let path = cache.resolved[&key].clone()?;
+                if !cache.files.contains_key(&path) {
+                    let mut file_fn = file_fn;
+                    let result = file_fn(path.clone()).await;
+                    cache.insert_file(path.clone(), result);
+                }
+                cache.files[&path].clone()?
+            };
+            let file = match file {
+                Ok(file) => file,
+                Err(error) => {
+                    cache.statistics.failures += 1;
+                    errors.push(error.into());
+                    continue;
+                }
+            };
+
+            let recursive_processed =
+                run_includes(&file.path, &file.contents, errors, rng, cache, resolve_fn, file_fn).await;
 
             output.push_str(&format!("\n%O{}%\n", chosen.offset));
             output.push_str(&recursive_processed);
             output.push_str(&format!("\n%O-{}%\n", chosen.offset));
-            last_match = mat.end();
         }
         output.push_str(&content[last_match..]);
 
@@ -412,18 +553,33 @@ mod test {
         rand::rngs::StdRng::seed_from_u64(42)
     }
 
-    type NewFileFnFut = impl Future<Output = Result<FileOutput, PreprocessingError>>;
-    fn new_file_fn(file_database: HashMap<String, String>) -> impl Fn(FileInput) -> NewFileFnFut {
+    type NewResolveFnFut = impl Future<Output = Result<String, PreprocessingError>>;
+    /// Resolves requests to the files in `file_database` ignoring case and a leading `./`, like the filesystem would.
+    fn new_resolve_fn(file_database: &HashMap<String, String>) -> impl Fn(FileInput) -> NewResolveFnFut {
+        let files: Vec<String> = file_database.keys().cloned().collect();
         move |file_input| {
             let requested = file_input.requested_path;
             let requested_smart: SmartString<LazyCompact> = requested[..].into();
+            let path_opt = files
+                .iter()
+                .find(|file| file.eq_ignore_ascii_case(requested.trim_start_matches("./")))
+                .cloned()
+                .ok_or_else(move || PreprocessingError::IncludeFileNotFound { file: requested_smart });
+            async move { path_opt }
+        }
+    }
+
+    type NewFileFnFut = impl Future<Output = Result<FileOutput, PreprocessingError>>;
+    fn new_file_fn(file_database: HashMap<String, String>) -> impl Fn(String) -> NewFileFnFut {
+        move |path| {
+            let path_smart: SmartString<LazyCompact> = path[..].into();
             let output_opt = file_database
-                .get(&requested)
+                .get(&path)
                 .map(String::clone)
-                .ok_or_else(move || PreprocessingError::IncludeFileNotFound { file: requested_smart });
+                .ok_or_else(move || PreprocessingError::IncludeFileNotFound { file: path_smart });
             async move {
                 Ok(FileOutput {
-                    path: requested,
+                    path,
                     contents: output_opt?,
                 })
             }
@@ -785,6 +941,7 @@ mod test {
         };
 
         let mut errors = Vec::new();
+        let resolve_fn = new_resolve_fn(&file_database);
         let file_fn = new_file_fn(file_database);
         let mut rng = new_rng();
 
@@ -794,7 +951,16 @@ mod test {
         "
         );
 
-        let processed: String = run_includes("", input, &mut errors, &mut rng, &file_fn).await;
+        let processed: String = run_includes(
+            "",
+            input,
+            &mut errors,
+            &mut rng,
+            &mut IncludeCache::default(),
+            &resolve_fn,
+            &file_fn,
+        )
+        .await;
         assert!(
             processed.contains("contents1"),
             "output missing contents: {}",
@@ -814,7 +980,16 @@ mod test {
         "
         );
 
-        let processed: String = run_includes("", input, &mut errors, &mut rng, &file_fn).await;
+        let processed: String = run_includes(
+            "",
+            input,
+            &mut errors,
+            &mut rng,
+            &mut IncludeCache::default(),
+            &resolve_fn,
+            &file_fn,
+        )
+        .await;
         assert!(
             processed.contains("contents1"),
             "output missing contents: {}",
@@ -835,6 +1010,7 @@ mod test {
         };
 
         let mut errors = Vec::new();
+        let resolve_fn = new_resolve_fn(&file_database);
         let file_fn = new_file_fn(file_database);
         let mut rng = new_rng();
 
@@ -844,7 +1020,16 @@ mod test {
         "
         );
 
-        let processed: String = run_includes("", input, &mut errors, &mut rng, &file_fn).await;
+        let processed: String = run_includes(
+            "",
+            input,
+            &mut errors,
+            &mut rng,
+            &mut IncludeCache::default(),
+            &resolve_fn,
+            &file_fn,
+        )
+        .await;
         assert!(
             processed.contains("contents1"),
             "output missing contents: {}",
@@ -872,6 +1057,7 @@ mod test {
         };
 
         let mut errors = Vec::new();
+        let resolve_fn = new_resolve_fn(&file_database);
         let file_fn = new_file_fn(file_database);
         let mut rng = new_rng();
 
@@ -886,7 +1072,16 @@ mod test {
         "
         );
 
-        let processed: String = run_includes("", positive_input, &mut errors, &mut rng, &file_fn).await;
+        let processed: String = run_includes(
+            "",
+            positive_input,
+            &mut errors,
+            &mut rng,
+            &mut IncludeCache::default(),
+            &resolve_fn,
+            &file_fn,
+        )
+        .await;
         assert!(
             processed.contains("contents1"),
             "output missing contents: {}",
@@ -899,7 +1094,16 @@ mod test {
         );
         assert!(errors.is_empty(), "{:?}", errors);
 
-        let processed: String = run_includes("", negative_input, &mut errors, &mut rng, &file_fn).await;
+        let processed: String = run_includes(
+            "",
+            negative_input,
+            &mut errors,
+            &mut rng,
+            &mut IncludeCache::default(),
+            &resolve_fn,
+            &file_fn,
+        )
+        .await;
         assert!(
             processed.contains("contents2"),
             "output missing contents: {}",
@@ -921,6 +1125,7 @@ mod test {
         };
 
         let mut errors = Vec::new();
+        let resolve_fn = new_resolve_fn(&file_database);
         let file_fn = new_file_fn(file_database);
         let mut rng = new_rng();
 
@@ -930,7 +1135,16 @@ mod test {
         "
         );
 
-        let processed: String = run_includes("", input, &mut errors, &mut rng, &file_fn).await;
+        let processed: String = run_includes(
+            "",
+            input,
+            &mut errors,
+            &mut rng,
+            &mut IncludeCache::default(),
+            &resolve_fn,
+            &file_fn,
+        )
+        .await;
         assert!(
             processed.contains("contents1"),
             "output missing contents: {}",
@@ -955,6 +1169,7 @@ mod test {
             String::from("file1") => String::from("$sub(0) = contents1"),
         };
 
+        let resolve_fn = new_resolve_fn(&file_database);
         let file_fn = new_file_fn(file_database);
         let mut rng = new_rng();
 
@@ -965,7 +1180,15 @@ mod test {
         "
         );
 
-        let (processed, errors) = preprocess_route("", input, &mut rng, &file_fn).await;
+        let (processed, errors, _) = preprocess_route(
+            "",
+            input,
+            &mut rng,
+            &RouteParseOptions::default(),
+            &resolve_fn,
+            &file_fn,
+        )
+        .await;
         assert!(
             processed.contains("contents1"),
             "output missing contents: {}",
@@ -978,4 +1201,150 @@ mod test {
         );
         assert!(errors.is_empty(), "{:?}", errors);
     }
+
+    #[async_std::test]
+    async fn include_cached() {
+        let file_database = maplit::hashmap! {
+            String::from("file1") => String::from("$include(file2)\ncontents1"),
+            String::from("file2") => String::from("contents2"),
+        };
+
+        let resolve_fn = new_resolve_fn(&file_database);
+        let file_fn = new_file_fn(file_database);
+        let calls = Rc::new(std::cell::Cell::new(0_usize));
+        let counted_file_fn = |path: String| {
+            calls.set(calls.get() + 1);
+            file_fn(path)
+        };
+        let mut rng = new_rng();
+
+        let input: &str = indoc::indoc!(
+            r"
+            $include(file1)
+            $include(file1)
+            $include(file2)
+            $include(./FILE2)
+            $include(missing)
+        "
+        );
+
+        let (processed, errors, statistics) = preprocess_route(
+            "",
+            input,
+            &mut rng,
+            &RouteParseOptions::default(),
+            &resolve_fn,
+            &counted_file_fn,
+        )
+        .await;
+        assert_eq!(processed.matches("contents1").count(), 2);
+        assert_eq!(processed.matches("contents2").count(), 4);
+        assert_eq!(errors.len(), 1, "{:?}", errors);
+        // Both spellings of file2 resolve to the same file, which is only loaded once
+        assert_eq!(calls.get(), 2);
+        assert_eq!(statistics, IncludeStatistics {
+            includes: 7,
+            files_loaded: 2,
+            distinct_files: 2,
+            failures: 1,
+        });
+        assert_eq!(statistics.cache_hits(), 4);
+    }
+
+    #[async_std::test]
+    async fn include_rng_order() {
+        let file_database = maplit::hashmap! {
+            String::from("file1") => String::from("$include(a;1;b;1)"),
+            String::from("a") => String::from("contentsa"),
+            String::from("b") => String::from("contentsb"),
+            String::from("c") => String::from("contentsc"),
+            String::from("d") => String::from("contentsd"),
+        };
+
+        let resolve_fn = new_resolve_fn(&file_database);
+        let file_fn = new_file_fn(file_database);
+        let mut rng = new_rng();
+
+        let input: &str = indoc::indoc!(
+            r"
+            $include(file1)
+            $include(c;1;d;1)
+            $rnd(1;1000)
+        "
+        );
+
+        // The nested include is chosen before the second top level include.
+        let mut expected_rng = new_rng();
+        let mut expected_errors = Vec::new();
+        let inner = parse_include("$include(a;1;b;1)").expect("valid include");
+        let inner = format!(
+            "contents{}",
+            choose_include(&inner, &mut expected_rng).expect("valid weights").file
+        );
+        let outer = parse_include("$include(c;1;d;1)").expect("valid include");
+        let outer = format!(
+            "contents{}",
+            choose_include(&outer, &mut expected_rng).expect("valid weights").file
+        );
+        let rnd = run_rnd("$rnd(1;1000)", &mut expected_errors, &mut expected_rng);
+
+        let (processed, errors, _) = preprocess_route(
+            "",
+            input,
+            &mut rng,
+            &RouteParseOptions::default(),
+            &resolve_fn,
+            &file_fn,
+        )
+        .await;
+        let lines: Vec<&str> = processed
+            .lines()
+            .map(str::trim)
+            .filter(|l| !l.is_empty() && !l.starts_with("%O"))
+            .collect();
+        assert_eq!(lines, [&inner[..], &outer[..], &rnd[..]]);
+        assert!(errors.is_empty(), "{:?}", errors);
+    }
+
+    #[async_std::test]
+    async fn include_not_followed() {
+        let file_database = maplit::hashmap! {
+            String::from("file1") => String::from("contents1"),
+        };
+
+        let resolve_fn = new_resolve_fn(&file_database);
+        let file_fn = new_file_fn(file_database);
+        let mut rng = new_rng();
+        let options = RouteParseOptions {
+            follow_includes: false,
+            ..RouteParseOptions::default()
+        };
+
+        let input: &str = indoc::indoc!(
+            r"
+            $include(file1)
+            contents2
+        "
+        );
+
+        let (processed, errors, statistics) =
+            preprocess_route("", input, &mut rng, &options, &resolve_fn, &file_fn).await;
+        assert!(
+            !processed.contains("contents1"),
+            "output contains included file: {}",
+            processed
+        );
+        assert!(
+            processed.contains("contents2"),
+            "output missing contents: {}",
+            processed
+        );
+        assert!(
+            !PREPROCESSING_VALIDATION.is_match(&processed),
+            "contains preprocessing directives: {}",
+            processed
+        );
+        assert!(errors.is_empty(), "{:?}", errors);
+        assert_eq!(statistics, IncludeStatistics::default());
+    }
 }
@@ -1,6 +1,9 @@
 use crate::{
     filesystem::resolve_path,
-    load::mesh::{load_mesh_from_file, LoadedStaticMesh, Vertex},
+    load::mesh::{
+        load_mesh_from_file, load_mesh_from_file_cached, placeholder_mesh, DiskMeshCache, LoadError, LoadedStaticMesh,
+        Vertex,
+    },
     runtime::{
         cache::{Cache, PathHandle, PathSet},
         client::Client,
@@ -40,12 +43,20 @@ impl<C: Client> Clone for MeshData<C> {
 }
 
 pub struct MeshCache<C: Client> {
-    inner: Cache<MeshData<C>>,
+    inner: Cache<Result<MeshData<C>, LoadError>>,
+    /// Shared by every mesh that failed to load. Never removed.
+    placeholder: AsyncMutex<Option<MeshData<C>>>,
+    /// Checked for already loaded meshes before loading them from the source files.
+    disk_cache: Option<DiskMeshCache>,
 }
 
 impl<C: Client> MeshCache<C> {
-    pub fn new() -> Self {
-        Self { inner: Cache::new() }
+    pub fn new(disk_cache: Option<DiskMeshCache>) -> Self {
+        Self {
+            inner: Cache::new(),
+            placeholder: AsyncMutex::new(None, false),
+            disk_cache,
+        }
     }
 
     fn combine_eligible_meshes(mut meta_mesh: LoadedStaticMesh) -> RawMeshData {
@@ -68,58 +79,98 @@ impl<C: Client> MeshCache<C> {
         }
     }
 
-    async fn load_mesh_impl(&self, client: &AsyncMutex<C>, path_set: &PathSet, path: &Path) -> MeshData<C> {
-        trace!("Loading mesh {}", path.display());
-        let meta_mesh = load_mesh_from_file(path)
-            .await
-            .expect("Path invalid, should have been validated earlier");
-
-        let raw_mesh_data = Self::combine_eligible_meshes(meta_mesh);
-
-        let parent_dir = path.parent().expect("File must be in directory");
-
+    async fn add_meshes(client: &AsyncMutex<C>, raw_mesh_data: RawMeshData) -> (MeshData<C>, Vec<String>) {
         let mut mesh_data = MeshData::<C>::new();
         for (vertices, indices, texture_id) in raw_mesh_data.meshes {
             let handle = client.lock().await.add_mesh(vertices, &indices);
             mesh_data.handles.push((handle, texture_id))
         }
+        (mesh_data, raw_mesh_data.textures)
+    }
+
+    async fn load_mesh_impl(
+        &self,
+        client: &AsyncMutex<C>,
+        path_set: &PathSet,
+        path: &Path,
+    ) -> Result<MeshData<C>, LoadError> {
+        trace!("Loading mesh {}", path.display());
+        let meta_mesh = match &self.disk_cache {
+            Some(disk_cache) => load_mesh_from_file_cached(path, disk_cache).await?,
+            None => load_mesh_from_file(path).await?,
+        };
+
+        let raw_mesh_data = Self::combine_eligible_meshes(meta_mesh);
 
-        for texture in raw_mesh_data.textures {
+        let parent_dir = path.parent().unwrap_or_else(|| Path::new("."));
+
+        let (mut mesh_data, textures) = Self::add_meshes(client, raw_mesh_data).await;
+
+        for texture in textures {
+            // Textures that can't be found fail to load in the texture cache and get the placeholder, like any other
+            // broken texture
+            let joined = parent_dir.join(&texture);
             let path = resolve_path(parent_dir, PathBuf::from(&texture))
                 .await
-                .unwrap_or_else(|| panic!("Could not find texture {}", texture));
+                .unwrap_or(joined);
             mesh_data.textures.push(path_set.insert(path).await);
         }
 
         trace!("Loaded mesh {}", path.display());
 
-        mesh_data
+        Ok(mesh_data)
     }
 
-    pub async fn load_mesh(&self, client: &AsyncMutex<C>, path_set: &PathSet, path: PathBuf) -> Option<MeshData<C>> {
-        let canonicalized = path.canonicalize().await.ok()?;
+    /// Loads a mesh, or gets it from the cache.
+    ///
+    /// # Errors
+    ///
+    /// Returns Err if the mesh could not be loaded. The error is cached, so the file won't be retried.
+    pub async fn load_mesh(
+        &self,
+        client: &AsyncMutex<C>,
+        path_set: &PathSet,
+        path: PathBuf,
+    ) -> Result<MeshData<C>, LoadError> {
+        let canonicalized = path.canonicalize().await.map_err(|err| LoadError::from_io(path, err))?;
         let path_handle = path_set.insert(canonicalized.clone()).await;
 
         trace!("Checking if mesh {} is loaded", path_handle.0);
-        let mesh = self
-            .inner
+        self.inner
             .get_or_insert(path_handle, async {
                 self.load_mesh_impl(client, path_set, &canonicalized).await
             })
-            .await;
-        Some(mesh)
+            .await
+    }
+
+    /// Mesh data to use in place of a mesh that failed to load. Has no textures.
+    pub async fn load_placeholder(&self, client: &AsyncMutex<C>) -> MeshData<C> {
+        let mut placeholder = self.placeholder.lock().await;
+        if let Some(data) = &*placeholder {
+            return data.clone();
+        }
+
+        trace!("Loading placeholder mesh");
+        let raw_mesh_data = Self::combine_eligible_meshes(placeholder_mesh());
+        let (data, _) = Self::add_meshes(client, raw_mesh_data).await;
+        *placeholder = Some(data.clone());
+        data
     }
 
     pub async fn remove_mesh(&self, client: &AsyncMutex<C>, path_handle: PathHandle) -> Option<Vec<PathHandle>> {
         trace!("Checking mesh {}", path_handle.0);
         self.inner
-            .remove(path_handle, async move |data| {
-                trace!("Removing mesh {}", path_handle.0);
-                let mut client_lock = client.lock().await;
-                for (handle, _) in data.handles {
-                    client_lock.remove_mesh(&handle);
+            .remove(path_handle, async move |data| match data {
+                Ok(data) => {
+                    trace!("Removing mesh {}", path_handle.0);
+                    let mut client_lock = client.lock().await;
+                    for (handle, _) in data.handles {
+                        client_lock.remove_mesh(&handle);
+                    }
+                    data.textures
                 }
-                data.textures
+                // Failed meshes share the placeholder, which stays loaded
+                Err(_) => Vec::new(),
             })
             .await
     }
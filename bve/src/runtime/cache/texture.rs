@@ -1,5 +1,9 @@
 use crate::{
     filesystem::resolve_path,
+    load::{
+        mesh::{LoadError, LoadErrorKind},
+        texture::{decode_texture, placeholder_texture},
+    },
     runtime::{
         cache::{Cache, PathHandle, PathSet},
         client::Client,
@@ -10,54 +14,71 @@ use async_std::{
     fs::read,
     path::{Path, PathBuf},
 };
-use image::guess_format;
 use log::trace;
-use std::io::Cursor;
 
 pub struct TextureCache<C: Client> {
-    inner: Cache<C::TextureHandle>,
+    inner: Cache<Result<C::TextureHandle, LoadError>>,
+    /// Shared by every texture that failed to load. Never removed.
+    placeholder: AsyncMutex<Option<C::TextureHandle>>,
 }
 
 impl<C: Client> TextureCache<C> {
     pub fn new() -> Self {
-        Self { inner: Cache::new() }
+        Self {
+            inner: Cache::new(),
+            placeholder: AsyncMutex::new(None, false),
+        }
     }
 
-    async fn load_texture_impl(&self, client: &AsyncMutex<C>, path: &Path) -> C::TextureHandle {
+    async fn load_texture_impl(&self, client: &AsyncMutex<C>, path: &Path) -> Result<C::TextureHandle, LoadError> {
         trace!("Loading texture {}", path.display());
 
-        let data = read(path).await.expect("Could not read file");
+        let data = read(path).await.map_err(|err| LoadError::from_io(path, err))?;
 
-        let format = guess_format(&data).expect("Could not guess format");
-        let image = image::load(Cursor::new(data), format).expect("Could not load image");
-        let rgba = image.into_rgba();
+        let rgba = decode_texture(&data).map_err(|err| LoadError {
+            path: path.into(),
+            kind: LoadErrorKind::Malformed { error: err.to_string() },
+        })?;
 
-        client.lock().await.add_texture(&rgba)
+        Ok(client.lock().await.add_texture(&rgba))
     }
 
+    /// Loads a texture, or gets it from the cache.
+    ///
+    /// # Errors
+    ///
+    /// Returns Err if the texture could not be loaded. The error is cached, so the file won't be retried.
     pub async fn load_texture_handle_path(
         &self,
         client: &AsyncMutex<C>,
         handle: PathHandle,
         path: PathBuf,
-    ) -> Option<C::TextureHandle> {
-        let tex = self
-            .inner
+    ) -> Result<C::TextureHandle, LoadError> {
+        self.inner
             .get_or_insert(handle, async { self.load_texture_impl(client, &path).await })
-            .await;
-        Some(tex)
+            .await
     }
 
+    /// Same as `load_texture_handle_path`, with the path looked up in `path_set`.
+    ///
+    /// # Errors
+    ///
+    /// Returns Err if the texture could not be loaded.
     pub async fn load_texture_handle(
         &self,
         client: &AsyncMutex<C>,
         path_set: &PathSet,
         handle: PathHandle,
-    ) -> Option<C::TextureHandle> {
+    ) -> Result<C::TextureHandle, LoadError> {
         let path = path_set.get(handle);
         self.load_texture_handle_path(client, handle, path).await
     }
 
+    /// Same as `load_texture_handle_path`, with `relative` resolved against `root_dir`.
+    ///
+    /// # Errors
+    ///
+    /// Returns Err if the texture could not be found or loaded.
     #[allow(dead_code)]
     pub async fn load_texture_relative(
         &self,
@@ -65,17 +86,35 @@ impl<C: Client> TextureCache<C> {
         path_set: &PathSet,
         root_dir: PathBuf,
         relative: PathBuf,
-    ) -> Option<C::TextureHandle> {
-        let resolved_path = resolve_path(root_dir, relative).await?;
+    ) -> Result<C::TextureHandle, LoadError> {
+        // Unresolved paths still go through the cache, so the missing file is only looked for once
+        let joined = root_dir.join(&relative);
+        let resolved_path = resolve_path(root_dir, relative).await.unwrap_or(joined);
         let handle = path_set.insert(resolved_path.clone()).await;
         self.load_texture_handle_path(client, handle, resolved_path).await
     }
 
+    /// Texture to use in place of a texture that failed to load.
+    pub async fn load_placeholder(&self, client: &AsyncMutex<C>) -> C::TextureHandle {
+        let mut placeholder = self.placeholder.lock().await;
+        if let Some(handle) = &*placeholder {
+            return handle.clone();
+        }
+
+        trace!("Loading placeholder texture");
+        let handle = client.lock().await.add_texture(&placeholder_texture());
+        *placeholder = Some(handle.clone());
+        handle
+    }
+
     pub async fn remove_texture(&self, client: &AsyncMutex<C>, path_handle: PathHandle) -> Option<()> {
         self.inner
             .remove(path_handle, async move |handle| {
-                let mut client_lock = client.lock().await;
-                client_lock.remove_texture(&handle);
+                // Failed textures share the placeholder, which stays loaded
+                if let Ok(handle) = handle {
+                    let mut client_lock = client.lock().await;
+                    client_lock.remove_texture(&handle);
+                }
             })
             .await
     }
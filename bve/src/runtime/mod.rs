@@ -5,6 +5,7 @@ pub use crate::runtime::{
     location::Location,
 };
 use crate::{
+    load::mesh::DiskMeshCache,
     runtime::{
         cache::{MeshCache, PathHandle, PathSet, TextureCache},
         chunk::{Chunk, ChunkSet, ChunkState, UnloadedObject, CHUNK_SIZE},
@@ -17,7 +18,7 @@ use futures::{
     StreamExt,
 };
 use hecs::World;
-use log::{debug, trace};
+use log::{debug, error, trace};
 use std::sync::{
     atomic::{AtomicI32, Ordering},
     Arc,
@@ -99,11 +100,17 @@ pub struct Runtime<C: Client> {
 impl<C: Client> Runtime<C> {
     #[must_use]
     pub fn new(client: Arc<AsyncMutex<C>>) -> Arc<Self> {
+        Self::with_disk_cache(client, None)
+    }
+
+    /// Meshes are looked up in `disk_cache` before being loaded, and added to it after.
+    #[must_use]
+    pub fn with_disk_cache(client: Arc<AsyncMutex<C>>, disk_cache: Option<DiskMeshCache>) -> Arc<Self> {
         Arc::new(Self {
             client,
             path_set: PathSet::new(),
             chunks: ChunkSet::new(),
-            meshes: MeshCache::new(),
+            meshes: MeshCache::new(disk_cache),
             textures: TextureCache::new(),
             location: AsyncMutex::new(
                 RuntimeLocation {
@@ -145,11 +152,13 @@ impl<C: Client> Runtime<C> {
     }
 
     async fn load_mesh_textures(self: &Arc<Self>, path: PathBuf) -> Vec<(C::MeshHandle, C::TextureHandle)> {
-        let mesh = self
-            .meshes
-            .load_mesh(&self.client, &self.path_set, path)
-            .await
-            .expect("Could not load mesh");
+        let mesh = match self.meshes.load_mesh(&self.client, &self.path_set, path).await {
+            Ok(mesh) => mesh,
+            Err(err) => {
+                error!("{}", err);
+                self.meshes.load_placeholder(&self.client).await
+            }
+        };
 
         let mut texture_futures = FuturesOrdered::new();
         for texture_path_handle in mesh.textures {
@@ -157,8 +166,15 @@ impl<C: Client> Runtime<C> {
         }
 
         let mut texture_handles = Vec::with_capacity(texture_futures.len());
-        while let Some(option_texture_handle) = texture_futures.next().await {
-            texture_handles.push(option_texture_handle.expect("TODO: Deal with unfound textures"));
+        while let Some(texture_result) = texture_futures.next().await {
+            let texture_handle = match texture_result {
+                Ok(handle) => handle,
+                Err(err) => {
+                    error!("{}", err);
+                    self.textures.load_placeholder(&self.client).await
+                }
+            };
+            texture_handles.push(texture_handle);
         }
 
         let mut combined_handles = Vec::with_capacity(mesh.handles.len());